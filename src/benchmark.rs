@@ -0,0 +1,105 @@
+use crate::ntru::{SilentObserver, gen_m, gen_polynomial, ntru_encrypt, ntru_gen_keys};
+use crate::polyxnm1::{Integer, PolyXNm1, Reduction, RingParams, UInteger, zp::*};
+use crate::polyxnm1::convolution::{CONVOLUTION_METHODS, ConvolutionMethod, ring_convolution_with};
+use crate::polyxnm1::inverse::find_inv_polynomial;
+use rand::Rng;
+use rust_xlsxwriter::{Format, Worksheet, XlsxError};
+use std::time::{Duration, Instant};
+
+/// Lengths around the thresholds of `ConvolutionMethod::Auto` and the N of the standard parameter sets.
+const CONVOLUTION_LENGTHS: [usize; 14] = [32, 48, 64, 96, 128, 192, 256, 384, 509, 701, 821, 1024, 1536, 2048];
+const CONVOLUTION_MODULE: UInteger = 2048;
+
+/// N of the IEEE 1363.1 and NIST sets with q = 2048, f and r of weight about 2N/3.
+const MODULUS_LENGTHS: [UInteger; 5] = [401, 449, 509, 677, 1087];
+const MODULUS_Q: UInteger = 2048;
+//=======================================================================================================================
+/// Both tables of the `bench` mode below each other, each averaged over `count_ex` runs.
+pub fn write_benchmarks (worksheet: &mut Worksheet, bold_format: &Format, count_ex: usize, rng: &mut impl Rng) -> Result<(), XlsxError> {
+    write_convolution_benchmark(worksheet, bold_format, 1, count_ex, rng)?;
+    write_modulus_benchmark(worksheet, bold_format, CONVOLUTION_LENGTHS.len() as u32 + 3, count_ex, rng)
+}
+//=======================================================================================================================
+/// Mean time of every top-level multiplication method for random operands mod 2048 in Z[x]/(x^N-1), one row per N.
+fn write_convolution_benchmark (worksheet: &mut Worksheet, bold_format: &Format, first_row: u32, count_ex: usize, rng: &mut impl Rng) -> Result<(), XlsxError> {
+    worksheet.write_with_format(first_row, 0, "N", bold_format)?;
+    for (col, method) in CONVOLUTION_METHODS.iter().enumerate() {
+        worksheet.write_with_format(first_row, col as u16 + 1, format!("{:?}, s", method), bold_format)?;
+    }
+
+    for (row, &n) in CONVOLUTION_LENGTHS.iter().enumerate() {
+        let row = first_row + row as u32 + 1;
+        worksheet.write(row, 0, n as u32)?;
+        let operands: Vec<(Vec<Integer>, Vec<Integer>)> = (0..count_ex).map(|_| (random(n, rng), random(n, rng))).collect();
+
+        let mut line = format!("N = {:>4}:", n);
+        for (col, &method) in CONVOLUTION_METHODS.iter().enumerate() {
+            let mean = mean_time(&operands, method, n);
+            worksheet.write(row, col as u16 + 1, mean.as_secs_f64())?;
+            line += &format!(" {:?} {:.3?}", method, mean);
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
+//=======================================================================================================================
+/// Mean time of `ntru_encrypt` and of the inversion of f mod q with the generic `ModQ` and the power-of-two `ModQPow2`
+/// (q = 2048), one row per N.
+fn write_modulus_benchmark (worksheet: &mut Worksheet, bold_format: &Format, first_row: u32, count_ex: usize, rng: &mut impl Rng) -> Result<(), XlsxError> {
+    let header = ["N", "ntru_encrypt ModQ, s", "ntru_encrypt ModQPow2, s", "inversion ModQ, s", "inversion ModQPow2, s"];
+    for (col, title) in header.iter().enumerate() {
+        worksheet.write_with_format(first_row, col as u16, *title, bold_format)?;
+    }
+
+    for (row, &n) in MODULUS_LENGTHS.iter().enumerate() {
+        let row = first_row + row as u32 + 1;
+        let params = RingParams::new(n, 3, MODULUS_Q);
+        let weight = (n / 3) as u16;
+        let encrypt = [encrypt_time::<ModQ>(&params, weight, count_ex, rng), encrypt_time::<ModQPow2>(&params, weight, count_ex, rng)];
+        let inversion = [inversion_time::<ModQ>(&params, weight, count_ex, rng), inversion_time::<ModQPow2>(&params, weight, count_ex, rng)];
+
+        worksheet.write(row, 0, n as f64)?;
+        for (col, time) in encrypt.iter().chain(&inversion).enumerate() {
+            worksheet.write(row, col as u16 + 1, time.as_secs_f64())?;
+        }
+        println!("N = {:>4}: ntru_encrypt ModQ {:.3?} ModQPow2 {:.3?}, inversion ModQ {:.3?} ModQPow2 {:.3?}",
+            n, encrypt[0], encrypt[1], inversion[0], inversion[1]);
+    }
+    Ok(())
+}
+//=======================================================================================================================
+fn encrypt_time <Q: Module> (params: &RingParams, weight: u16, count_ex: usize, rng: &mut impl Rng) -> Duration {
+    let (public_key, _) = ntru_gen_keys::<Q, Integer>(params, weight, weight, rng, &mut SilentObserver);
+    let messages: Vec<_> = (0..count_ex).map(|_| gen_m(params, rng)).collect();
+
+    let start = Instant::now();
+    for m in &messages {
+        std::hint::black_box(ntru_encrypt(weight, &public_key, m, rng, &mut SilentObserver));
+    }
+    start.elapsed() / count_ex.max(1) as u32
+}
+//=======================================================================================================================
+fn inversion_time <Q: Module> (params: &RingParams, weight: u16, count_ex: usize, rng: &mut impl Rng) -> Duration {
+    let keys: Vec<PolyXNm1<Q>> = (0..count_ex)
+        .map(|_| PolyXNm1::from_integers(params, &gen_polynomial(params, weight, weight - 1, rng)))
+        .collect();
+
+    let start = Instant::now();
+    for f in &keys {
+        let _ = std::hint::black_box(find_inv_polynomial(f));
+    }
+    start.elapsed() / count_ex.max(1) as u32
+}
+//=======================================================================================================================
+fn mean_time (operands: &[(Vec<Integer>, Vec<Integer>)], method: ConvolutionMethod, n: usize) -> Duration {
+    let start = Instant::now();
+    for (a, b) in operands {
+        std::hint::black_box(ring_convolution_with(method, a, b, n, Reduction::Cyclic, CONVOLUTION_MODULE));
+    }
+    start.elapsed() / operands.len().max(1) as u32
+}
+//=======================================================================================================================
+fn random (n: usize, rng: &mut impl Rng) -> Vec<Integer> {
+    (0..n).map(|_| rng.random_range(0..CONVOLUTION_MODULE as Integer)).collect()
+}
+//=======================================================================================================================
@@ -0,0 +1,220 @@
+use crate::ntru_kem::NtruKemParams;
+use crate::polyxnm1::{InversionMethod, Reduction};
+use crate::polyxnm1::integer::IntegerType;
+use crate::presets::NtruParams;
+use crate::serialization::FileFormat;
+//=======================================================================================================================
+const USAGE: &str = "usage: cargo run -- [MODE] [PRESET] [cyclic | negacyclic] [divsteps] [integer i32 | i64 | i128 | bigint] [naep] [seed <u64>] [save <prefix> | save-text <prefix>]
+modes: ntru-prime, failure-rate, ntru-sign, cca, reaction, mitm, hybrid, bench, load <prefix>,
+       hps2048509 | hps2048677 | hps4096821 | hrss701 [kat <file>]";
+//=======================================================================================================================
+/// Experiment chosen on the command line; the default is the NTRUEncrypt round trip and its lattice attacks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Ntru,
+    /// `ntru-prime`: Streamlined NTRU Prime instead of the classic NTRU.
+    NtruPrime,
+    /// `hps2048509` (or another NIST round 3 parameter set name): the NTRU KEM.
+    NtruKem(NtruKemParams),
+    /// `load <prefix>`: attacks an instance saved with `save <prefix>` instead of generating one.
+    Load(String),
+    /// `failure-rate`: estimates the decryption failure probability of a parameter set.
+    FailureRate,
+    /// `ntru-sign`: signs with NTRUSign and recovers its key from a signature transcript.
+    NtruSign,
+    /// `cca`: recovers the private key of unpadded NTRUEncrypt from a decryption oracle.
+    Cca,
+    /// `reaction`: recovers the private key from the decryption failures of chosen ciphertexts.
+    Reaction,
+    /// `mitm`: Odlyzko's meet-in-the-middle search; with time-measurement it is timed next to BKZ.
+    Mitm,
+    /// `hybrid`: the hybrid lattice-reduction / meet-in-the-middle attack for a range of split points.
+    Hybrid,
+    /// `bench` (time-measurement only): times the multiplication methods of `ConvolutionMethod` and `ModQ` against
+    /// `ModQPow2`.
+    Bench
+}
+//=======================================================================================================================
+/// Parsed command line. Words that take a value (`seed`, `integer`, `save`, `save-text`, `load`, `kat`) consume the next
+/// argument, so a value is never read as a mode: `save cca` saves to the prefix "cca".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Args {
+    pub mode: Mode,
+    /// `ntru-107` (or another name of `NTRU_PRESETS`): classic NTRU parameters instead of reading them from stdin.
+    pub preset: Option<NtruParams>,
+    /// `negacyclic`: classic NTRU over Z[x]/(x^N+1) instead of the default `cyclic` Z[x]/(x^N-1).
+    pub reduction: Reduction,
+    /// `divsteps`: keys are inverted by the constant-time divsteps instead of the extended Euclidean algorithm.
+    pub inversion: InversionMethod,
+    /// `integer bigint` (or `i32`, `i64`, `i128`): coefficient type of the NTRUEncrypt rings instead of `Integer`.
+    pub integer: IntegerType,
+    /// `kat <file>`: checks the KEM against a NIST known-answer file.
+    pub kat_file: Option<String>,
+    /// `naep`: NAEP padding instead of raw NTRUEncrypt.
+    pub naep: bool,
+    /// `seed 42` repeats the run with seed 42; otherwise a fresh seed is drawn.
+    pub seed: Option<u64>,
+    /// `save <prefix>` (binary) or `save-text <prefix>` stores the generated instance.
+    pub save: Option<(String, FileFormat)>
+}
+//=======================================================================================================================
+impl Args {
+    /// Arguments of the process; prints the error and the usage and exits if they cannot be parsed.
+    pub fn from_env () -> Args {
+        Args::parse(std::env::args().skip(1)).unwrap_or_else(|error| {
+            eprintln!("{}\n{}", error, USAGE);
+            std::process::exit(2);
+        })
+    }
+
+    pub fn parse (args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+        let mut parsed = Args { mode: Mode::Ntru, preset: None, reduction: Reduction::Cyclic, inversion: InversionMethod::Euclid, integer: IntegerType::default(), kat_file: None, naep: false, seed: None, save: None };
+        let mut reduction_set = false;
+        let mut integer_set = false;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("`{}` needs a value", arg));
+            let mode = match arg.as_str() {
+                "ntru-prime" => Mode::NtruPrime,
+                "failure-rate" => Mode::FailureRate,
+                "ntru-sign" => Mode::NtruSign,
+                "cca" => Mode::Cca,
+                "reaction" => Mode::Reaction,
+                "mitm" => Mode::Mitm,
+                "hybrid" => Mode::Hybrid,
+                "bench" => Mode::Bench,
+                "load" => Mode::Load(value()?),
+                "kat" => {
+                    parsed.kat_file = Some(value()?);
+                    continue;
+                }
+                "cyclic" | "negacyclic" => {
+                    if reduction_set {
+                        return Err("more than one reduction polynomial".to_string());
+                    }
+                    reduction_set = true;
+                    parsed.reduction = if arg == "cyclic" { Reduction::Cyclic } else { Reduction::Negacyclic };
+                    continue;
+                }
+                "divsteps" => {
+                    parsed.inversion = InversionMethod::Divsteps;
+                    continue;
+                }
+                "integer" => {
+                    let name = value()?;
+                    parsed.integer = IntegerType::from_name(&name).ok_or_else(|| format!("unknown ring integer `{}`", name))?;
+                    integer_set = true;
+                    continue;
+                }
+                "naep" => {
+                    parsed.naep = true;
+                    continue;
+                }
+                "seed" => {
+                    let seed = value()?;
+                    parsed.seed = Some(seed.parse().map_err(|_| format!("seed `{}` is not an unsigned 64-bit integer", seed))?);
+                    continue;
+                }
+                "save" | "save-text" => {
+                    let format = if arg == "save" { FileFormat::Binary } else { FileFormat::Text };
+                    parsed.save = Some((value()?, format));
+                    continue;
+                }
+                name => {
+                    if let Some(preset) = NtruParams::from_name(name) {
+                        if parsed.preset.replace(preset).is_some() {
+                            return Err("more than one parameter preset".to_string());
+                        }
+                        continue;
+                    }
+                    Mode::NtruKem(NtruKemParams::from_name(name).ok_or_else(|| format!("unknown argument `{}`", name))?)
+                }
+            };
+            if parsed.mode != Mode::Ntru {
+                return Err(format!("`{}` selects a second mode", arg));
+            }
+            parsed.mode = mode;
+        }
+
+        if parsed.kat_file.is_some() && !matches!(parsed.mode, Mode::NtruKem(_)) {
+            return Err("`kat` needs an NTRU KEM parameter set".to_string());
+        }
+        if integer_set && !matches!(parsed.mode, Mode::Ntru) {
+            return Err("`integer` only applies to NTRUEncrypt".to_string());
+        }
+        if let Mode::NtruKem(kem_params) = &mut parsed.mode {
+            *kem_params = kem_params.with_inversion(parsed.inversion);
+        }
+        Ok(parsed)
+    }
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ntru_kem::NTRU_HPS2048509;
+    use crate::presets::TOY_11;
+
+    fn parse (line: &str) -> Result<Args, String> {
+        Args::parse(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn values_are_not_read_as_modes () {
+        let args = parse("save cca").unwrap();
+        assert_eq!(args.mode, Mode::Ntru);
+        assert_eq!(args.save, Some(("cca".to_string(), FileFormat::Binary)));
+
+        let args = parse("load mitm").unwrap();
+        assert_eq!(args.mode, Mode::Load("mitm".to_string()));
+
+        let args = parse("toy-11 save-text hybrid seed 7").unwrap();
+        assert_eq!(args.mode, Mode::Ntru);
+        assert_eq!(args.preset, Some(TOY_11));
+        assert_eq!(args.save, Some(("hybrid".to_string(), FileFormat::Text)));
+        assert_eq!(args.seed, Some(7));
+        assert_eq!(args.reduction, Reduction::Cyclic);
+    }
+
+    #[test]
+    fn reduction () {
+        assert_eq!(parse("cca negacyclic").unwrap().reduction, Reduction::Negacyclic);
+        assert_eq!(parse("save negacyclic").unwrap().reduction, Reduction::Cyclic);
+        assert!(parse("cyclic negacyclic").is_err());
+    }
+
+    #[test]
+    fn inversion () {
+        assert_eq!(parse("cca").unwrap().inversion, InversionMethod::Euclid);
+        assert_eq!(parse("divsteps cca").unwrap().inversion, InversionMethod::Divsteps);
+        let Mode::NtruKem(kem_params) = parse("divsteps hrss701").unwrap().mode else { panic!("not a KEM mode") };
+        assert_eq!(kem_params.ring_params().inversion(), InversionMethod::Divsteps);
+    }
+
+    #[test]
+    fn integer () {
+        assert_eq!(parse("toy-11").unwrap().integer, IntegerType::default());
+        assert_eq!(parse("integer bigint toy-11").unwrap().integer, IntegerType::BigInt);
+        assert_eq!(parse("integer i32").unwrap().integer, IntegerType::I32);
+        assert!(parse("integer u8").is_err());
+        assert!(parse("integer i64 cca").is_err());
+    }
+
+    #[test]
+    fn kem_and_kat () {
+        let args = parse("hps2048509 kat PQCkemKAT_935.rsp").unwrap();
+        assert_eq!(args.mode, Mode::NtruKem(NTRU_HPS2048509));
+        assert_eq!(args.kat_file.as_deref(), Some("PQCkemKAT_935.rsp"));
+    }
+
+    #[test]
+    fn rejects_bad_command_lines () {
+        assert!(parse("cca mitm").is_err());
+        assert!(parse("toy-11 toy-53").is_err());
+        assert!(parse("seed").is_err());
+        assert!(parse("seed x").is_err());
+        assert!(parse("kat file.rsp").is_err());
+        assert!(parse("unknown").is_err());
+    }
+}
+//=======================================================================================================================
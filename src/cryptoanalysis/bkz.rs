@@ -1,7 +1,8 @@
+use crate::cryptoanalysis::LatticeInteger;
 use crate::cryptoanalysis::lll::*;
 use crate::polyxnm1::Integer;
 //=======================================================================================================================
-pub fn bkz (b: &mut Vec<Vec<Integer>>, beta: usize, delta: Float) {
+pub fn bkz <L: LatticeInteger> (b: &mut Vec<Vec<L>>, beta: usize, delta: Float) {
     let (mut c, mut gs_coeff) = lll(b, delta, false);
 
     let mut z = 0usize;
@@ -12,11 +13,10 @@ pub fn bkz (b: &mut Vec<Vec<Integer>>, beta: usize, delta: Float) {
         
         let (_, c_j, bj_new) = bkz_enum(b, &c, &gs_coeff, j, k);
         
-        let h = (k + 1).min(m - 1);
         if delta * c[j] > c_j {
             let mut new_b = b[0..j].to_vec();
             new_b.push(bj_new);
-            new_b.extend_from_slice(&b[j..=h]);
+            new_b.extend_from_slice(&b[j..]);
             
             *b = new_b;
 
@@ -35,7 +35,7 @@ pub fn bkz (b: &mut Vec<Vec<Integer>>, beta: usize, delta: Float) {
     }
 }
 //=======================================================================================================================
-fn bkz_enum (b: &Vec<Vec<Integer>>, c: &Vec<Float>, gs_coeff: &Vec<Vec<Float>>, j: usize, k: usize) -> (Vec<Integer>, Float, Vec<Integer>) {
+fn bkz_enum <L: LatticeInteger> (b: &[Vec<L>], c: &[Float], gs_coeff: &[Vec<Float>], j: usize, k: usize) -> (Vec<Integer>, Float, Vec<L>) {
     // 1. initialization
     let vec_size = k + 2;
     let mut c_j = c[j];
@@ -60,8 +60,8 @@ fn bkz_enum (b: &Vec<Vec<Integer>>, c: &Vec<Float>, gs_coeff: &Vec<Vec<Float>>,
                 t -= 1;
 
                 let mut sum = 0 as Float;
-                for i in (t+1)..=s {
-                    sum += u_[i] as Float * gs_coeff[i][t];
+                for (i, &u_i) in u_.iter().enumerate().take(s + 1).skip(t + 1) {
+                    sum += u_i as Float * gs_coeff[i][t];
                 }
                 y[t] = sum;
                 v[t] = (-y[t]).round() as Integer;
@@ -72,9 +72,7 @@ fn bkz_enum (b: &Vec<Vec<Integer>>, c: &Vec<Float>, gs_coeff: &Vec<Vec<Float>>,
             }
             else {
                 c_j = c_[j];
-                for i in j..=k {
-                    u[i] = u_[i];
-                }
+                u[j..=k].copy_from_slice(&u_[j..=k]);
             }
         }
         else {
@@ -93,13 +91,60 @@ fn bkz_enum (b: &Vec<Vec<Integer>>, c: &Vec<Float>, gs_coeff: &Vec<Vec<Float>>,
     (u, c_j, bj_new)
 }
 //=======================================================================================================================
-fn sum_ui_bi (u: &Vec<Integer>, b: &Vec<Vec<Integer>>, j: usize, k: usize) -> Vec<Integer> {
-    let mut result = vec![0 as Integer; b[0].len()];
+fn sum_ui_bi <L: LatticeInteger> (u: &[Integer], b: &[Vec<L>], j: usize, k: usize) -> Vec<L> {
+    let mut result = vec![L::zero(); b[0].len()];
     for i in j..=k {
-        for x in 0..result.len() {
-            result[x] += u[i] * b[i][x];
+        let u_i = L::from(u[i]);
+        for (x, b_ix) in result.iter_mut().zip(&b[i]) {
+            *x = checked_add(x, &checked_mul(&u_i, b_ix));
         }
     }
     result
 }
-//=======================================================================================================================
\ No newline at end of file
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+    use num_traits::{One, Zero};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    /// Bareiss elimination: every division is exact.
+    fn determinant (b: &[Vec<BigInt>]) -> BigInt {
+        let mut a = b.to_vec();
+        let n = a.len();
+        let (mut previous, mut sign) = (BigInt::one(), BigInt::one());
+        for k in 0..n - 1 {
+            if a[k][k].is_zero() {
+                let Some(i) = (k + 1..n).find(|&i| !a[i][k].is_zero()) else { return BigInt::zero() };
+                a.swap(k, i);
+                sign = -sign;
+            }
+            for i in k + 1..n {
+                for j in k + 1..n {
+                    a[i][j] = (&a[i][j] * &a[k][k] - &a[i][k] * &a[k][j]) / &previous;
+                }
+            }
+            previous = a[k][k].clone();
+        }
+        sign * &a[n - 1][n - 1]
+    }
+
+    #[test]
+    fn small_blocks_keep_the_lattice () {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for dim in [6, 8, 10] {
+            let mut b: Vec<Vec<BigInt>> = (0..dim).map(|i| (0..dim).map(|j| {
+                BigInt::from(if i == j { 97 } else { rng.random_range(-40..=40) })
+            }).collect()).collect();
+            let det = determinant(&b);
+            assert!(!det.is_zero());
+
+            bkz(&mut b, 2, 0.99);
+            assert_eq!(b.len(), dim);
+            assert_eq!(determinant(&b).magnitude(), det.magnitude(), "dim = {}", dim);
+        }
+    }
+}
+//=======================================================================================================================
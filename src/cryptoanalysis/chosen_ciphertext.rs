@@ -0,0 +1,151 @@
+use crate::ntru::{Ciphertext, Plaintext, PrivateKey, PublicKey, SilentObserver, ntru_decrypt};
+use crate::polyxnm1::{Integer, PolyXNm1, RingParams, UInteger, zp::*};
+use crate::polyxnm1::inverse::find_inv_polynomial;
+use crate::polyxnm1::service::mod_center;
+use rand::Rng;
+use rand::seq::index;
+
+/// Largest number of equal coefficients in one chosen ciphertext.
+const MAX_TERMS: usize = 16;
+//=======================================================================================================================
+/// Decryption oracle of the chosen-ciphertext model: decrypts any ciphertext with a hidden private key and counts
+/// the queries.
+pub struct DecryptionOracle<'a> {
+    key: &'a PrivateKey,
+    queries: usize
+}
+
+impl <'a> DecryptionOracle<'a> {
+    pub fn new (key: &'a PrivateKey) -> DecryptionOracle<'a> {
+        DecryptionOracle { key, queries: 0 }
+    }
+
+    pub fn decrypt <Q: Module> (&mut self, cipher: &Ciphertext<Q>) -> Plaintext {
+        self.queries += 1;
+        ntru_decrypt(self.key, cipher, &mut SilentObserver)
+    }
+
+    pub fn queries (&self) -> usize {
+        self.queries
+    }
+}
+//=======================================================================================================================
+/// Chosen-ciphertext key recovery of unpadded NTRU (Jaulmes, Joux 2000). A query e = y * (x^k1 + ... + x^kt) gives
+/// a = f * e with |a_i| <= t * y, and a_i wraps mod q only where t coefficients of f line up with equal signs. Then
+/// m = y * s - q * fp * w (mod p) for s = x^k1 + ... + x^kt and the wrapped positions w, so a single wrapped
+/// position w = +-x^j reveals fp * x^j and f * x^-j up to sign, which is an equivalent private key (for x^N+1 up to
+/// the signs of the wrapped coefficients). Candidates are accepted if f is ternary of weight 2df - 1 and f * h is
+/// ternary mod q. Returns None if `max_queries` oracle calls do not give such a candidate.
+pub fn jaulmes_joux_attack <Q: Module> (public_key: &PublicKey<Q>, df: u16, oracle: &mut DecryptionOracle, max_queries: usize, rng: &mut impl Rng) -> Option<PrivateKey> {
+    let params = public_key.h.params();
+    let n = params.n() as usize;
+    let (p, q) = (params.p(), params.q());
+    let (t, y) = ciphertext_shape(n, df, q)?;
+    let q_inv = (1..p).find(|&x| x * q % p == 1)? as Integer;
+
+    while oracle.queries() < max_queries {
+        let mut s = vec![0 as Integer; n];
+        for k in index::sample(rng, n, t) {
+            s[k] = 1;
+        }
+        let e = PolyXNm1::<Q>::from_integers(params, &s.iter().map(|&x| x * y).collect::<Vec<Integer>>());
+
+        let mut m = oracle.decrypt(&Ciphertext { e }).m;
+        m.resize(n, 0);
+
+        // fp * w = -(m - y * s) / q (mod p)
+        let fp_w: Vec<Integer> = m.iter().zip(&s).map(|(&x, &s)| mod_center(-(x - y * s) * q_inv, p)).collect();
+        if fp_w.iter().all(|&x| x == 0) {
+            continue;
+        }
+        if let Some(key) = candidate_key(public_key, df, &fp_w) {
+            return Some(key);
+        }
+    }
+    None
+}
+//=======================================================================================================================
+/// (k, sign) with recovered f = sign * x^k * f of the real key, if the recovered key is a rotation of it.
+pub fn private_key_rotation <Q: Module> (params: &RingParams, recovered: &PrivateKey, real: &PrivateKey) -> Option<(usize, Integer)> {
+    let n = params.n() as usize;
+    let q = params.q();
+    let recovered = PolyXNm1::<Q>::from_integers(params, &recovered.f).to_integers();
+    let real = PolyXNm1::<Q>::from_integers(params, &real.f);
+
+    (0..n).find_map(|k| {
+        let mut monomial = vec![0 as Integer; k + 1];
+        monomial[k] = 1;
+        let rotated = (PolyXNm1::from_integers(params, &monomial) * &real).to_integers();
+        [1, -1].into_iter()
+            .find(|&sign| rotated.iter().zip(&recovered).all(|(&x, &y)| mod_center(sign * x - y, q) == 0))
+            .map(|sign| (k, sign))
+    })
+}
+//=======================================================================================================================
+/// t coefficients y per query: about N * ((df/N)^t + ((df-1)/N)^t) coefficients of f * e wrap, t brings this
+/// closest to 1. (t - 1) * y stays in the centered range mod q, t * y leaves it.
+fn ciphertext_shape (n: usize, df: u16, q: UInteger) -> Option<(usize, Integer)> {
+    let (n_f, df) = (n as f64, df as f64);
+    let expected_wraps = |t: usize| n_f * ((df / n_f).powi(t as i32) + ((df - 1.0) / n_f).powi(t as i32));
+    let t = (2..=MAX_TERMS.min(n)).min_by(|&a, &b| expected_wraps(a).ln().abs().total_cmp(&expected_wraps(b).ln().abs()))?;
+
+    let y = ((q - 1) / 2) as Integer / (t as Integer - 1);
+    (t as Integer * y > (q / 2) as Integer).then_some((t, y))
+}
+//=======================================================================================================================
+/// Private key with the candidate fp, if its inverse f is ternary of weight 2df - 1 and f * h is ternary mod q.
+fn candidate_key <Q: Module> (public_key: &PublicKey<Q>, df: u16, fp: &[Integer]) -> Option<PrivateKey> {
+    let params = public_key.h.params();
+    let fp = PolyXNm1::<ModP>::from_integers(params, fp);
+    let f = find_inv_polynomial(&fp).ok()?;
+    let f: Vec<Integer> = f.coeffs().iter().map(|x| mod_center(x.get(), params.p())).collect();
+
+    if f.iter().any(|&x| x.abs() > 1) || f.iter().filter(|&&x| x != 0).count() != 2 * df as usize - 1 {
+        return None;
+    }
+    let g = PolyXNm1::<Q>::from_integers(params, &f) * &public_key.h;
+    if g.coeffs().iter().any(|x| mod_center(x.get(), params.q()).abs() > 1) {
+        return None;
+    }
+
+    Some(PrivateKey { f, fp })
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ntru::ntru_gen_keys;
+    use crate::polyxnm1::Reduction;
+    use crate::presets::{NTRU_107, TOY_53};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn recovers_a_rotation_of_the_key () {
+        let max_queries = 100;
+        for reduction in [Reduction::Cyclic, Reduction::Negacyclic] {
+            let params = RingParams::with_reduction(NTRU_107.n, NTRU_107.p, NTRU_107.q, reduction);
+            for seed in 0..3 {
+                let mut rng = ChaCha20Rng::seed_from_u64(seed);
+                let (public_key, private_key) = ntru_gen_keys::<ModQPow2, Integer>(&params, NTRU_107.df, NTRU_107.dg, &mut rng, &mut SilentObserver);
+                let mut oracle = DecryptionOracle::new(&private_key);
+
+                let key = jaulmes_joux_attack(&public_key, NTRU_107.df, &mut oracle, max_queries, &mut rng)
+                    .unwrap_or_else(|| panic!("{}, seed {}: no key after {} queries", reduction, seed, max_queries));
+                assert!(oracle.queries() <= max_queries);
+                assert!(private_key_rotation::<ModQPow2>(&params, &key, &private_key).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn gives_up_after_max_queries () {
+        let params = RingParams::new(TOY_53.n, TOY_53.p, TOY_53.q);
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let (public_key, private_key) = ntru_gen_keys::<ModQPow2, Integer>(&params, TOY_53.df, TOY_53.dg, &mut rng, &mut SilentObserver);
+        let mut oracle = DecryptionOracle::new(&private_key);
+        assert!(jaulmes_joux_attack(&public_key, TOY_53.df, &mut oracle, 0, &mut rng).is_none());
+        assert_eq!(oracle.queries(), 0);
+    }
+}
+//=======================================================================================================================
@@ -0,0 +1,273 @@
+use crate::cryptoanalysis::{LatticeInt, svp_create_lattice_basis};
+use crate::cryptoanalysis::bkz::bkz;
+use crate::cryptoanalysis::lll::{Float, scalar_product, squared_norm};
+use crate::cryptoanalysis::meet_in_the_middle::{HalfKey, for_each_half_key, half_key_count};
+use crate::ntru::{PrivateKey, PublicKey};
+use crate::polyxnm1::{Integer, PolyXNm1, RingParams, zp::*};
+use crate::polyxnm1::inverse::find_inv_polynomial;
+use crate::polyxnm1::service::mod_center;
+use num_traits::ToPrimitive;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+/// Labels are u64 bit strings, one bit per coordinate of a Babai residue.
+const MAX_LABEL_BITS: usize = 64;
+/// Residues of this many first half-guesses choose the coordinates and thresholds of the labels.
+const LABEL_SAMPLE: usize = 256;
+//=======================================================================================================================
+/// Result of `hybrid_attack`.
+pub struct HybridReport {
+    pub params: RingParams,
+    pub df: u16,
+    /// Number of guessed coefficients of f and the weights of the two halves of the guess.
+    pub guessed: usize,
+    pub split: (usize, usize),
+    pub block_size: usize,
+    pub dimension: usize,
+    pub reduction_time: Duration,
+    pub table_entries: usize,
+    pub buckets: usize,
+    /// Estimated heap size of the table.
+    pub table_bytes: usize,
+    pub searched: usize,
+    /// Pairs of half-guesses with colliding labels that were checked.
+    pub collisions: usize,
+    pub search_time: Duration,
+    pub key: Option<PrivateKey>
+}
+
+impl fmt::Display for HybridReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "N = {}, q = {}, df = {}, r = {}: BKZ-{} on dimension {} ({:?}), guesses {} + {}, {} half-guesses in \
+            {} buckets ({:.3} MiB), {} searched, {} collisions ({:?}), ",
+            self.params.n(), self.params.q(), self.df, self.guessed, self.block_size, self.dimension, self.reduction_time,
+            self.split.0, self.split.1, self.table_entries, self.buckets, self.table_bytes as f64 / (1 << 20) as f64,
+            self.searched, self.collisions, self.search_time)?;
+        match self.key {
+            Some(_) => write!(f, "key recovered"),
+            None => write!(f, "key not recovered")
+        }
+    }
+}
+//=======================================================================================================================
+/// Hybrid lattice-reduction and meet-in-the-middle attack (Howgrave-Graham, 2007). The last r coefficients of f
+/// are guessed: removing their rows and columns from the `svp_create_lattice_basis` matrix leaves the block L' of
+/// dimension 2N - r (the upper-left block in Howgrave-Graham's ordering), which is reduced with BKZ, and
+/// (f_head, g) = t(f_tail) - w for the vector t(u) = (0, u * h restricted to the guessed rows) and some w in L'.
+/// If the reduced basis is good enough, Babai's nearest plane maps t(f_tail) to exactly (f_head, g). The guess
+/// f_tail = u1 + u2 is met in the middle: Babai residues of u1 and -u2 differ by the ternary (f_head, g), so u1
+/// are hashed by comparing coordinates of their residues with thresholds and every u2 looks up each label the
+/// ternary difference allows. Collisions are accepted if the residue of u1 + u2 completes a ternary f with f * h
+/// ternary mod q.
+///
+/// The guessed part carries the floor of its share of the 2df - 1 nonzero coefficients, as some rotation of f does
+/// for x^N -+ 1, and u1 and u2 may sit anywhere in it. The reduced rows themselves are tried as keys with nothing
+/// guessed first, so r = 0 is the plain lattice attack. None of the stages is bounded: choose r and the block size
+/// with `hybrid_table_size` in mind.
+pub fn hybrid_attack <Q: Module> (public_key: &PublicKey<Q>, df: u16, guessed: usize, block_size: usize) -> HybridReport {
+    let params = public_key.h.params();
+    let n = params.n() as usize;
+    assert!(guessed < n, "hybrid attack: cannot guess {} of {} coefficients", guessed, n);
+    let head = n - guessed;
+    let dimension = 2 * n - guessed;
+    let (w1, w2) = guess_split(n, df, guessed);
+
+    let basis: Vec<Vec<LatticeInt>> = svp_create_lattice_basis(&public_key.h);
+    let kept: Vec<usize> = (0..head).chain(n..2 * n).collect();
+    let mut block: Vec<Vec<LatticeInt>> = kept.iter().map(|&i| kept.iter().map(|&j| &basis[i][j]).cloned().collect()).collect();
+
+    let start = Instant::now();
+    bkz(&mut block, block_size.min(dimension - 1), 0.99);
+    let reduction_time = start.elapsed();
+
+    let start = Instant::now();
+    let block: Vec<Vec<Float>> = block.iter().map(|v| v.iter().map(|x| x.to_f64().expect("hybrid attack: basis entry is not finite")).collect()).collect();
+    let mut key = block.iter().find_map(|v| candidate_key(public_key, v, &[], head));
+
+    let gram_schmidt = gram_schmidt(&block);
+    // targets[k] = t(x^(head + k)): the g half of the row of the guessed coefficient
+    let targets: Vec<Vec<Float>> = (head..n).map(|i| {
+        let mut t = vec![0.0; dimension];
+        for (x, y) in t[head..].iter_mut().zip(&basis[i][n..]) {
+            *x = y.to_f64().expect("hybrid attack: basis entry is not finite");
+        }
+        t
+    }).collect();
+    let residue = |guess: &HalfKey, sign: Float| {
+        let mut t = vec![0.0; dimension];
+        for &(k, s) in guess {
+            for (x, &y) in t.iter_mut().zip(&targets[k - head]) {
+                *x += sign * s as Float * y;
+            }
+        }
+        babai_residue(&block, &gram_schmidt, &t)
+    };
+
+    let mut table: HashMap<u64, Vec<HalfKey>> = HashMap::new();
+    let mut table_entries = 0;
+    let (mut searched, mut collisions) = (0, 0);
+    if key.is_none() {
+        let label_bits = (hybrid_table_size(n, df, guessed).log2().ceil() as usize).clamp(1, dimension.min(MAX_LABEL_BITS));
+        let mut sample = Vec::new();
+        for_each_half_key(head..n, w1, true, |first| {
+            sample.push(residue(&first, 1.0));
+            sample.len() < LABEL_SAMPLE
+        });
+        let thresholds = label_thresholds(&sample, label_bits);
+
+        for_each_half_key(head..n, w1, true, |first| {
+            let label = label(&residue(&first, 1.0), &thresholds);
+            table.entry(label).or_default().push(first);
+            table_entries += 1;
+            true
+        });
+
+        for_each_half_key(head..n, w2, false, |second| {
+            searched += 1;
+            let target = residue(&second, -1.0);
+            for label in labels_near(&target, &thresholds) {
+                for first in table.get(&label).into_iter().flatten() {
+                    collisions += 1;
+                    let mut tail = vec![0 as Integer; guessed];
+                    for &(k, s) in first.iter().chain(&second) {
+                        tail[k - head] += s;
+                    }
+                    if tail.iter().any(|x| x.abs() > 1) {
+                        continue;
+                    }
+                    let guess: HalfKey = tail.iter().enumerate().filter(|&(_, &s)| s != 0).map(|(k, &s)| (head + k, s)).collect();
+                    key = candidate_key(public_key, &residue(&guess, 1.0), &tail, head);
+                    if key.is_some() {
+                        return false;
+                    }
+                }
+            }
+            true
+        });
+    }
+    let table_bytes = table.len() * (size_of::<u64>() + size_of::<Vec<HalfKey>>())
+        + table_entries * (size_of::<HalfKey>() + w1 * size_of::<(usize, Integer)>());
+
+    HybridReport { params: *params, df, guessed, split: (w1, w2), block_size, dimension, reduction_time, table_entries,
+        buckets: table.len(), table_bytes, searched, collisions, search_time: start.elapsed(), key }
+}
+//=======================================================================================================================
+/// Number of first half-guesses the attack stores.
+pub fn hybrid_table_size (n: usize, df: u16, guessed: usize) -> f64 {
+    let (w1, _) = guess_split(n, df, guessed);
+    half_key_count(guessed, w1, true)
+}
+//=======================================================================================================================
+/// Weights of u1 and u2: halves of the floor of the share of the 2df - 1 nonzero coefficients in r of N.
+fn guess_split (n: usize, df: u16, guessed: usize) -> (usize, usize) {
+    let weight = (2 * df as usize - 1) * guessed / n;
+    (weight / 2, weight - weight / 2)
+}
+//=======================================================================================================================
+/// Gram-Schmidt orthogonalization of the rows.
+fn gram_schmidt (basis: &[Vec<Float>]) -> Vec<Vec<Float>> {
+    let mut orthogonal: Vec<Vec<Float>> = Vec::with_capacity(basis.len());
+    for b in basis {
+        let mut v = b.clone();
+        for u in &orthogonal {
+            let mu = scalar_product(&v, u) / squared_norm(u);
+            for (x, &y) in v.iter_mut().zip(u) {
+                *x -= mu * y;
+            }
+        }
+        orthogonal.push(v);
+    }
+    orthogonal
+}
+//=======================================================================================================================
+/// t minus the lattice vector Babai's nearest plane finds for it.
+fn babai_residue (basis: &[Vec<Float>], gram_schmidt: &[Vec<Float>], t: &[Float]) -> Vec<Float> {
+    let mut residue = t.to_vec();
+    for (b, u) in basis.iter().zip(gram_schmidt).rev() {
+        let c = (scalar_product(&residue, u) / squared_norm(u)).round();
+        if c != 0.0 {
+            for (x, &y) in residue.iter_mut().zip(b) {
+                *x -= c * y;
+            }
+        }
+    }
+    residue
+}
+//=======================================================================================================================
+/// (coordinate, threshold) of each label bit: the median of the coordinate in the sample, for the `bits`
+/// coordinates that are least often next to it (where the bit of a ternary shift is ambiguous).
+fn label_thresholds (sample: &[Vec<Float>], bits: usize) -> Vec<(usize, Float)> {
+    let mut thresholds: Vec<(usize, Float, usize)> = (0..sample[0].len()).map(|i| {
+        let mut column: Vec<Float> = sample.iter().map(|v| v[i]).collect();
+        column.sort_by(Float::total_cmp);
+        let median = column[column.len() / 2];
+        (i, median, column.iter().filter(|&&x| x == median || x == median - 1.0).count())
+    }).collect();
+    thresholds.sort_by_key(|&(_, _, ambiguous)| ambiguous);
+    thresholds.into_iter().take(bits).map(|(i, median, _)| (i, median)).collect()
+}
+//=======================================================================================================================
+/// Bits x_i >= threshold_i.
+fn label (v: &[Float], thresholds: &[(usize, Float)]) -> u64 {
+    thresholds.iter().enumerate().fold(0, |label, (bit, &(i, threshold))| label | ((v[i] >= threshold) as u64) << bit)
+}
+//=======================================================================================================================
+/// Labels of all v + g with ternary g: a coordinate threshold - 1 or threshold gives both bits.
+fn labels_near (v: &[Float], thresholds: &[(usize, Float)]) -> Vec<u64> {
+    let mut labels = vec![label(v, thresholds)];
+    for (bit, &(i, threshold)) in thresholds.iter().enumerate() {
+        if v[i] == threshold || v[i] == threshold - 1.0 {
+            let flipped: Vec<u64> = labels.iter().map(|label| label ^ (1 << bit)).collect();
+            labels.extend(flipped);
+        }
+    }
+    labels
+}
+//=======================================================================================================================
+/// Private key f = (f_head, tail) for a vector (f_head, g) of the block, if f is ternary and nonzero, f * h is
+/// ternary mod q and f is invertible mod p.
+fn candidate_key <Q: Module> (public_key: &PublicKey<Q>, v: &[Float], tail: &[Integer], head: usize) -> Option<PrivateKey> {
+    let params = public_key.h.params();
+    if v.iter().any(|x| x.abs() > 1.0) {
+        return None;
+    }
+    let f: Vec<Integer> = v[..head].iter().map(|&x| x as Integer).chain(tail.iter().copied()).collect();
+    if f.iter().all(|&x| x == 0) {
+        return None;
+    }
+
+    let g = PolyXNm1::<Q>::from_integers(params, &f) * &public_key.h;
+    if g.coeffs().iter().any(|x| mod_center(x.get(), params.q()).abs() > 1) {
+        return None;
+    }
+    let fp = find_inv_polynomial(&PolyXNm1::<ModP>::from_integers(params, &f)).ok()?;
+    Some(PrivateKey { f, fp })
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptoanalysis::chosen_ciphertext::private_key_rotation;
+    use crate::ntru::{SilentObserver, ntru_gen_keys};
+    use crate::presets::TOY_11;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    /// The reduced rows alone do not give the key of this seed, so it is found by the meet-in-the-middle search.
+    #[test]
+    fn recovers_a_toy_key_by_guessing () {
+        let params = RingParams::new(TOY_11.n, TOY_11.p, TOY_11.q);
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        let (public_key, private_key) = ntru_gen_keys::<ModQPow2, Integer>(&params, TOY_11.df, TOY_11.dg, &mut rng, &mut SilentObserver);
+
+        let report = hybrid_attack(&public_key, TOY_11.df, 4, 2);
+        assert_eq!(report.split, guess_split(11, TOY_11.df, 4));
+        assert_eq!(report.table_entries as f64, hybrid_table_size(11, TOY_11.df, 4));
+        assert!(report.searched > 0);
+        let key = report.key.expect("hybrid attack: key not recovered");
+        assert!(private_key_rotation::<ModQPow2>(&params, &key, &private_key).is_some());
+    }
+}
+//=======================================================================================================================
@@ -1,9 +1,9 @@
-use crate::polyxnm1::Integer;
+use crate::cryptoanalysis::LatticeInteger;
 use num_traits::Zero;
 
 pub type Float = f64;
 //=======================================================================================================================
-pub fn lll (b: &mut Vec<Vec<Integer>>, delta: Float, mut fc: bool) -> (Vec<Float>, Vec<Vec<Float>>) {
+pub fn lll <L: LatticeInteger> (b: &mut Vec<Vec<L>>, delta: Float, mut fc: bool) -> (Vec<Float>, Vec<Vec<Float>>) {
     let n = b[0].len();
 
     let mut gs_coeff = vec![vec![0 as Float; n]; b.len()];
@@ -11,7 +11,7 @@ pub fn lll (b: &mut Vec<Vec<Integer>>, delta: Float, mut fc: bool) -> (Vec<Float
 
     // 1. initialization
     let mut k = 1;
-    let mut b_ = to_float(&b);
+    let mut b_ = to_float(b);
 
     while k < b.len() {
         // 2. GS-orthogonalization
@@ -22,7 +22,7 @@ pub fn lll (b: &mut Vec<Vec<Integer>>, delta: Float, mut fc: bool) -> (Vec<Float
 
         for j in 0..k {
             let s = if scalar_product(&b_[k], &b_[j]).abs() < (2 as Float).powf(-(Float::MANTISSA_DIGITS as Float / 2.)) * squared_norm(&b_[k]).sqrt() * squared_norm(&b_[j]).sqrt() {
-                scalar_product(&b[k], &b[j]) as Float
+                to_float_scalar(&integer_scalar_product(&b[k], &b[j]))
             }
             else {
                 scalar_product(&b_[k], &b_[j])
@@ -39,24 +39,26 @@ pub fn lll (b: &mut Vec<Vec<Integer>>, delta: Float, mut fc: bool) -> (Vec<Float
         // 3. size-reduction of b
         for j in (0..k).rev() {
             if gs_coeff[k][j].abs() > 0.5 {
-                let nu = gs_coeff[k][j].round() as Integer;
-                if nu.abs() > 2i32.pow(Float::MANTISSA_DIGITS / 2) as Integer { fc = true; }
-                for i in 0..j {
-                    gs_coeff[k][i] -= nu as Float * gs_coeff[j][i]; 
+                let nu = gs_coeff[k][j].round();
+                if nu.abs() > (2 as Float).powi(Float::MANTISSA_DIGITS as i32 / 2) { fc = true; }
+                let (upper, lower) = gs_coeff.split_at_mut(k);
+                for (x, &y) in lower[0].iter_mut().zip(&upper[j]).take(j) {
+                    *x -= nu * y;
                 }
-                gs_coeff[k][j] -= nu as Float;
+                gs_coeff[k][j] -= nu;
                 
-                sub_vec(b, k, j, nu);
+                let nu = L::from_f64(nu).expect("LLL: size-reduction coefficient is not finite or does not fit the lattice integer type");
+                sub_vec(b, k, j, &nu);
                 assign_vec_to_float(&mut b_[k], &b[k]);
             }
         }
-        if fc == true {
+        if fc {
             fc = false;
             k = (k - 1).max(1);
             continue;
         }
         // additional assignment for step 3: zero-vectors removing
-        if is_zero(&b[k]) == true {
+        if is_zero(&b[k]) {
             b.remove(k);
             b_.remove(k);
             c.remove(k);
@@ -78,54 +80,78 @@ pub fn lll (b: &mut Vec<Vec<Integer>>, delta: Float, mut fc: bool) -> (Vec<Float
     (c, gs_coeff)
 }
 //=======================================================================================================================
-fn to_float(m: &Vec<Vec<Integer>>) -> Vec<Vec<Float>> {
-    let mut result = vec![vec![0 as Float; m[0].len()]; m.len()];
-    for i in 0..result.len() {
-        for j in 0..result[0].len() {
-            result[i][j] = m[i][j] as Float;
-        }
-    }
-    result
+fn to_float <L: LatticeInteger> (m: &[Vec<L>]) -> Vec<Vec<Float>> {
+    m.iter().map(|v| v.iter().map(to_float_scalar).collect()).collect()
 }
 //=======================================================================================================================
 #[inline(always)]
-pub fn squared_norm<T: std::ops::Mul<Output = T> + std::ops::AddAssign + Zero + Copy>(v: &Vec<T>) -> T {
+fn to_float_scalar <L: LatticeInteger> (x: &L) -> Float {
+    x.to_f64().unwrap_or(Float::NAN)
+}
+//=======================================================================================================================
+#[inline(always)]
+pub fn squared_norm<T: std::ops::Mul<Output = T> + std::ops::AddAssign + Zero + Copy>(v: &[T]) -> T {
     scalar_product::<T>(v, v)
 }
 //=======================================================================================================================
-fn scalar_product<T: std::ops::Mul<Output = T> + std::ops::AddAssign + Zero + Copy>(a: &Vec<T>, b: &Vec<T>) -> T {
+pub fn scalar_product<T: std::ops::Mul<Output = T> + std::ops::AddAssign + Zero + Copy>(a: &[T], b: &[T]) -> T {
     let mut result: T = T::zero();
-    for i in 0..a.len() {
-        result += a[i] * b[i];
+    for (&x, &y) in a.iter().zip(b) {
+        result += x * y;
+    }
+    result
+}
+//=======================================================================================================================
+#[allow(dead_code)]
+#[inline(always)]
+pub fn integer_squared_norm <L: LatticeInteger> (v: &[L]) -> L {
+    integer_scalar_product(v, v)
+}
+//=======================================================================================================================
+fn integer_scalar_product <L: LatticeInteger> (a: &[L], b: &[L]) -> L {
+    let mut result = L::zero();
+    for (x, y) in a.iter().zip(b) {
+        result = checked_add(&result, &checked_mul(x, y));
     }
     result
 }
 //=======================================================================================================================
-fn sub_vec(m: &mut Vec<Vec<Integer>>, index_1: usize, index_2: usize, b_mul: Integer) {
+fn sub_vec <L: LatticeInteger> (m: &mut [Vec<L>], index_1: usize, index_2: usize, b_mul: &L) {
     for i in 0..m[0].len() {
-        m[index_1][i] -= b_mul * m[index_2][i];
+        m[index_1][i] = checked_sub(&m[index_1][i], &checked_mul(b_mul, &m[index_2][i]));
     }
 }
 //=======================================================================================================================
-fn assign_vec_to_float (a: &mut Vec<Float>, b: &Vec<Integer>) {
-    for i in 0..a.len() {
-        a[i] = b[i] as Float;
+fn assign_vec_to_float <L: LatticeInteger> (a: &mut [Float], b: &[L]) {
+    for (x, y) in a.iter_mut().zip(b) {
+        *x = to_float_scalar(y);
     }
 }
 //=======================================================================================================================
 #[allow(dead_code)]
-fn assign_vec (v: &mut Vec<Vec<Integer>>, index_1: usize, index_2: usize) {
-    for i in 0..v[0].len() {
-        v[index_1][i] = v[index_2][i];
-    }
+fn assign_vec <L: LatticeInteger> (v: &mut [Vec<L>], index_1: usize, index_2: usize) {
+    let source = v[index_2].clone();
+    v[index_1].clone_from_slice(&source);
 }
 //=======================================================================================================================
-fn is_zero(v: &Vec<Integer>) -> bool {
-    for i in 0..v.len() {
-        if v[i] != 0 {
-            return false;
-        }
-    }
-    true
+fn is_zero <L: LatticeInteger> (v: &[L]) -> bool {
+    v.iter().all(|x| x.is_zero())
+}
+//=======================================================================================================================
+#[inline(always)]
+pub fn checked_add <L: LatticeInteger> (a: &L, b: &L) -> L {
+    a.checked_add(b).expect(OVERFLOW)
+}
+
+#[inline(always)]
+pub fn checked_sub <L: LatticeInteger> (a: &L, b: &L) -> L {
+    a.checked_sub(b).expect(OVERFLOW)
 }
-//=======================================================================================================================
\ No newline at end of file
+
+#[inline(always)]
+pub fn checked_mul <L: LatticeInteger> (a: &L, b: &L) -> L {
+    a.checked_mul(b).expect(OVERFLOW)
+}
+
+const OVERFLOW: &str = "lattice: integer overflow, use a wider integer backend (integer-i64, integer-i128 or lattice-bigint)";
+//=======================================================================================================================
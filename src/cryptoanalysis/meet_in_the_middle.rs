@@ -0,0 +1,266 @@
+use crate::ntru::PrivateKey;
+#[cfg(not(feature = "time-measurement"))] use crate::ntru::PublicKey;
+use crate::polyxnm1::{Integer, PolyXNm1, RingParams, zp::*};
+use crate::polyxnm1::convolution::fold;
+use crate::polyxnm1::inverse::find_inv_polynomial;
+use crate::polyxnm1::service::mod_center;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::size_of;
+
+/// Labels are u64 bit strings, one bit per coefficient of f1 * h.
+const MAX_LABEL_BITS: usize = 64;
+
+/// Nonzero coefficients of a half of f: (index, sign).
+pub type HalfKey = Vec<(usize, Integer)>;
+//=======================================================================================================================
+/// Result of `odlyzko_search`.
+pub struct MitmReport {
+    pub params: RingParams,
+    pub df: u16,
+    /// Nonzero coefficients of f in the first half (stored) and in the second half (searched).
+    pub split: (usize, usize),
+    pub label_bits: usize,
+    pub table_entries: usize,
+    pub buckets: usize,
+    /// Estimated heap size of the table.
+    pub table_bytes: usize,
+    pub searched: usize,
+    /// Pairs f1 + f2 with colliding labels that were checked.
+    pub collisions: usize,
+    pub key: Option<PrivateKey>
+}
+
+impl fmt::Display for MitmReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "N = {}, q = {}, df = {}: split {} + {}, {} half-keys in {} buckets of {}-bit labels ({:.3} MiB), \
+            {} second halves searched, {} collisions, ",
+            self.params.n(), self.params.q(), self.df, self.split.0, self.split.1, self.table_entries, self.buckets,
+            self.label_bits, self.table_bytes as f64 / (1 << 20) as f64, self.searched, self.collisions)?;
+        match self.key {
+            Some(_) => write!(f, "key recovered"),
+            None => write!(f, "key not recovered")
+        }
+    }
+}
+//=======================================================================================================================
+/// Odlyzko's meet-in-the-middle search for the key of a public key from `ntru_gen_keys`.
+#[cfg(not(feature = "time-measurement"))]
+pub fn meet_in_the_middle_attack <Q: Module> (public_key: &PublicKey<Q>, df: u16) -> MitmReport {
+    odlyzko_search(public_key.h.params(), &public_key.h.to_integers(), df)
+}
+//=======================================================================================================================
+/// Meet-in-the-middle key search (Odlyzko, see Howgrave-Graham, Silverman, Whyte 2003). f = f1 + f2 with f1 on the
+/// first N/2 coefficients and f2 on the others gives f1 * h = -f2 * h + g with ternary g, so f1 * h and -f2 * h
+/// agree in the high bit of every coefficient mod q except next to the boundaries. All f1 (first nonzero coefficient
+/// 1, since -f is a key too) are hashed into buckets by the high bits of their first coefficients of f1 * h, then
+/// each f2 looks up every label within +-1 of -f2 * h and the pairs are accepted if (f1 + f2) * h is ternary mod q.
+///
+/// f of weight 2df - 1 is split into the floor of its share of the first half and the rest. For x^N -+ 1 some
+/// rotation +-x^k * f, again a key, has exactly this split, so the search always succeeds; for x^N - x - 1 only f
+/// itself can be found.
+pub fn odlyzko_search (params: &RingParams, h: &[Integer], df: u16) -> MitmReport {
+    let n = params.n() as usize;
+    let q = params.q() as Integer;
+    let (half, w1, w2) = split(n, df);
+    let label_bits = (mitm_table_size(n, df).log2().ceil() as usize).clamp(1, n.min(MAX_LABEL_BITS));
+
+    // rows[k] = x^k * h mod q
+    let mut rows = vec![h.iter().map(|&x| x.rem_euclid(q)).collect::<Vec<Integer>>()];
+    for k in 1..n {
+        let shifted: Vec<Integer> = std::iter::once(0).chain(rows[k - 1].iter().copied()).collect();
+        rows.push(fold(&shifted, n, params.reduction()).into_iter().map(|x| x.rem_euclid(q)).collect());
+    }
+
+    let mut table: HashMap<u64, Vec<HalfKey>> = HashMap::new();
+    let mut table_entries = 0;
+    for_each_half_key(0..half, w1, true, |first| {
+        let label = label(&partial_product(&rows, &first, label_bits, q, 1), q);
+        table.entry(label).or_default().push(first);
+        table_entries += 1;
+        true
+    });
+    let table_bytes = table.len() * (size_of::<u64>() + size_of::<Vec<HalfKey>>())
+        + table_entries * (size_of::<HalfKey>() + w1 * size_of::<(usize, Integer)>());
+
+    let (mut searched, mut collisions, mut key) = (0, 0, None);
+    for_each_half_key(half..n, w2, false, |second| {
+        searched += 1;
+        let target = partial_product(&rows, &second, label_bits, q, -1);
+        for label in labels_near(&target, q) {
+            for first in table.get(&label).into_iter().flatten() {
+                collisions += 1;
+                key = candidate_key(params, &rows, first, &second);
+                if key.is_some() {
+                    return false;
+                }
+            }
+        }
+        true
+    });
+
+    MitmReport { params: *params, df, split: (w1, w2), label_bits, table_entries, buckets: table.len(), table_bytes,
+        searched, collisions, key }
+}
+//=======================================================================================================================
+/// Number of first halves the search stores.
+pub fn mitm_table_size (n: usize, df: u16) -> f64 {
+    let (half, w1, _) = split(n, df);
+    half_key_count(half, w1, true)
+}
+//=======================================================================================================================
+/// (N/2, nonzero coefficients of f1, nonzero coefficients of f2).
+fn split (n: usize, df: u16) -> (usize, usize, usize) {
+    let weight = 2 * df as usize - 1;
+    let w1 = weight * (n / 2) / n;
+    (n / 2, w1, weight - w1)
+}
+//=======================================================================================================================
+/// Number of vectors `for_each_half_key` visits on `positions` coefficients.
+pub fn half_key_count (positions: usize, weight: usize, positive_first: bool) -> f64 {
+    let binomial = (0..weight).map(|i| (positions - i) as f64 / (i + 1) as f64).product::<f64>();
+    let free_signs = if positive_first { weight.saturating_sub(1) } else { weight };
+    binomial * 2f64.powi(free_signs as i32)
+}
+//=======================================================================================================================
+/// Calls `visit` on every ternary vector with `weight` nonzero coefficients on `positions` (with the first of them
+/// 1 if `positive_first`) until it returns false.
+pub fn for_each_half_key (positions: std::ops::Range<usize>, weight: usize, positive_first: bool, mut visit: impl FnMut(HalfKey) -> bool) {
+    let free_signs = if positive_first { weight.saturating_sub(1) } else { weight };
+    let mut support: Vec<usize> = (0..weight).collect();
+    loop {
+        for mask in 0..1u64 << free_signs {
+            let half_key = support.iter().enumerate().map(|(j, &k)| {
+                let negative = if positive_first { j > 0 && mask >> (j - 1) & 1 == 1 } else { mask >> j & 1 == 1 };
+                (positions.start + k, if negative { -1 } else { 1 })
+            }).collect();
+            if !visit(half_key) {
+                return;
+            }
+        }
+        if !next_combination(&mut support, positions.len()) {
+            return;
+        }
+    }
+}
+//=======================================================================================================================
+/// Next k-element subset of 0..n in lexicographic order, false after the last one.
+fn next_combination (indices: &mut [usize], n: usize) -> bool {
+    let k = indices.len();
+    let Some(i) = (0..k).rev().find(|&i| indices[i] < n - k + i) else { return false };
+    indices[i] += 1;
+    let first = indices[i];
+    for (offset, x) in indices[i + 1..].iter_mut().enumerate() {
+        *x = first + offset + 1;
+    }
+    true
+}
+//=======================================================================================================================
+/// First `len` coefficients of sign * half_key * h mod q.
+fn partial_product (rows: &[Vec<Integer>], half_key: &HalfKey, len: usize, q: Integer, sign: Integer) -> Vec<Integer> {
+    let mut product = vec![0 as Integer; len];
+    for &(k, s) in half_key {
+        for (x, &y) in product.iter_mut().zip(&rows[k]) {
+            *x += s * y;
+        }
+    }
+    product.into_iter().map(|x| (sign * x).rem_euclid(q)).collect()
+}
+//=======================================================================================================================
+/// High bits of the coefficients in [0, q).
+fn label (v: &[Integer], q: Integer) -> u64 {
+    v.iter().enumerate().fold(0, |label, (i, &x)| label | ((x >= q / 2) as u64) << i)
+}
+//=======================================================================================================================
+/// Labels of all v + g with ternary g: a coefficient next to a boundary gives both high bits.
+fn labels_near (v: &[Integer], q: Integer) -> Vec<u64> {
+    let mut labels = vec![label(v, q)];
+    for (i, &x) in v.iter().enumerate() {
+        let high = |x: Integer| x.rem_euclid(q) >= q / 2;
+        if high(x - 1) != high(x) || high(x + 1) != high(x) {
+            let flipped: Vec<u64> = labels.iter().map(|label| label ^ (1 << i)).collect();
+            labels.extend(flipped);
+        }
+    }
+    labels
+}
+//=======================================================================================================================
+/// Private key f = first + second, if f * h is ternary mod q and f is invertible mod p.
+fn candidate_key (params: &RingParams, rows: &[Vec<Integer>], first: &HalfKey, second: &HalfKey) -> Option<PrivateKey> {
+    let n = params.n() as usize;
+    let q = params.q();
+    let support: Vec<(usize, Integer)> = first.iter().chain(second).copied().collect();
+    let g = partial_product(rows, &support, n, q as Integer, 1);
+    if g.iter().any(|&x| mod_center(x, q).abs() > 1) {
+        return None;
+    }
+
+    let mut f = vec![0 as Integer; n];
+    for (k, s) in support {
+        f[k] = s;
+    }
+    let fp = find_inv_polynomial(&PolyXNm1::<ModP>::from_integers(params, &f)).ok()?;
+    Some(PrivateKey { f, fp })
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptoanalysis::chosen_ciphertext::private_key_rotation;
+    use crate::ntru::{SilentObserver, ntru_gen_keys};
+    use crate::polyxnm1::Reduction;
+    use crate::presets::{TOY_11, TOY_53};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use std::collections::HashSet;
+
+    #[test]
+    fn half_key_count_counts_the_visits () {
+        for (positions, weight) in [(5, 0), (5, 1), (6, 3), (9, 4), (7, 7)] {
+            for positive_first in [true, false] {
+                let mut visits = 0;
+                let mut distinct = HashSet::new();
+                for_each_half_key(2..2 + positions, weight, positive_first, |half_key| {
+                    assert_eq!(half_key.len(), weight);
+                    assert!(half_key.iter().all(|&(k, s)| (2..2 + positions).contains(&k) && s.abs() == 1));
+                    assert!(!positive_first || half_key.first().is_none_or(|&(_, s)| s == 1));
+                    distinct.insert(half_key);
+                    visits += 1;
+                    true
+                });
+                assert_eq!(visits as f64, half_key_count(positions, weight, positive_first));
+                assert_eq!(distinct.len(), visits);
+            }
+        }
+
+        let mut visits = 0;
+        for_each_half_key(0..6, 3, true, |_| {
+            visits += 1;
+            visits < 10
+        });
+        assert_eq!(visits, 10);
+    }
+
+    /// At N = 11 other short vectors than the rotations of f can be keys, so only N = 53 has to give a rotation.
+    #[test]
+    fn finds_a_key () {
+        for preset in [TOY_11, TOY_53] {
+            for reduction in [Reduction::Cyclic, Reduction::Negacyclic] {
+                let params = RingParams::with_reduction(preset.n, preset.p, preset.q, reduction);
+                let mut rng = ChaCha20Rng::seed_from_u64(4);
+                let (public_key, private_key) = ntru_gen_keys::<ModQPow2, Integer>(&params, preset.df, preset.dg, &mut rng, &mut SilentObserver);
+
+                let report = odlyzko_search(&params, &public_key.h.to_integers(), preset.df);
+                assert_eq!(report.table_entries as f64, mitm_table_size(preset.n as usize, preset.df));
+                let key = report.key.unwrap_or_else(|| panic!("{} {}: key not recovered", preset.name, reduction));
+                assert_eq!(key.f.iter().filter(|&&x| x != 0).count(), 2 * preset.df as usize - 1);
+                let g = PolyXNm1::<ModQPow2>::from_integers(&params, &key.f) * &public_key.h;
+                assert!(g.to_integers().iter().all(|&x| mod_center(x, preset.q).abs() <= 1));
+                if preset == TOY_53 {
+                    assert!(private_key_rotation::<ModQPow2>(&params, &key, &private_key).is_some(), "{}", reduction);
+                }
+            }
+        }
+    }
+}
+//=======================================================================================================================
@@ -1,83 +1,88 @@
 pub mod lll;
 pub mod bkz;
-
-use std::vec;
-
-#[cfg(not(feature = "time-measurement"))] use crate::cryptoanalysis::lll::squared_norm;
-use crate::polyxnm1::zp::{ModQ, Zp};
-use crate::polyxnm1::{Integer, N, P, Q};
+pub mod ntru_kem;
+pub mod ntru_prime;
+pub mod meet_in_the_middle;
+pub mod parallelepiped;
+pub mod chosen_ciphertext;
+pub mod reaction;
+pub mod hybrid;
+
+#[cfg(not(feature = "time-measurement"))] use crate::cryptoanalysis::lll::integer_squared_norm;
+use crate::polyxnm1::zp::{Module, Zp};
+use crate::polyxnm1::convolution::fold;
+use crate::polyxnm1::integer::RingInteger;
+use crate::polyxnm1::{Integer, RingParams};
 use crate::PolyXNm1;
+use num_traits::{CheckedAdd, CheckedMul, CheckedSub, FromPrimitive, Signed, ToPrimitive};
+use std::fmt;
 //=======================================================================================================================
-pub fn svp_create_lattice_basis (h_poly: &PolyXNm1<ModQ>) -> Vec<Vec<Integer>> {
-    //let q = *Q.get().unwrap();
-    let mut h: Vec<Integer> = h_poly.clone().to_polynomial().coeffs().into_iter().map(|x| x.get()).collect();
-    let basis = create_lattice_basis(&mut h);
+/// Entries of lattice bases. Reduction makes them grow far beyond q, so they may be wider than `Integer`;
+/// all arithmetic on them is checked and panics on overflow.
+pub trait LatticeInteger: Clone + PartialEq + Signed + FromPrimitive + ToPrimitive + From<Integer>
+    + CheckedAdd + CheckedSub + CheckedMul + fmt::Display + fmt::Debug {}
 
-    basis
+impl <T: Clone + PartialEq + Signed + FromPrimitive + ToPrimitive + From<Integer>
+    + CheckedAdd + CheckedSub + CheckedMul + fmt::Display + fmt::Debug> LatticeInteger for T {}
+
+/// Lattice entry type used by the experiments: `Integer` or, with the `lattice-bigint` feature, `BigInt`.
+#[cfg(not(feature = "lattice-bigint"))]
+pub type LatticeInt = Integer;
+
+#[cfg(feature = "lattice-bigint")]
+pub type LatticeInt = num_bigint::BigInt;
+//=======================================================================================================================
+pub fn svp_create_lattice_basis <Q: Module, L: LatticeInteger> (h_poly: &PolyXNm1<Q, impl RingInteger>) -> Vec<Vec<L>> {
+    create_lattice_basis(h_poly.params(), &h_poly.to_integers())
 }
 //=======================================================================================================================
 #[cfg(not(feature = "time-measurement"))]
-pub fn search_potentional_secret_key (basis: &Vec<Vec<Integer>>, df: u8) {
-    let n = *N.get().unwrap() as usize;
-    for i in 0..basis.len() {
-        let f_candidate = &basis[i][0..n];
+pub fn search_potentional_secret_key <L: LatticeInteger> (params: &RingParams, basis: &[Vec<L>], df: u16) {
+    let n = params.n() as usize;
+    for (i, vec) in basis.iter().enumerate() {
+        let f_candidate = &vec[0..n];
 
         // check 1: {-1 0 1} coeffs
-        let is_mod_p = f_candidate.into_iter().all(|&x| x == 0 || x == 1 || x == -1);
-        if is_mod_p == false {
+        let is_mod_p = f_candidate.iter().all(is_ternary);
+        if !is_mod_p {
             continue;
         }
 
         // check 2: df + df - 1 == norm(f)^2
-        let norm_sq: Integer = squared_norm(&f_candidate.to_vec());
-        if norm_sq == (df + df - 1) as Integer {
+        let norm_sq = integer_squared_norm(f_candidate);
+        if norm_sq == L::from((df + df - 1) as Integer) {
             println!("Potentional f found: {:?} (vec_index = {})", f_candidate, i);
-            println!("Potentional g found: {:?}", basis[i][n..2 * n].to_vec());
+            println!("Potentional g found: {:?}", vec[n..2 * n].to_vec());
             break;
         }
     }
 }
 //=======================================================================================================================
-pub fn cvp_create_lattice_basis (h_poly: &PolyXNm1<ModQ>, e_poly: &PolyXNm1<ModQ>, m_coeff: Integer) -> Vec<Vec<Integer>> {
-    let p = *P.get().unwrap() as Integer;
-
-    let mut h: Vec<Integer> = h_poly.clone().to_polynomial().coeffs().into_iter().map(|&x| (Zp::<ModQ>::new(p) * x).get()).collect();
-    let mut basis = create_lattice_basis(&mut h);
-
-    for vec in &mut basis {
-        vec.push(0);
-    }
-
-    let n = *N.get().unwrap() as usize;
-    basis.push(vec![0 as Integer; 2 * n + 1]);
-
-    let e = e_poly.clone().to_polynomial();
-    for i in 0..e.coeffs().len() {
-        basis[2 * n][n + i] = e.coeffs()[i].get();
-    }
-    basis[2 * n][2 * n] = m_coeff;
-
+pub fn cvp_create_lattice_basis <Q: Module, L: LatticeInteger, I: RingInteger> (h_poly: &PolyXNm1<Q, I>, e_poly: &PolyXNm1<Q, I>, m_coeff: Integer) -> Vec<Vec<L>> {
+    let params = h_poly.params();
+    let p = I::from_module(params.p());
 
-    basis
+    let h: Vec<I> = h_poly.coeffs().iter().map(|x| (Zp::<Q, I>::new(p.clone(), params) * x.clone()).get()).collect();
+    create_cvp_lattice_basis(params, &h, &e_poly.to_integers(), m_coeff)
 }
 //=======================================================================================================================
 #[cfg(not(feature = "time-measurement"))]
-pub fn search_potentional_plaintext (basis: &Vec<Vec<Integer>>, dr: u8) {
-    let n = *N.get().unwrap() as usize;
+pub fn search_potentional_plaintext <L: LatticeInteger> (params: &RingParams, basis: &[Vec<L>], dr: u16) {
+    let n = params.n() as usize;
 
-    for i in 0..basis.len() {
-        let r_candidate = &basis[i][0..n];
+    for (i, vec) in basis.iter().enumerate() {
+        let r_candidate = &vec[0..n];
 
         // check 1: {-1 0 1} coeffs
-        let is_mod_p = r_candidate.into_iter().all(|&x| x == 0 || x == 1 || x == -1);
-        if is_mod_p == false {
+        let is_mod_p = r_candidate.iter().all(is_ternary);
+        if !is_mod_p {
             continue;
         }
 
         // check 2: dr + dr == norm(r)^2
-        let norm_sq: Integer = squared_norm(&r_candidate.to_vec());
-        if norm_sq == (dr + dr) as Integer {
-            println!("Potentional (r, m) found: {:?} (vec_index = {}). Except last element!", basis[i], i);
+        let norm_sq = integer_squared_norm(r_candidate);
+        if norm_sq == L::from((dr + dr) as Integer) {
+            println!("Potentional (r, m) found: {:?} (vec_index = {}). Except last element!", vec, i);
             break;
         }
 
@@ -85,36 +90,63 @@ pub fn search_potentional_plaintext (basis: &Vec<Vec<Integer>>, dr: u8) {
 }
 //=======================================================================================================================
 #[inline(always)]
-fn create_lattice_basis (h: &mut Vec<Integer>) -> Vec<Vec<Integer>> {
-    let n = *N.get().unwrap() as usize;
-    let q = *Q.get().unwrap();
+fn create_lattice_basis <L: LatticeInteger, I: RingInteger> (params: &RingParams, h: &[I]) -> Vec<Vec<L>> {
+    let n = params.n() as usize;
+    let q = params.q();
 
-    let mut basis = vec![vec![0 as Integer; 2 * n]; 2 * n];
+    let mut basis = vec![vec![L::zero(); 2 * n]; 2 * n];
     
     // unit matrix
-    for i in 0..n {
-        basis[i][i] = 1;
+    for (i, vec) in basis.iter_mut().enumerate().take(n) {
+        vec[i] = L::one();
     }
 
-    // h matrix
-    if h.len() < n { h.append(&mut vec![0 as Integer; n - h.len()]); }
-
-    for i in 0..n {
-        for j in 0..n {
-            basis[i][j+n] = h[j];
-        }
-        for x in (1..n).rev() {
-            let temp = h[x];
-            h[x] = h[x-1];
-            h[x-1] = temp;
+    // h matrix: the i-th row is x^i * h (circulant for x^N-1, anti-circulant for x^N+1)
+    let mut h = h.to_vec();
+    for vec in basis.iter_mut().take(n) {
+        for (x, y) in vec[n..2 * n].iter_mut().zip(&h) {
+            *x = lattice_entry(y);
         }
+        h.insert(0, I::zero());
+        h = fold(&h, n, params.reduction());
     }
     
     // q matrix
-    for i in n..(2 * n) {
-        basis[i][i] = q as Integer;
+    for (i, vec) in basis.iter_mut().enumerate().skip(n) {
+        vec[i] = lattice_entry(&I::from_module(q));
     }
 
     basis
 }
-//=======================================================================================================================
\ No newline at end of file
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+#[inline(always)]
+fn is_ternary <L: LatticeInteger> (x: &L) -> bool {
+    x.is_zero() || x.abs().is_one()
+}
+//=======================================================================================================================
+/// `x` of a ring of any integer type as a lattice entry; panics if it does not fit `L`.
+#[inline(always)]
+fn lattice_entry <I: RingInteger, L: LatticeInteger> (x: &I) -> L {
+    x.to_i128().and_then(L::from_i128).expect("ring coefficient does not fit the lattice integer")
+}
+//=======================================================================================================================
+/// Lattice of `h` extended by the target row (0, e, m_coeff).
+fn create_cvp_lattice_basis <L: LatticeInteger, I: RingInteger> (params: &RingParams, h: &[I], e: &[I], m_coeff: Integer) -> Vec<Vec<L>> {
+    let mut basis = create_lattice_basis(params, h);
+
+    for vec in &mut basis {
+        vec.push(L::zero());
+    }
+
+    let n = params.n() as usize;
+    basis.push(vec![L::zero(); 2 * n + 1]);
+
+    for (x, y) in basis[2 * n][n..2 * n].iter_mut().zip(e) {
+        *x = lattice_entry(y);
+    }
+    basis[2 * n][2 * n] = L::from(m_coeff);
+
+    basis
+}
+//=======================================================================================================================
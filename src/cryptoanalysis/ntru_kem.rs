@@ -0,0 +1,58 @@
+#[cfg(not(feature = "time-measurement"))] use crate::cryptoanalysis::is_ternary;
+use crate::cryptoanalysis::{LatticeInteger, create_cvp_lattice_basis};
+use crate::polyxnm1::zp::ModQPow2;
+use crate::polyxnm1::{Integer, PolyXNm1};
+#[cfg(not(feature = "time-measurement"))] use crate::polyxnm1::RingParams;
+//=======================================================================================================================
+/// NTRU KEM: c = r * h + Lift(m), where h already contains the factor 3, so (-r, Lift(m), m_coeff) is a short
+/// vector of the lattice of h and c.
+pub fn ntru_kem_cvp_create_lattice_basis <L: LatticeInteger> (h_poly: &PolyXNm1<ModQPow2>, c_poly: &PolyXNm1<ModQPow2>, m_coeff: Integer) -> Vec<Vec<L>> {
+    create_cvp_lattice_basis(h_poly.params(), &h_poly.to_integers(), &c_poly.to_integers(), m_coeff)
+}
+//=======================================================================================================================
+/// h = 3g / f: the SVP basis of h contains (f, 3g) for HPS and (f, 3(x-1)g) for HRSS.
+#[cfg(not(feature = "time-measurement"))]
+pub fn search_potentional_ntru_kem_key <L: LatticeInteger> (params: &RingParams, basis: &[Vec<L>]) {
+    let n = params.n() as usize;
+    let three = L::from(3);
+    let six = L::from(6);
+
+    for (i, vec) in basis.iter().enumerate() {
+        let (f_candidate, g_candidate) = vec.split_at(n);
+
+        // check 1: {-1 0 1} coeffs of f, f != 0
+        if !f_candidate.iter().all(is_ternary) || f_candidate.iter().all(|x| x.is_zero()) {
+            continue;
+        }
+
+        // check 2: coeffs of 3g (or 3(x-1)g) are in {-6 -3 0 3 6}
+        if g_candidate.iter().all(|x| [L::zero(), three.clone(), six.clone()].contains(&x.abs())) {
+            println!("Potentional f found: {:?} (vec_index = {})", f_candidate, i);
+            println!("Potentional 3g found: {:?}", g_candidate.to_vec());
+            break;
+        }
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+pub fn search_potentional_ntru_kem_plaintext <L: LatticeInteger> (params: &RingParams, basis: &[Vec<L>]) {
+    let n = params.n() as usize;
+    let two = L::from(2);
+
+    for (i, vec) in basis.iter().enumerate() {
+        let (r_candidate, m_candidate) = vec.split_at(n);
+
+        // check 1: {-1 0 1} coeffs of r, r != 0
+        if !r_candidate.iter().all(is_ternary) || r_candidate.iter().all(|x| x.is_zero()) {
+            continue;
+        }
+
+        // check 2: coeffs of Lift(m) are in {-2 -1 0 1 2}
+        if m_candidate[..n].iter().all(|x| is_ternary(x) || x.abs() == two) {
+            println!("Potentional r found: {:?} (vec_index = {}). Up to sign!", r_candidate, i);
+            println!("Potentional Lift(m) found: {:?}", m_candidate[..n].to_vec());
+            break;
+        }
+    }
+}
+//=======================================================================================================================
@@ -0,0 +1,59 @@
+#[cfg(not(feature = "time-measurement"))] use crate::cryptoanalysis::is_ternary;
+use crate::cryptoanalysis::{LatticeInteger, create_cvp_lattice_basis, create_lattice_basis};
+use crate::polyxnm1::zp::{ModQ, Zp};
+use crate::polyxnm1::{Integer, PolyXNm1};
+#[cfg(not(feature = "time-measurement"))] use crate::polyxnm1::RingParams;
+//=======================================================================================================================
+/// Streamlined NTRU Prime: h = g / (3f), so f * 3h = g and (f, g) is a short vector of the lattice of 3h.
+pub fn ntru_prime_svp_create_lattice_basis <L: LatticeInteger> (h_poly: &PolyXNm1<ModQ>) -> Vec<Vec<L>> {
+    let params = h_poly.params();
+    let three = Zp::<ModQ>::new(3, params);
+
+    let h: Vec<Integer> = h_poly.coeffs().iter().map(|&x| (three * x).get()).collect();
+    create_lattice_basis(params, &h)
+}
+//=======================================================================================================================
+/// c = Round(h * r) = h * r + e with small e, so (-r, e, m_coeff) is a short vector of the lattice of h and c.
+pub fn ntru_prime_cvp_create_lattice_basis <L: LatticeInteger> (h_poly: &PolyXNm1<ModQ>, c_poly: &PolyXNm1<ModQ>, m_coeff: Integer) -> Vec<Vec<L>> {
+    create_cvp_lattice_basis(h_poly.params(), &h_poly.to_integers(), &c_poly.to_integers(), m_coeff)
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+pub fn search_potentional_ntru_prime_key <L: LatticeInteger> (params: &RingParams, basis: &[Vec<L>], w: usize) {
+    let n = params.n() as usize;
+    for (i, vec) in basis.iter().enumerate() {
+        let (f_candidate, g_candidate) = vec.split_at(n);
+
+        // check 1: {-1 0 1} coeffs of f and g
+        if !f_candidate.iter().all(is_ternary) || !g_candidate[..n].iter().all(is_ternary) {
+            continue;
+        }
+
+        // check 2: weight(f) == w
+        if f_candidate.iter().filter(|x| !x.is_zero()).count() == w {
+            println!("Potentional f found: {:?} (vec_index = {})", f_candidate, i);
+            println!("Potentional g found: {:?}", g_candidate[..n].to_vec());
+            break;
+        }
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+pub fn search_potentional_ntru_prime_plaintext <L: LatticeInteger> (params: &RingParams, basis: &[Vec<L>], w: usize) {
+    let n = params.n() as usize;
+    for (i, vec) in basis.iter().enumerate() {
+        let (r_candidate, e_candidate) = vec.split_at(n);
+
+        // check 1: {-1 0 1} coeffs of r and of the rounding error
+        if !r_candidate.iter().all(is_ternary) || !e_candidate[..n].iter().all(is_ternary) {
+            continue;
+        }
+
+        // check 2: weight(r) == w
+        if r_candidate.iter().filter(|x| !x.is_zero()).count() == w {
+            println!("Potentional r found: {:?} (vec_index = {}). Up to sign!", r_candidate, i);
+            break;
+        }
+    }
+}
+//=======================================================================================================================
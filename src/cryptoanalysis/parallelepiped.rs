@@ -0,0 +1,177 @@
+use crate::polyxnm1::{Integer, PolyXNm1, zp::*};
+use crate::polyxnm1::service::mod_center;
+use rand::Rng;
+
+/// Step of the gradient descent for samples uniform in a hypercube [-1, 1]^n (Nguyen and Regev).
+const DESCENT_STEP: f64 = 0.75;
+const MAX_DESCENT_ITERATIONS: usize = 1000;
+//=======================================================================================================================
+/// Learning a parallelepiped (Nguyen, Regev 2006). Every sample is x * B with x uniform in [-1/2, 1/2]^n and a
+/// secret basis B. The samples 2 * x * B are morphed into a hypercube with their covariance B^T B / 3, then the
+/// fourth moment is minimized over the unit sphere from `attempts` random starts: its minima are the rows of the
+/// hypercube, which map back to rows of +-B. Returns the rounded rows, without duplicates.
+pub fn learn_parallelepiped (samples: &[Vec<Integer>], attempts: usize, rng: &mut impl Rng) -> Vec<Vec<Integer>> {
+    let n = samples[0].len();
+    let count = samples.len() as f64;
+
+    let doubled: Vec<Vec<f64>> = samples.iter().map(|v| v.iter().map(|&x| 2.0 * x as f64).collect()).collect();
+    let mut covariance = vec![vec![0.0; n]; n];
+    for v in &doubled {
+        for (row, &x) in covariance.iter_mut().zip(v) {
+            for (c, &y) in row.iter_mut().zip(v) {
+                *c += 3.0 * x * y / count;
+            }
+        }
+    }
+
+    // covariance = R * R^T; c = R^-1 * v is uniform in the hypercube spanned by the orthonormal rows of B * R^-T
+    let r = cholesky(&covariance);
+    let cube: Vec<Vec<f64>> = doubled.iter().map(|v| forward_substitution(&r, v)).collect();
+
+    let mut rows: Vec<Vec<Integer>> = Vec::new();
+    for _ in 0..attempts {
+        let w = descend(&cube, random_unit_vector(n, rng));
+        let row: Vec<Integer> = (0..n).map(|i| (0..=i).map(|j| r[i][j] * w[j]).sum::<f64>().round() as Integer).collect();
+        let negated: Vec<Integer> = row.iter().map(|&x| -x).collect();
+        if !rows.contains(&row) && !rows.contains(&negated) {
+            rows.push(row);
+        }
+    }
+    rows
+}
+//=======================================================================================================================
+/// Rows (a, b) found by `learn_parallelepiped` that are vectors of the NTRU lattice (b = a * h mod q), compared with
+/// the rotations x^k * (a, b) of the named secret rows.
+#[cfg_attr(feature = "time-measurement", allow(dead_code))]
+pub fn search_potentional_sign_key <Q: Module> (h: &PolyXNm1<Q>, rows: &[Vec<Integer>], secret: &[(&str, Vec<Integer>)]) {
+    let params = h.params();
+    let n = params.n() as usize;
+    let q = params.q();
+
+    for (i, row) in rows.iter().enumerate() {
+        let (a, b) = row.split_at(n);
+        let ah = PolyXNm1::<Q>::from_integers(params, a) * h;
+        if !ah.to_integers().iter().zip(b).all(|(&x, &y)| mod_center(x - y, q) == 0) {
+            continue;
+        }
+
+        match secret_rotation(row, secret) {
+            Some((name, k, sign)) => println!("Secret basis vector found: {}x^{} * {} = {:?} (row {})", sign, k, name, row, i),
+            None => println!("Lattice vector found: {:?} (row {})", row, i)
+        }
+    }
+}
+//=======================================================================================================================
+/// Number of rows that are rotations +-x^k * (a, b) of one of the secret rows.
+#[cfg_attr(not(feature = "time-measurement"), allow(dead_code))]
+pub fn count_secret_rows (rows: &[Vec<Integer>], secret: &[(&str, Vec<Integer>)]) -> usize {
+    rows.iter().filter(|row| secret_rotation(row, secret).is_some()).count()
+}
+//=======================================================================================================================
+/// (name, k, sign) with row = sign x^k * secret row, rotating both halves of the row.
+fn secret_rotation<'a> (row: &[Integer], secret: &[(&'a str, Vec<Integer>)]) -> Option<(&'a str, usize, &'static str)> {
+    let n = row.len() / 2;
+    secret.iter().find_map(|&(name, ref s)| (0..n).find_map(|k| {
+        let rotated: Vec<Integer> = (0..2 * n).map(|j| s[j / n * n + (j % n + n - k) % n]).collect();
+        if rotated == row {
+            Some((name, k, ""))
+        }
+        else if rotated.iter().zip(row).all(|(&x, &y)| x == -y) {
+            Some((name, k, "-"))
+        }
+        else {
+            None
+        }
+    }))
+}
+//=======================================================================================================================
+/// Gradient descent of mom4(w) = E[<c, w>^4] on the unit sphere, until the moment stops decreasing.
+fn descend (cube: &[Vec<f64>], mut w: Vec<f64>) -> Vec<f64> {
+    let mut moment = fourth_moment(cube, &w);
+    for _ in 0..MAX_DESCENT_ITERATIONS {
+        let mut gradient = vec![0.0; w.len()];
+        for c in cube {
+            let dot = dot(c, &w);
+            for (g, &x) in gradient.iter_mut().zip(c) {
+                *g += 4.0 * dot * dot * dot * x / cube.len() as f64;
+            }
+        }
+
+        let next = normalized(w.iter().zip(&gradient).map(|(&x, &g)| x - DESCENT_STEP * g).collect());
+        let next_moment = fourth_moment(cube, &next);
+        if next_moment >= moment {
+            break;
+        }
+        (w, moment) = (next, next_moment);
+    }
+    w
+}
+//=======================================================================================================================
+fn fourth_moment (cube: &[Vec<f64>], w: &[f64]) -> f64 {
+    cube.iter().map(|c| dot(c, w).powi(4)).sum::<f64>() / cube.len() as f64
+}
+//=======================================================================================================================
+fn random_unit_vector (n: usize, rng: &mut impl Rng) -> Vec<f64> {
+    normalized((0..n).map(|_| rng.random_range(-1.0..1.0)).collect())
+}
+//=======================================================================================================================
+/// Lower triangular R with a = R * R^T.
+fn cholesky (a: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let n = a.len();
+    let mut r = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| r[i][k] * r[j][k]).sum();
+            r[i][j] = if i == j { (a[i][i] - sum).sqrt() } else { (a[i][j] - sum) / r[j][j] };
+        }
+    }
+    r
+}
+//=======================================================================================================================
+/// R^-1 * v for lower triangular R.
+fn forward_substitution (r: &[Vec<f64>], v: &[f64]) -> Vec<f64> {
+    let mut x = vec![0.0; v.len()];
+    for i in 0..v.len() {
+        let sum: f64 = (0..i).map(|j| r[i][j] * x[j]).sum();
+        x[i] = (v[i] - sum) / r[i][i];
+    }
+    x
+}
+//=======================================================================================================================
+#[inline(always)]
+fn dot (a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+//=======================================================================================================================
+fn normalized (v: Vec<f64>) -> Vec<f64> {
+    let norm = dot(&v, &v).sqrt();
+    v.into_iter().map(|x| x / norm).collect()
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ntru_sign::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    /// A transcript of 5000 toy signatures is enough to learn rotations of the secret rows.
+    #[test]
+    fn learns_a_secret_row_from_signatures () {
+        let params = ntru_sign_params(11, 64);
+        let mut rng = ChaCha20Rng::seed_from_u64(3);
+        let (public_key, secret_key) = ntru_sign_gen_keys::<ModQPow2>(&params, 4, 4, &mut rng);
+        let transcript: Vec<Vec<Integer>> = (0..5000u64).map(|i| {
+            let message = i.to_be_bytes();
+            ntru_sign_error_vector(&public_key.h, &message, &ntru_sign::<ModQPow2>(&params, &secret_key, &message))
+        }).collect();
+
+        let rows = learn_parallelepiped(&transcript, 8, &mut rng);
+        let secret = [
+            ("(f, g)", [secret_key.f.clone(), secret_key.g.clone()].concat()),
+            ("(F, G)", [secret_key.big_f.clone(), secret_key.big_g.clone()].concat())
+        ];
+        assert!(count_secret_rows(&rows, &secret) > 0);
+    }
+}
+//=======================================================================================================================
@@ -0,0 +1,311 @@
+use crate::ntru::{Ciphertext, Plaintext, PrivateKey, PublicKey, SilentObserver, ntru_decrypt, ntru_encrypt_with_blinding};
+use crate::polyxnm1::{Integer, PolyXNm1, Reduction, RingParams, zp::*};
+use crate::polyxnm1::inverse::find_inv_polynomial;
+use crate::polyxnm1::service::mod_center;
+use crate::ternary::sample_ternary;
+use rand::Rng;
+use std::fmt;
+
+/// Queries per step of the ciphertext search: a batch without failures makes the ciphertexts more likely to fail,
+/// more than a 1 / MAX_FAILURE_SHARE share of failures less likely (a failure is most informative when it is rare).
+const SEARCH_BATCH: usize = 200;
+const MAX_FAILURE_SHARE: usize = 8;
+/// Step of the amplitude of m in the search.
+const AMPLITUDE_STEP: f64 = 0.25;
+/// m has about M_DENSITY_FACTOR times as many nonzero coefficients as f (all of them at most): coefficients of m
+/// off the support of f only blur the failures.
+const M_DENSITY_FACTOR: f64 = 2.0;
+/// Each time the number of failures doubles, they are aligned again from the running sum and from as many single
+/// failures as references, with this many passes each, and the most concentrated sum is kept.
+const REALIGN_REFERENCES: usize = 8;
+const REALIGN_PASSES: usize = 3;
+/// After a realignment, f is also tried without any `SPARE_COEFFS` of the 2df - 1 + SPARE_COEFFS largest coefficients.
+const SPARE_COEFFS: usize = 2;
+//=======================================================================================================================
+/// Reaction oracle: tells only whether a ciphertext decrypts to the message it was made from, and counts the queries.
+pub struct FailureOracle<'a> {
+    key: &'a PrivateKey,
+    queries: usize
+}
+
+impl <'a> FailureOracle<'a> {
+    pub fn new (key: &'a PrivateKey) -> FailureOracle<'a> {
+        FailureOracle { key, queries: 0 }
+    }
+
+    pub fn decrypts <Q: Module> (&mut self, cipher: &Ciphertext<Q>, message: &Plaintext) -> bool {
+        self.queries += 1;
+        ntru_decrypt(self.key, cipher, &mut SilentObserver) == *message
+    }
+
+    pub fn queries (&self) -> usize {
+        self.queries
+    }
+}
+//=======================================================================================================================
+/// Result of `reaction_attack`.
+pub struct ReactionReport {
+    pub params: RingParams,
+    pub df: u16,
+    pub dg: u16,
+    pub dr: u16,
+    /// Weight of r (r_weight ones and minus ones) and mean |m_i| the search ended with.
+    pub r_weight: u16,
+    pub m_amplitude: f64,
+    pub queries: usize,
+    pub failures: usize,
+    pub key: Option<PrivateKey>
+}
+
+impl fmt::Display for ReactionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "N = {}, q = {}, df = {}, dg = {}, dr = {}: r of weight {}, |m_i| = {:.2}, {} queries, {} failures, ",
+            self.params.n(), self.params.q(), self.df, self.dg, self.dr, 2 * self.r_weight, self.m_amplitude,
+            self.queries, self.failures)?;
+        match self.key {
+            Some(_) => write!(f, "key recovered"),
+            None => write!(f, "key not recovered")
+        }
+    }
+}
+//=======================================================================================================================
+/// Decryption-failure attack (Howgrave-Graham et al., 2003). Decryption of e = p*r*h + m fails iff some coefficient
+/// a_i = <x^i * z, (g, f)> of a = p*r*g + f*m leaves the centered range mod q, with z = (p * R(r), R(m)) and
+/// R(v)_k = (x^k * v)_0. So the z of failing ciphertexts, rotated by the unknown i and signed, correlate with (g, f):
+/// every failure is aligned to the running sum by the rotation and sign of the largest correlation, and the 2df - 1
+/// largest coefficients of the f half are tried as the key.
+///
+/// The ciphertexts are honest encryptions with known r and m, except that the nonzero coefficients of m are
+/// +-floor(t) or +-ceil(t) with mean t (only m mod p is compared to the decryption): the search starts with r of
+/// weight 2dr and t = 1 and steers the share of failures by t, and by the weight of r once t is at its minimum.
+pub fn reaction_attack <Q: Module> (public_key: &PublicKey<Q>, (df, dg, dr): (u16, u16, u16), oracle: &mut FailureOracle, max_queries: usize, rng: &mut impl Rng) -> ReactionReport {
+    let params = public_key.h.params();
+    assert_ne!(params.reduction(), Reduction::NtruPrime, "reaction attack: x^N-x-1 is not supported");
+    let n = params.n() as usize;
+    let p = params.p();
+    let negacyclic = params.reduction() == Reduction::Negacyclic;
+
+    let (mut r_weight, mut m_amplitude) = (dr, 1.0);
+    let m_density = (M_DENSITY_FACTOR * (2 * df - 1) as f64 / n as f64).min(1.0);
+    let mut samples: Vec<Vec<f64>> = Vec::new();
+    let mut sum = vec![0.0; 2 * n];
+    let mut batch_failures = 0;
+    let mut key = None;
+    while key.is_none() && oracle.queries() < max_queries {
+        if oracle.queries() > 0 && oracle.queries().is_multiple_of(SEARCH_BATCH) {
+            if batch_failures == 0 {
+                if r_weight < dr { r_weight += 1 } else { m_amplitude += AMPLITUDE_STEP }
+            }
+            else if batch_failures * MAX_FAILURE_SHARE > SEARCH_BATCH {
+                if m_amplitude > AMPLITUDE_STEP { m_amplitude -= AMPLITUDE_STEP } else { r_weight = r_weight.saturating_sub(1) }
+            }
+            batch_failures = 0;
+        }
+
+        let r = sample_ternary(n, r_weight as usize, r_weight as usize, rng);
+        let m: Vec<Integer> = (0..n).map(|_| {
+            if !rng.random_bool(m_density) {
+                return 0;
+            }
+            let abs = m_amplitude.floor() as Integer + rng.random_bool(m_amplitude.fract()) as Integer;
+            if rng.random_bool(0.5) { abs } else { -abs }
+        }).collect();
+        let e = ntru_encrypt_with_blinding(public_key, &r, &Plaintext { m: m.clone() });
+
+        let message = Plaintext { m: m.iter().map(|&x| mod_center(x, p)).collect() };
+        if oracle.decrypts(&e, &message) {
+            continue;
+        }
+        batch_failures += 1;
+
+        let z: Vec<f64> = functional(&r, negacyclic).into_iter().map(|x| p as f64 * x).chain(functional(&m, negacyclic)).collect();
+        if samples.is_empty() {
+            sum = z.clone();
+        }
+        else {
+            add_aligned(&mut sum, &z, negacyclic);
+        }
+        samples.push(z);
+
+        // early alignments against a noisy sum are redone, and the weakest coefficients of f are double-checked
+        if samples.len().is_power_of_two() {
+            sum = best_alignment(&samples, &sum, df, negacyclic, rng);
+            key = candidate_key(public_key, df, &sum[n..], SPARE_COEFFS);
+        }
+        else {
+            key = candidate_key(public_key, df, &sum[n..], 0);
+        }
+    }
+
+    ReactionReport { params: *params, df, dg, dr, r_weight, m_amplitude, queries: oracle.queries(), failures: samples.len(), key }
+}
+//=======================================================================================================================
+/// R(v)_k = (x^k * v)_0, so that (v * u)_0 = <R(v), u>.
+fn functional (v: &[Integer], negacyclic: bool) -> Vec<f64> {
+    let n = v.len();
+    (0..n).map(|k| match k {
+        0 => v[0] as f64,
+        _ if negacyclic => -v[n - k] as f64,
+        _ => v[n - k] as f64
+    }).collect()
+}
+//=======================================================================================================================
+/// x^i * v.
+fn rotated (v: &[f64], i: usize, negacyclic: bool) -> Vec<f64> {
+    let n = v.len();
+    (0..n).map(|k| match k {
+        _ if k >= i => v[k - i],
+        _ if negacyclic => -v[k + n - i],
+        _ => v[k + n - i]
+    }).collect()
+}
+//=======================================================================================================================
+/// Both halves of z rotated by the i and signed by the sign of the largest |<x^i * z, sum>|.
+fn aligned (sum: &[f64], z: &[f64], negacyclic: bool) -> Vec<f64> {
+    let n = z.len() / 2;
+    let rotate = |i: usize| -> Vec<f64> {
+        rotated(&z[..n], i, negacyclic).into_iter().chain(rotated(&z[n..], i, negacyclic)).collect()
+    };
+
+    let (i, correlation) = (0..n)
+        .map(|i| (i, rotate(i).iter().zip(sum).map(|(x, y)| x * y).sum::<f64>()))
+        .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+        .unwrap();
+    let sign = if correlation < 0.0 { -1.0 } else { 1.0 };
+    rotate(i).into_iter().map(|x| sign * x).collect()
+}
+
+fn add_aligned (sum: &mut [f64], z: &[f64], negacyclic: bool) {
+    let z = aligned(sum, z, negacyclic);
+    for (s, x) in sum.iter_mut().zip(z) {
+        *s += x;
+    }
+}
+
+/// Sum of all failures aligned from the running sum or from a random failure, whichever puts more weight on the
+/// 2df - 1 largest coefficients of the f half.
+fn best_alignment (samples: &[Vec<f64>], sum: &[f64], df: u16, negacyclic: bool, rng: &mut impl Rng) -> Vec<f64> {
+    let n = sum.len() / 2;
+    let concentration = |sum: &[f64]| {
+        top_indices(&sum[n..], 2 * df as usize - 1).iter().map(|&k| sum[n + k].abs()).sum::<f64>() / sum[n..].iter().map(|x| x.abs()).sum::<f64>()
+    };
+
+    let references = std::iter::once(sum.to_vec())
+        .chain((0..REALIGN_REFERENCES).map(|_| samples[rng.random_range(0..samples.len())].clone()));
+    references.map(|mut reference| {
+        for _ in 0..REALIGN_PASSES {
+            reference = realigned(samples, &reference, negacyclic);
+        }
+        reference
+    }).max_by(|a, b| concentration(a).total_cmp(&concentration(b))).unwrap()
+}
+
+fn realigned (samples: &[Vec<f64>], reference: &[f64], negacyclic: bool) -> Vec<f64> {
+    let mut sum = vec![0.0; reference.len()];
+    for z in samples {
+        for (s, x) in sum.iter_mut().zip(aligned(reference, z, negacyclic)) {
+            *s += x;
+        }
+    }
+    sum
+}
+//=======================================================================================================================
+/// f with the signs of 2df - 1 of the 2df - 1 + spare largest |estimate_k|, if f * h is ternary mod q (f is then
+/// +-x^j times the key).
+fn candidate_key <Q: Module> (public_key: &PublicKey<Q>, df: u16, estimate: &[f64], spare: usize) -> Option<PrivateKey> {
+    let params = public_key.h.params();
+    let top = top_indices(estimate, 2 * df as usize - 1 + spare);
+
+    combinations(top.len(), spare).into_iter().find_map(|dropped| {
+        let mut f = vec![0 as Integer; estimate.len()];
+        for (_, &k) in top.iter().enumerate().filter(|(i, _)| !dropped.contains(i)) {
+            f[k] = if estimate[k] < 0.0 { -1 } else { 1 };
+        }
+
+        let g = PolyXNm1::<Q>::from_integers(params, &f) * &public_key.h;
+        if g.coeffs().iter().any(|x| mod_center(x.get(), params.q()).abs() > 1) {
+            return None;
+        }
+        let fp = find_inv_polynomial(&PolyXNm1::<ModP>::from_integers(params, &f)).ok()?;
+        Some(PrivateKey { f, fp })
+    })
+}
+//=======================================================================================================================
+/// Indices of the `count` largest |v_k|, largest first.
+fn top_indices (v: &[f64], count: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..v.len()).collect();
+    order.sort_by(|&a, &b| v[b].abs().total_cmp(&v[a].abs()));
+    order.truncate(count);
+    order
+}
+//=======================================================================================================================
+/// All k-element subsets of 0..n, in lexicographic order.
+fn combinations (n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    (k - 1..n).flat_map(|last| combinations(last, k - 1).into_iter().map(move |mut subset| {
+        subset.push(last);
+        subset
+    })).collect()
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptoanalysis::chosen_ciphertext::private_key_rotation;
+    use crate::ntru::ntru_gen_keys;
+    use crate::polyxnm1::convolution::ring_product;
+    use crate::presets::TOY_53;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    const REDUCTIONS: [Reduction; 2] = [Reduction::Cyclic, Reduction::Negacyclic];
+
+    fn product (a: &[Integer], b: &[Integer], reduction: Reduction) -> Vec<f64> {
+        ring_product(a, b, a.len(), reduction).iter().map(|&x| x as f64).collect()
+    }
+
+    #[test]
+    fn functional_gives_the_constant_coefficient () {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for reduction in REDUCTIONS {
+            let v = sample_ternary(7, 3, 2, &mut rng);
+            let u: Vec<Integer> = (0..7).map(|_| rng.random_range(-5..=5)).collect();
+            let r = functional(&v, reduction == Reduction::Negacyclic);
+            assert_eq!(r.iter().zip(&u).map(|(&x, &y)| x * y as f64).sum::<f64>(), product(&v, &u, reduction)[0]);
+        }
+    }
+
+    #[test]
+    fn rotated_multiplies_by_a_monomial () {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        for reduction in REDUCTIONS {
+            let v: Vec<Integer> = (0..7).map(|_| rng.random_range(-5..=5)).collect();
+            let v_f64: Vec<f64> = v.iter().map(|&x| x as f64).collect();
+            for i in 0..7 {
+                let mut monomial = vec![0 as Integer; 7];
+                monomial[i] = 1;
+                assert_eq!(rotated(&v_f64, i, reduction == Reduction::Negacyclic), product(&monomial, &v, reduction));
+            }
+        }
+    }
+
+    /// q = 64 instead of 128 makes the toy set fail often enough to recover its key within a few thousand queries.
+    #[test]
+    fn recovers_a_rotation_of_the_key () {
+        let max_queries = 20000;
+        for reduction in REDUCTIONS {
+            let params = RingParams::with_reduction(TOY_53.n, TOY_53.p, 64, reduction);
+            let mut rng = ChaCha20Rng::seed_from_u64(0);
+            let (public_key, private_key) = ntru_gen_keys::<ModQPow2, Integer>(&params, TOY_53.df, TOY_53.dg, &mut rng, &mut SilentObserver);
+            let mut oracle = FailureOracle::new(&private_key);
+
+            let report = reaction_attack(&public_key, (TOY_53.df, TOY_53.dg, TOY_53.dr), &mut oracle, max_queries, &mut rng);
+            assert!(report.queries <= max_queries);
+            let key = report.key.unwrap_or_else(|| panic!("{}: no key after {} queries", reduction, max_queries));
+            assert!(private_key_rotation::<ModQPow2>(&params, &key, &private_key).is_some());
+        }
+    }
+}
+//=======================================================================================================================
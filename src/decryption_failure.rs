@@ -0,0 +1,135 @@
+use crate::ntru::{SilentObserver, gen_m, gen_polynomial, message_coeff_distribution, ntru_decrypt, ntru_encrypt_with_blinding, ntru_gen_keys};
+use crate::polyxnm1::{Integer, PolyXNm1, RingParams, Wide, zp::*};
+use crate::polyxnm1::convolution::ring_product;
+use crate::polyxnm1::service::mod_center;
+use crate::params_check::{gaussian_failure_probability, union_over_coeffs};
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::fmt;
+//=======================================================================================================================
+/// Result of `estimate_decryption_failures`: decryption of e = p*r*h + m fails iff some coefficient of
+/// a = p*r*g + f*m (computed over Z) leaves the centered interval [-(q-1)/2, q/2].
+pub struct FailureReport {
+    pub params: RingParams,
+    pub trials: usize,
+    pub failures: usize,
+    /// Number of trials by max |a_i|.
+    pub max_coeff_histogram: BTreeMap<Integer, usize>,
+    /// Normal approximation of every coefficient of a, union over the N coefficients.
+    pub gaussian_estimate: f64,
+    /// Exact distribution of one coefficient of a for independent coefficients of r, g, f and m, union over N.
+    pub convolution_estimate: f64
+}
+//=======================================================================================================================
+/// Monte-Carlo run of `trials` fresh key pairs and messages through `ntru_gen_keys`, `ntru_encrypt_with_blinding`
+/// and `ntru_decrypt`, with the analytic estimates for the same parameters.
+pub fn estimate_decryption_failures <Q: Module> (params: &RingParams, df: u16, dg: u16, dr: u16, trials: usize, rng: &mut impl Rng) -> FailureReport {
+    let n = params.n() as usize;
+    let p = params.p() as Integer;
+    let reduction = params.reduction();
+
+    let mut failures = 0;
+    let mut max_coeff_histogram = BTreeMap::new();
+    for _ in 0..trials {
+        let (public_key, private_key) = ntru_gen_keys::<Q, Integer>(params, df, dg, rng, &mut SilentObserver);
+        let r = gen_polynomial(params, dr, dr, rng);
+        let m = gen_m(params, rng);
+
+        let cipher = ntru_encrypt_with_blinding(&public_key, &r, &m);
+        if ntru_decrypt(&private_key, &cipher, &mut SilentObserver) != m {
+            failures += 1;
+        }
+
+        // g = f * h is ternary, so it is recovered exactly from its residues mod q
+        let f = &private_key.f;
+        let g: Vec<Integer> = (PolyXNm1::<Q>::from_integers(params, f) * &public_key.h).to_integers().into_iter()
+            .map(|x| mod_center(x, params.q()))
+            .collect();
+        let rg = ring_product(&r, &g, n, reduction);
+        let fm = ring_product(f, &m.m, n, reduction);
+        let max_coeff = rg.iter().zip(&fm).map(|(&x, &y)| (p as Wide * x + y).abs() as Integer).max().unwrap_or(0);
+        *max_coeff_histogram.entry(max_coeff).or_insert(0) += 1;
+    }
+
+    FailureReport {
+        params: *params,
+        trials,
+        failures,
+        max_coeff_histogram,
+        gaussian_estimate: gaussian_failure_probability(params, df, dg, dr),
+        convolution_estimate: convolution_estimate(params, df, dg, dr)
+    }
+}
+//=======================================================================================================================
+/// a_i = sum over N terms p * r_j * g_(i-j) plus sum over N terms f_j * m_(i-j), each term drawn independently.
+fn convolution_estimate (params: &RingParams, df: u16, dg: u16, dr: u16) -> f64 {
+    let n = params.n() as usize;
+    let p = params.p() as Integer;
+    let nf = n as f64;
+
+    let rg_term = [(-p, 2.0 * dr as f64 * dg as f64 / (nf * nf)), (p, 2.0 * dr as f64 * dg as f64 / (nf * nf))];
+    let f_coeff = [(1, df as f64 / nf), (-1, (df as f64 - 1.0) / nf)];
+    let fm_term: Vec<(Integer, f64)> = f_coeff.iter()
+        .flat_map(|&(f, f_prob)| message_coeff_distribution(params).into_iter().map(move |(m, m_prob)| (f * m, f_prob * m_prob)))
+        .collect();
+
+    let mut distribution = BTreeMap::from([(0 as Integer, 1.0)]);
+    for term in [&rg_term[..], &fm_term[..]] {
+        for _ in 0..n {
+            distribution = add_term(&distribution, term);
+        }
+    }
+
+    let limit = (params.q() as Integer - 1) / 2;
+    let coeff_failure: f64 = distribution.iter().filter(|&(&x, _)| x < -limit || x > params.q() as Integer / 2).map(|(_, &prob)| prob).sum();
+    union_over_coeffs(coeff_failure, params.n() as f64)
+}
+//=======================================================================================================================
+/// Distribution of X + T for independent X and T, where T is the listed values (zero takes the remaining mass).
+fn add_term (distribution: &BTreeMap<Integer, f64>, term: &[(Integer, f64)]) -> BTreeMap<Integer, f64> {
+    let zero_prob = 1.0 - term.iter().map(|&(_, prob)| prob).sum::<f64>();
+    let mut result = BTreeMap::new();
+    for (&x, &x_prob) in distribution {
+        *result.entry(x).or_insert(0.0) += x_prob * zero_prob;
+        for &(t, t_prob) in term {
+            *result.entry(x + t).or_insert(0.0) += x_prob * t_prob;
+        }
+    }
+    result
+}
+//=======================================================================================================================
+impl fmt::Display for FailureReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let q = self.params.q() as Integer;
+        writeln!(f, "N = {}, p = {}, q = {}, {}: {} trials", self.params.n(), self.params.p(), q, self.params.reduction(), self.trials)?;
+        writeln!(f, "empirical failure rate: {} / {} = {:.3e}", self.failures, self.trials, self.failures as f64 / self.trials.max(1) as f64)?;
+        writeln!(f, "gaussian estimate:      {:.3e}", self.gaussian_estimate)?;
+        writeln!(f, "convolution estimate:   {:.3e}", self.convolution_estimate)?;
+        writeln!(f, "max |p*r*g + f*m| (q/2 = {}):", q / 2)?;
+        for (&max_coeff, &count) in &self.max_coeff_histogram {
+            let marker = if max_coeff > (q - 1) / 2 { " (may fail)" } else { "" };
+            writeln!(f, "{:>8}: {}{}", max_coeff, count, marker)?;
+        }
+        Ok(())
+    }
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn failures_match_the_exact_coefficients () {
+        // odd q: decryption fails iff some |a_i| > (q - 1) / 2
+        for q in [23, 29, 41] {
+            let params = RingParams::new(31, 3, q);
+            let report = estimate_decryption_failures::<ModQ>(&params, 6, 5, 5, 200, &mut ChaCha20Rng::seed_from_u64(7));
+            let outside: usize = report.max_coeff_histogram.range((q as Integer - 1) / 2 + 1..).map(|(_, &count)| count).sum();
+            assert_eq!(report.failures, outside, "q = {}", q);
+            assert_eq!(report.max_coeff_histogram.values().sum::<usize>(), report.trials);
+        }
+    }
+}
+//=======================================================================================================================
@@ -0,0 +1,266 @@
+use crate::ntru_kem::*;
+use aes::Aes256;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use rand::RngCore;
+use rand::rand_core::impls;
+use std::{fmt, fs, io};
+
+const SEED_BYTES: usize = 48;
+//=======================================================================================================================
+/// AES-256 CTR_DRBG of the NIST PQC `rng.c` (no derivation function, no personalization, no reseeding):
+/// every `fill_bytes` call is one `randombytes` call, so the KEM consumes the stream exactly like the reference code.
+pub struct AesCtrDrbg {
+    key: [u8; 32],
+    v: [u8; 16]
+}
+
+impl AesCtrDrbg {
+    pub fn new (entropy_input: &[u8; SEED_BYTES]) -> Self {
+        let mut drbg = AesCtrDrbg { key: [0; 32], v: [0; 16] };
+        drbg.update(Some(entropy_input));
+        drbg
+    }
+
+    fn update (&mut self, provided_data: Option<&[u8; SEED_BYTES]>) {
+        let mut temp = [0u8; SEED_BYTES];
+        for block in temp.chunks_mut(16) {
+            block.copy_from_slice(&self.next_block());
+        }
+        if let Some(data) = provided_data {
+            for (x, &y) in temp.iter_mut().zip(data) {
+                *x ^= y;
+            }
+        }
+        self.key.copy_from_slice(&temp[..32]);
+        self.v.copy_from_slice(&temp[32..]);
+    }
+
+    /// V = V + 1 (big endian), output AES_K(V).
+    fn next_block (&mut self) -> [u8; 16] {
+        for x in self.v.iter_mut().rev() {
+            *x = x.wrapping_add(1);
+            if *x != 0 {
+                break;
+            }
+        }
+
+        let mut block = self.v.into();
+        Aes256::new(&self.key.into()).encrypt_block(&mut block);
+        block.into()
+    }
+}
+
+impl RngCore for AesCtrDrbg {
+    fn next_u32 (&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64 (&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes (&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(16) {
+            let block = self.next_block();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        self.update(None);
+    }
+}
+//=======================================================================================================================
+#[derive(Debug)]
+pub enum KatError {
+    Io(io::Error),
+    /// Malformed line of the .rsp file (1-based line number).
+    Parse { line: usize, message: String },
+    /// First record whose `field` differs from the value computed by the KEM.
+    Mismatch { count: usize, field: &'static str }
+}
+
+impl fmt::Display for KatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KatError::Io(error) => write!(f, "cannot read KAT file: {}", error),
+            KatError::Parse { line, message } => write!(f, "KAT file, line {}: {}", line, message),
+            KatError::Mismatch { count, field } => write!(f, "KAT mismatch: count = {}, field `{}`", count, field)
+        }
+    }
+}
+
+impl From<io::Error> for KatError {
+    fn from(error: io::Error) -> Self {
+        KatError::Io(error)
+    }
+}
+//=======================================================================================================================
+/// One record of a `PQCkemKAT_*.rsp` file.
+#[derive(Default)]
+struct KatRecord {
+    count: usize,
+    /// Line of the seed, or of the count if the record has no seed.
+    seed_line: usize,
+    seed: Vec<u8>,
+    pk: Vec<u8>,
+    sk: Vec<u8>,
+    ct: Vec<u8>,
+    ss: Vec<u8>
+}
+//=======================================================================================================================
+/// Runs every record of a NIST KAT file: the DRBG is seeded with the record's seed, then keypair, encapsulation and
+/// decapsulation are compared with pk, sk, ct and ss. Returns the number of records checked.
+pub fn run_kat_file (params: &NtruKemParams, path: &str) -> Result<usize, KatError> {
+    run_kat_text(params, &fs::read_to_string(path)?)
+}
+//=======================================================================================================================
+fn run_kat_text (params: &NtruKemParams, text: &str) -> Result<usize, KatError> {
+    let records = parse_kat_records(text)?;
+
+    for record in &records {
+        let seed: &[u8; SEED_BYTES] = record.seed.as_slice().try_into()
+            .map_err(|_| KatError::Parse { line: record.seed_line, message: format!("count = {}: seed is not {} bytes", record.count, SEED_BYTES) })?;
+        let mut drbg = AesCtrDrbg::new(seed);
+        let mismatch = |field| KatError::Mismatch { count: record.count, field };
+
+        let (pk, sk) = ntru_kem_keypair(params, &mut drbg);
+        if pk != record.pk {
+            return Err(mismatch("pk"));
+        }
+        if sk != record.sk {
+            return Err(mismatch("sk"));
+        }
+
+        let (ct, ss) = ntru_kem_encapsulate(params, &pk, &mut drbg);
+        if ct != record.ct {
+            return Err(mismatch("ct"));
+        }
+        if ss[..] != record.ss[..] {
+            return Err(mismatch("ss"));
+        }
+
+        if ntru_kem_decapsulate(params, &sk, &ct)[..] != record.ss[..] {
+            return Err(mismatch("ss (decapsulation)"));
+        }
+    }
+    Ok(records.len())
+}
+//=======================================================================================================================
+/// `key = value` lines, records separated by blank lines, `#` comments.
+fn parse_kat_records (text: &str) -> Result<Vec<KatRecord>, KatError> {
+    let mut records = Vec::new();
+    let mut record: Option<KatRecord> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parse_error = |message: String| KatError::Parse { line: i + 1, message };
+
+        let (key, value) = line.split_once('=').ok_or_else(|| parse_error(format!("expected `key = value`, got `{}`", line)))?;
+        let (key, value) = (key.trim(), value.trim());
+
+        if key == "count" {
+            records.extend(record.take());
+            let count = value.parse().map_err(|_| parse_error(format!("invalid count `{}`", value)))?;
+            record = Some(KatRecord { count, seed_line: i + 1, ..Default::default() });
+            continue;
+        }
+
+        let current = record.as_mut().ok_or_else(|| parse_error(format!("`{}` before the first count", key)))?;
+        let field = match key {
+            "seed" => {
+                current.seed_line = i + 1;
+                &mut current.seed
+            }
+            "pk" => &mut current.pk,
+            "sk" => &mut current.sk,
+            "ct" => &mut current.ct,
+            "ss" => &mut current.ss,
+            _ => return Err(parse_error(format!("unknown field `{}`", key)))
+        };
+        *field = from_hex(value).ok_or_else(|| parse_error(format!("invalid hex value of `{}`", key)))?;
+    }
+    records.extend(record);
+
+    Ok(records)
+}
+//=======================================================================================================================
+fn from_hex (s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first two seeds of every NIST PQC KAT file: `randombytes(48)` after seeding `rng.c` with 0, 1, ..., 47.
+    #[test]
+    fn drbg_matches_the_nist_rng () {
+        let entropy_input: [u8; SEED_BYTES] = std::array::from_fn(|i| i as u8);
+        let mut drbg = AesCtrDrbg::new(&entropy_input);
+        for expected in ["061550234D158C5EC95595FE04EF7A25767F2E24CC2BC479D09D86DC9ABCFDE7056A8C266F9EF97ED08541DBD2E1FFA1",
+                         "D81C4D8D734FCBFBEADE3D3F8A039FAA2A2C9957E835AD55B22E75BF57BB556AC81ADDE6AEEB4A5A875C3BFCADFA958F"] {
+            let mut seed = [0u8; SEED_BYTES];
+            drbg.fill_bytes(&mut seed);
+            assert_eq!(seed.to_vec(), from_hex(expected).unwrap());
+        }
+    }
+
+    fn to_hex (bytes: &[u8]) -> String {
+        bytes.iter().map(|x| format!("{:02X}", x)).collect()
+    }
+
+    /// Record `count = 0` in the layout of the NIST files, with the first seed of `drbg_matches_the_nist_rng` and the
+    /// values computed by the KEM: checks the driver, not the KEM against the reference implementation.
+    fn kem_record (params: &NtruKemParams) -> Vec<(&'static str, String)> {
+        let entropy_input: [u8; SEED_BYTES] = std::array::from_fn(|i| i as u8);
+        let mut seed = [0u8; SEED_BYTES];
+        AesCtrDrbg::new(&entropy_input).fill_bytes(&mut seed);
+
+        let mut drbg = AesCtrDrbg::new(&seed);
+        let (pk, sk) = ntru_kem_keypair(params, &mut drbg);
+        let (ct, ss) = ntru_kem_encapsulate(params, &pk, &mut drbg);
+        vec![("count", "0".to_string()), ("seed", to_hex(&seed)), ("pk", to_hex(&pk)), ("sk", to_hex(&sk)),
+             ("ct", to_hex(&ct)), ("ss", to_hex(&ss))]
+    }
+
+    fn rsp_text (record: &[(&str, String)]) -> String {
+        let lines: Vec<String> = record.iter().map(|(key, value)| format!("{} = {}", key, value)).collect();
+        format!("# ntruhps2048509\n\n{}\n\n", lines.join("\n"))
+    }
+
+    #[test]
+    fn checks_every_field_of_a_record () {
+        let params = NTRU_HPS2048509;
+        let record = kem_record(&params);
+        assert_eq!(run_kat_text(&params, &rsp_text(&record)).unwrap(), 1);
+
+        for field in ["pk", "sk", "ct", "ss"] {
+            let mut tampered = record.clone();
+            let value = &mut tampered.iter_mut().find(|(key, _)| *key == field).unwrap().1;
+            let flipped = if value.starts_with('0') { "1" } else { "0" };
+            value.replace_range(0..1, flipped);
+            match run_kat_text(&params, &rsp_text(&tampered)) {
+                Err(KatError::Mismatch { count: 0, field: mismatch }) => assert_eq!(mismatch, field),
+                other => panic!("{}: {:?}", field, other)
+            }
+        }
+    }
+
+    #[test]
+    fn reports_the_line_of_a_bad_seed () {
+        let text = "# ntruhps2048509\n\ncount = 0\nseed = 0001\npk = 00\n";
+        match run_kat_text(&NTRU_HPS2048509, text) {
+            Err(KatError::Parse { line, .. }) => assert_eq!(line, 4),
+            other => panic!("{:?}", other)
+        }
+        match run_kat_text(&NTRU_HPS2048509, "count = 0\npk = 00\n") {
+            Err(KatError::Parse { line, .. }) => assert_eq!(line, 1),
+            other => panic!("{:?}", other)
+        }
+    }
+}
+//=======================================================================================================================
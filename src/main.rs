@@ -1,126 +1,745 @@
+mod cli;
 mod polyxnm1;
 mod ntru;
+mod ternary;
+#[cfg_attr(feature = "time-measurement", allow(dead_code))] mod message;
+#[cfg_attr(feature = "time-measurement", allow(dead_code))] mod naep;
+#[cfg_attr(feature = "time-measurement", allow(dead_code))] mod kat;
+mod decryption_failure;
+mod ntru_sign;
+mod ntru_kem;
+mod ntru_prime;
 mod cryptoanalysis;
+mod serialization;
+mod params_check;
+mod presets;
+#[cfg(feature = "time-measurement")] mod benchmark;
 
+use cli::{Args, Mode};
 use polyxnm1::PolyXNm1;
+use polyxnm1::RingParams;
 use polyxnm1::UInteger;
+use polyxnm1::Integer;
+use polyxnm1::integer::{IntegerType, RingInteger};
 use ntru::*;
+#[cfg(not(feature = "time-measurement"))] use message::*;
+#[cfg(not(feature = "time-measurement"))] use naep::*;
+use decryption_failure::*;
+use ntru_sign::*;
+use cryptoanalysis::parallelepiped::*;
+use cryptoanalysis::chosen_ciphertext::*;
+use cryptoanalysis::reaction::*;
+use cryptoanalysis::hybrid::*;
+use serialization::*;
+use params_check::{check_ntru_prime_params, check_params};
+use presets::NtruParams;
+use cryptoanalysis::LatticeInt;
 use cryptoanalysis::svp_create_lattice_basis;
 use cryptoanalysis::cvp_create_lattice_basis;
 use cryptoanalysis::bkz::bkz;
+use cryptoanalysis::meet_in_the_middle::*;
+use ntru_kem::*;
+use ntru_prime::*;
+use cryptoanalysis::ntru_kem::*;
+use cryptoanalysis::ntru_prime::*;
 
 #[cfg(not(feature = "time-measurement"))] use cryptoanalysis::search_potentional_secret_key;
 #[cfg(not(feature = "time-measurement"))] use cryptoanalysis::search_potentional_plaintext;
-#[cfg(not(feature = "time-measurement"))] use polyxnm1::{init_polynomial_ring, zp::*};
+use polyxnm1::zp::*;
 
 #[cfg(feature = "time-measurement")] use rust_xlsxwriter::XlsxError;
 #[cfg(feature = "time-measurement")] use std::time::Duration;
-#[cfg(feature = "time-measurement")] use polyxnm1::init_polynomial_ring;
-#[cfg(feature = "time-measurement")] use polyxnm1::zp::ModQ;
 #[cfg(feature = "time-measurement")] use rust_xlsxwriter::*;
 
+use num_bigint::BigInt;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
 use std::str::FromStr;
 use std::fmt::Debug;
 use std::time::Instant;
 use std::io::{self, Write};
+
+/// Source of every random choice of a run: ChaCha20 seeded with the run seed, so a run can be regenerated exactly.
+type ExperimentRng = ChaCha20Rng;
+
+/// SVP and CVP bases of one key pair and ciphertext.
+#[cfg(feature = "time-measurement")]
+type LatticeBases = (Vec<Vec<LatticeInt>>, Vec<Vec<LatticeInt>>);
+
+/// h and df of a key pair generated from NTRU parameters, for the meet-in-the-middle search.
+#[cfg(feature = "time-measurement")]
+type MitmTarget = (Vec<Integer>, u16);
+
 //=======================================================================================================================
 #[cfg(not(feature = "time-measurement"))]
 fn main() {
+    let args = Args::from_env();
+    let seed = args.seed.unwrap_or_else(rand::random);
+    println!("run seed = {} (repeat with `cargo run -- seed {}`)", seed, seed);
+    let mut rng = ExperimentRng::seed_from_u64(seed);
+
+    match &args.mode {
+        Mode::NtruKem(kem_params) => match &args.kat_file {
+            Some(path) => ntru_kem_kat(kem_params, path),
+            None => ntru_kem_session(kem_params, &mut rng)
+        },
+        Mode::NtruPrime => ntru_prime_main(&args, &mut rng),
+        Mode::Load(prefix) => ntru_load_main(prefix),
+        Mode::FailureRate => failure_rate_main(&args, &mut rng),
+        Mode::NtruSign => ntru_sign_main(&args, &mut rng),
+        Mode::Cca => cca_main(&args, &mut rng),
+        Mode::Reaction => reaction_main(&args, &mut rng),
+        Mode::Mitm => mitm_main(&args, &mut rng),
+        Mode::Hybrid => hybrid_main(&args, &mut rng),
+        Mode::Ntru => ntru_main(&args, &mut rng),
+        Mode::Bench => println!("bench measures times: run it with `--features time-measurement`")
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_main (args: &Args, rng: &mut ExperimentRng) {
+    let ntru_params = input_ntru_params(args);
+
+    print!("Input message (empty for a random m): ");
+    let message: String = stdin_read();
+
+    let Some(params) = checked_params(&ntru_params, args) else { return; };
+    let NtruParams { df, dg, dr, .. } = ntru_params;
+
+    match args.integer {
+        IntegerType::I32 => ntru_ring_session::<i32>(&params, df, dg, dr, &message, args, rng),
+        IntegerType::I64 => ntru_ring_session::<i64>(&params, df, dg, dr, &message, args, rng),
+        IntegerType::I128 => ntru_ring_session::<i128>(&params, df, dg, dr, &message, args, rng),
+        IntegerType::BigInt => ntru_ring_session::<BigInt>(&params, df, dg, dr, &message, args, rng)
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_ring_session <I: RingInteger> (params: &RingParams, df: u16, dg: u16, dr: u16, message: &str, args: &Args, rng: &mut ExperimentRng) {
+    println!("ring integer: {}", I::NAME);
+    if params.q().is_power_of_two() {
+        ntru_session::<ModQPow2, I>(params, df, dg, dr, message, args, rng);
+    }
+    else {
+        ntru_session::<ModQ, I>(params, df, dg, dr, message, args, rng);
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn failure_rate_main (args: &Args, rng: &mut ExperimentRng) {
+    let ntru_params = input_ntru_params(args);
+
+    print!("Input number of trials: ");
+    let trials: usize = stdin_read();
+
+    let Some(params) = checked_params(&ntru_params, args) else { return; };
+    let NtruParams { df, dg, dr, .. } = ntru_params;
+    let report = if params.q().is_power_of_two() {
+        estimate_decryption_failures::<ModQPow2>(&params, df, dg, dr, trials, rng)
+    }
+    else {
+        estimate_decryption_failures::<ModQ>(&params, df, dg, dr, trials, rng)
+    };
+    print!("{}", report);
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_sign_main (args: &Args, rng: &mut ExperimentRng) {
     print!("Input n: ");
     let n: UInteger = stdin_read();
-    
-    print!("Input p: ");
-    let p: UInteger = stdin_read();
 
     print!("Input q: ");
     let q: UInteger = stdin_read();
 
     print!("Input df: ");
-    let df: u8 = stdin_read();
+    let df: u16 = stdin_read();
 
     print!("Input dg: ");
-    let dg: u8 = stdin_read();
+    let dg: u16 = stdin_read();
 
-    print!("Input dr: ");
-    let dr: u8 = stdin_read();
-    
-    // let n: UInteger = 11;
-    // let p: UInteger = 3;
-    // let q: UInteger = 32;
-    // let df = 4u8;
-    // let dg = 3u8;
-    // let dr = 3u8;
+    print!("Input number of signatures: ");
+    let count: usize = stdin_read();
+
+    let params = ntru_sign_params(n, q).with_inversion(args.inversion);
+    if q.is_power_of_two() {
+        ntru_sign_session::<ModQPow2>(&params, df, dg, count, rng);
+    }
+    else {
+        ntru_sign_session::<ModQ>(&params, df, dg, count, rng);
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_sign_session <Q: Module> (params: &RingParams, df: u16, dg: u16, count: usize, rng: &mut ExperimentRng) {
+    println!("\nNTRUSign: GEN_KEYS");
+    let (public_key, secret_key) = ntru_sign_gen_keys::<Q>(params, df, dg, rng);
+    println!("f = {:?}\ng = {:?}\nF = {:?}\nG = {:?}", secret_key.f, secret_key.g, secret_key.big_f, secret_key.big_g);
+    println!("h = {}\nnorm bound = {:.2}", public_key.h, public_key.norm_bound);
+
+    println!("\nNTRUSign: SIGN");
+    let signature = ntru_sign::<Q>(params, &secret_key, b"message");
+    println!("s = {:?}", signature);
+    println!("verify(\"message\") = {}", ntru_sign_verify(&public_key, b"message", &signature));
+    println!("verify(\"massage\") = {}", ntru_sign_verify(&public_key, b"massage", &signature));
 
-    init_polynomial_ring(n, p, q);
+    println!("\nNTRUSign: PARALLELEPIPED ATTACK ({} signatures)", count);
+    let transcript: Vec<Vec<Integer>> = (0..count).map(|i| {
+        let message = (i as u64).to_be_bytes();
+        ntru_sign_error_vector(&public_key.h, &message, &ntru_sign::<Q>(params, &secret_key, &message))
+    }).collect();
+
+    let start = Instant::now();
+    let rows = learn_parallelepiped(&transcript, 4 * params.n() as usize, rng);
+    println!("Execution time: {:?}", start.elapsed());
+
+    let secret = [
+        ("(f, g)", [secret_key.f.clone(), secret_key.g.clone()].concat()),
+        ("(F, G)", [secret_key.big_f.clone(), secret_key.big_g.clone()].concat())
+    ];
+    search_potentional_sign_key(&public_key.h, &rows, &secret);
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn cca_main (args: &Args, rng: &mut ExperimentRng) {
+    let ntru_params = input_ntru_params(args);
 
-    let m = gen_m();
-    println!("message m = {}", m.to_string());
-    let m = PolyXNm1::<ModQ>::from_polynomial(m);
+    print!("Input max number of oracle queries: ");
+    let max_queries: usize = stdin_read();
 
+    let Some(params) = checked_params(&ntru_params, args) else { return; };
+    let NtruParams { df, dg, dr, .. } = ntru_params;
+    if params.q().is_power_of_two() {
+        cca_session::<ModQPow2>(&params, df, dg, dr, max_queries, rng);
+    }
+    else {
+        cca_session::<ModQ>(&params, df, dg, dr, max_queries, rng);
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn cca_session <Q: Module> (params: &RingParams, df: u16, dg: u16, dr: u16, max_queries: usize, rng: &mut ExperimentRng) {
     println!("\nNTRUEncrypt: GEN_KEYS");
-    let (h, (f, fp)) = ntru_gen_keys(df, dg);
-    println!("h = {}", h.to_string());
+    let (public_key, private_key) = ntru_gen_keys::<Q, Integer>(params, df, dg, rng, &mut SilentObserver);
+    println!("f = {:?}\nh = {}", private_key.f, public_key);
 
-    println!("\nNTRUEncrypt: ENCRYPT");
-    let e = ntru_encrypt(dr, &h, &m);
-    println!("e = {}", e.to_string());
+    println!("\nJAULMES-JOUX CHOSEN-CIPHERTEXT ATTACK");
+    let mut oracle = DecryptionOracle::new(&private_key);
+    let start = Instant::now();
+    let recovered = jaulmes_joux_attack(&public_key, df, &mut oracle, max_queries, rng);
+    println!("Execution time: {:?}", start.elapsed());
 
-    println!("\nNTRUEncrypt: DECRYPT");
-    let m = ntru_decrypt((&f, &fp), &e);
-    println!("m = {}", m.to_string());
+    let Some(recovered) = recovered else {
+        println!("no key found after {} oracle queries", oracle.queries());
+        return;
+    };
+    println!("recovered f = {:?} after {} oracle queries", recovered.f, oracle.queries());
+    print_key_rotation::<Q>(params, &recovered, &private_key);
 
-    println!("\nNTRUEncrypt: LATTICE CRYPTOANALYSIS:\nSECRET KEY ATTACK:");
-    let mut basis = svp_create_lattice_basis(&h);
-    println!("basis:");
-    for i in 0..basis.len() {
-        for j in 0..basis[0].len() {
-            print!("{} ", basis[i][j]);
+    let m = gen_m(params, rng);
+    let e = ntru_encrypt(dr, &public_key, &m, rng, &mut SilentObserver);
+    println!("decrypts with the recovered key: {}", ntru_decrypt(&recovered, &e, &mut SilentObserver) == m);
+}
+//=======================================================================================================================
+/// Prints which rotation +-x^k * f of the private key an attack recovered.
+#[cfg(not(feature = "time-measurement"))]
+fn print_key_rotation <Q: Module> (params: &RingParams, recovered: &PrivateKey, private_key: &PrivateKey) {
+    match private_key_rotation::<Q>(params, recovered, private_key) {
+        Some((k, sign)) => println!("recovered f = {}x^{} * f", if sign < 0 { "-" } else { "" }, k),
+        None => println!("recovered f is not a rotation of f")
+    }
+}
+//=======================================================================================================================
+/// The reaction attack on the parameter set and on the same weights with q/2 and 2q.
+#[cfg(not(feature = "time-measurement"))]
+fn reaction_main (args: &Args, rng: &mut ExperimentRng) {
+    let ntru_params = input_ntru_params(args);
+
+    print!("Input max number of oracle queries: ");
+    let max_queries: usize = stdin_read();
+
+    let Some(params) = checked_params(&ntru_params, args) else { return; };
+    let NtruParams { df, dg, dr, .. } = ntru_params;
+
+    println!("\nDECRYPTION FAILURE ATTACK");
+    for q in [params.q() / 2, params.q(), 2 * params.q()] {
+        let params = if q == params.q() {
+            params
         }
-        println!();
+        else {
+            println!("\nq = {}:", q);
+            let Some(params) = checked_params(&NtruParams { q, ..ntru_params }, args) else {
+                println!("skipping q = {}", q);
+                continue;
+            };
+            params
+        };
+        if q.is_power_of_two() {
+            reaction_session::<ModQPow2>(&params, (df, dg, dr), max_queries, rng);
+        }
+        else {
+            reaction_session::<ModQ>(&params, (df, dg, dr), max_queries, rng);
+        }
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn reaction_session <Q: Module> (params: &RingParams, weights: (u16, u16, u16), max_queries: usize, rng: &mut ExperimentRng) {
+    let (public_key, private_key) = ntru_gen_keys::<Q, Integer>(params, weights.0, weights.1, rng, &mut SilentObserver);
+
+    let mut oracle = FailureOracle::new(&private_key);
+    let start = Instant::now();
+    let report = reaction_attack(&public_key, weights, &mut oracle, max_queries, rng);
+    println!("{} ({:?})", report, start.elapsed());
+
+    if let Some(key) = &report.key {
+        print_key_rotation::<Q>(params, key, &private_key);
     }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn mitm_main (args: &Args, rng: &mut ExperimentRng) {
+    let ntru_params = input_ntru_params(args);
+
+    print!("Input max number of stored half-keys: ");
+    let max_entries: usize = stdin_read();
+
+    let Some(params) = checked_params(&ntru_params, args) else { return; };
+    let NtruParams { df, dg, .. } = ntru_params;
+    if params.q().is_power_of_two() {
+        mitm_session::<ModQPow2>(&params, df, dg, max_entries, rng);
+    }
+    else {
+        mitm_session::<ModQ>(&params, df, dg, max_entries, rng);
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn mitm_session <Q: Module> (params: &RingParams, df: u16, dg: u16, max_entries: usize, rng: &mut ExperimentRng) {
+    println!("\nNTRUEncrypt: GEN_KEYS");
+    let (public_key, private_key) = ntru_gen_keys::<Q, Integer>(params, df, dg, rng, &mut SilentObserver);
+    println!("f = {:?}\nh = {}", private_key.f, public_key);
+
+    println!("\nODLYZKO MEET-IN-THE-MIDDLE SEARCH");
+    let table_size = mitm_table_size(params.n() as usize, df);
+    if table_size > max_entries as f64 {
+        println!("the table of {:.3e} half-keys exceeds the limit", table_size);
+        return;
+    }
+    let start = Instant::now();
+    let report = meet_in_the_middle_attack(&public_key, df);
+    println!("{} ({:?})", report, start.elapsed());
+
+    if let Some(key) = &report.key {
+        print_key_rotation::<Q>(params, key, &private_key);
+    }
+}
+//=======================================================================================================================
+/// The hybrid attack on one key pair for every number of guessed coefficients in a range, for cost curves.
+#[cfg(not(feature = "time-measurement"))]
+fn hybrid_main (args: &Args, rng: &mut ExperimentRng) {
+    let ntru_params = input_ntru_params(args);
+
+    print!("Input BKZ block size: ");
+    let block_size: usize = stdin_read();
+
+    print!("Input min number of guessed coefficients: ");
+    let min_guessed: usize = stdin_read();
+
+    print!("Input max number of guessed coefficients: ");
+    let max_guessed: usize = stdin_read();
+
+    print!("Input step: ");
+    let step: usize = stdin_read();
+
+    print!("Input max number of stored half-guesses: ");
+    let max_entries: usize = stdin_read();
+
+    let Some(params) = checked_params(&ntru_params, args) else { return; };
+    let NtruParams { df, dg, .. } = ntru_params;
+    let guessed: Vec<usize> = (min_guessed..=max_guessed.min(params.n() as usize - 1)).step_by(step.max(1)).collect();
+    if params.q().is_power_of_two() {
+        hybrid_session::<ModQPow2>(&params, df, dg, block_size, &guessed, max_entries, rng);
+    }
+    else {
+        hybrid_session::<ModQ>(&params, df, dg, block_size, &guessed, max_entries, rng);
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn hybrid_session <Q: Module> (params: &RingParams, df: u16, dg: u16, block_size: usize, guessed: &[usize], max_entries: usize, rng: &mut ExperimentRng) {
+    println!("\nNTRUEncrypt: GEN_KEYS");
+    let (public_key, private_key) = ntru_gen_keys::<Q, Integer>(params, df, dg, rng, &mut SilentObserver);
+    println!("f = {:?}\nh = {}", private_key.f, public_key);
+
+    println!("\nHYBRID LATTICE-REDUCTION / MEET-IN-THE-MIDDLE ATTACK");
+    for &r in guessed {
+        let table_size = hybrid_table_size(params.n() as usize, df, r);
+        if table_size > max_entries as f64 {
+            println!("r = {}: the table of {:.3e} half-guesses exceeds the limit", r, table_size);
+            continue;
+        }
+        let report = hybrid_attack(&public_key, df, r, block_size);
+        println!("{}", report);
+
+        if let Some(key) = &report.key {
+            print_key_rotation::<Q>(params, key, &private_key);
+        }
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_session <Q: Module, I: RingInteger> (params: &RingParams, df: u16, dg: u16, dr: u16, message: &str, args: &Args, rng: &mut ExperimentRng) {
+    println!("\nNTRUEncrypt: GEN_KEYS");
+    let (public_key, private_key) = ntru_gen_keys::<Q, I>(params, df, dg, rng, &mut PrintObserver);
+    println!("h = {}", public_key);
+
+    let ciphertexts = if args.naep {
+        match ntru_naep_round_trip(&public_key, &private_key, dr, message, rng) {
+            Some(ciphertexts) => ciphertexts,
+            None => return
+        }
+    }
+    else {
+        ntru_round_trip(&public_key, &private_key, dr, message, rng)
+    };
+    let e = &ciphertexts[0];
+
+    if let Some((prefix, format)) = &args.save {
+        save_instance(prefix, *format, &public_key, &private_key, e);
+    }
+
+    ntru_attack(params, &public_key.h, &e.e, df, dr);
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_attack <Q: Module, I: RingInteger> (params: &RingParams, h: &PolyXNm1<Q, I>, e: &PolyXNm1<Q, I>, df: u16, dr: u16) {
+    let n = params.n();
+
+    println!("\nNTRUEncrypt: LATTICE CRYPTOANALYSIS:\nSECRET KEY ATTACK:");
+    let mut basis = svp_create_lattice_basis::<Q, LatticeInt>(h);
+    println!("basis:");
+    print_basis(&basis, false);
     
     println!("BKZ");
     let start = Instant::now();
     bkz(&mut basis, 2 * n as usize, 0.99);
     let duration = start.elapsed();
 
-    for i in 0..basis.len() {
-        print!("{}:\t", i);
-        for j in 0..basis[0].len() {
-            print!("{} ", basis[i][j]);
-        }
-        println!();
-    }
+    print_basis(&basis, true);
     println!("Execution time: {:?}", duration);
 
     println!("\nSECRET KEY SEARCHING");
-    search_potentional_secret_key(&basis, df);
+    search_potentional_secret_key(params, &basis, df);
 
     println!("PLAINTEXT ATTACK:");
-    let mut basis = cvp_create_lattice_basis(&h, &e, 1);
+    let mut basis = cvp_create_lattice_basis::<Q, LatticeInt, I>(h, e, 1);
     println!("basis:");
-    for i in 0..basis.len() {
-        for j in 0..basis[0].len() {
-            print!("{} ", basis[i][j]);
-        }
-        println!();
-    }
+    print_basis(&basis, false);
     println!("BKZ");
     let start = Instant::now();
     bkz(&mut basis, 2 * n as usize + 1, 0.99);
     let duration = start.elapsed();
     
-    for i in 0..basis.len() {
-        print!("{}:\t", i);
-        for j in 0..basis[0].len() {
-            print!("{} ", basis[i][j]);
+    print_basis(&basis, true);
+    println!("Execution time: {:?}", duration);
+
+    println!("\nPLAINTEXT SEARCHING");
+    search_potentional_plaintext(params, &basis, dr);
+}
+//=======================================================================================================================
+/// Writes `<prefix>.params`, `<prefix>.pub`, `<prefix>.key` and `<prefix>.ct` (the first ciphertext).
+/// The files store `Integer` coefficients, so a ring whose modules do not fit `Integer` is not saved.
+fn save_instance <Q: Module, I: RingInteger> (prefix: &str, format: FileFormat, public_key: &PublicKey<Q, I>, private_key: &PrivateKey, cipher: &Ciphertext<Q, I>) {
+    let converted = public_key.h.change_integer::<Integer>().and_then(|h| Ok((h, cipher.e.change_integer::<Integer>()?)));
+    let (h, e) = match converted {
+        Ok(instance) => instance,
+        Err(error) => {
+            println!("cannot save {}: {}", prefix, error);
+            return;
+        }
+    };
+    let files = [
+        ("params", NtruFile::<Q>::Params(*h.params())),
+        ("pub", NtruFile::PublicKey(h)),
+        ("key", NtruFile::PrivateKey(private_key.clone())),
+        ("ct", NtruFile::Ciphertext(e))
+    ];
+    for (extension, file) in files {
+        let path = format!("{}.{}", prefix, extension);
+        match file.save(&path, format) {
+            Ok(()) => println!("saved {}", path),
+            Err(error) => println!("cannot save {}: {}", path, error)
+        }
+    }
+}
+//=======================================================================================================================
+/// Attacks an instance saved by `cargo run -- save <prefix>`; the private key file is optional and only used to
+/// decrypt.
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_load_main (prefix: &str) {
+    let params = match load_params(&format!("{}.params", prefix)) {
+        Ok(params) => params,
+        Err(error) => {
+            println!("cannot load {}.params: {}", prefix, error);
+            return;
+        }
+    };
+
+    if params.q().is_power_of_two() {
+        ntru_load_session::<ModQPow2>(prefix);
+    }
+    else {
+        ntru_load_session::<ModQ>(prefix);
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_load_session <Q: Module> (prefix: &str) {
+    let (h, e) = match load_instance::<Q>(prefix) {
+        Ok(instance) => instance,
+        Err(error) => {
+            println!("cannot load instance {}: {}", prefix, error);
+            return;
+        }
+    };
+    let params = *h.params();
+    println!("N = {}, p = {}, q = {}, {}", params.n(), params.p(), params.q(), params.reduction());
+    println!("h = {}", h);
+    println!("e = {}", e);
+
+    if let Ok(private_key) = load_private_key::<Q>(&format!("{}.key", prefix)) {
+        println!("\nNTRUEncrypt: DECRYPT");
+        println!("m = {}", ntru_decrypt(&private_key, &Ciphertext { e: e.clone() }, &mut PrintObserver));
+    }
+
+    print!("Input df: ");
+    let df: u16 = stdin_read();
+
+    print!("Input dr: ");
+    let dr: u16 = stdin_read();
+
+    ntru_attack(&params, &h, &e, df, dr);
+}
+//=======================================================================================================================
+/// Unpadded encryption: a chosen message is split into as many plaintext polynomials as needed.
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_round_trip <Q: Module, I: RingInteger> (public_key: &PublicKey<Q, I>, private_key: &PrivateKey, dr: u16, message: &str, rng: &mut ExperimentRng) -> Vec<Ciphertext<Q, I>> {
+    let params = public_key.h.params();
+    let messages = if message.is_empty() {
+        vec![gen_m(params, rng)]
+    }
+    else {
+        encode_message(params, message.as_bytes()).into_iter().map(|m| Plaintext { m }).collect()
+    };
+
+    println!("\nNTRUEncrypt: ENCRYPT");
+    let ciphertexts: Vec<Ciphertext<Q, I>> = messages.iter().map(|m| {
+        println!("message m = {}", m);
+        let e = ntru_encrypt(dr, public_key, m, rng, &mut PrintObserver);
+        println!("e = {}", e);
+        e
+    }).collect();
+
+    println!("\nNTRUEncrypt: DECRYPT");
+    let decrypted: Vec<Plaintext> = ciphertexts.iter().map(|e| ntru_decrypt(private_key, e, &mut SilentObserver)).collect();
+    for m in &decrypted {
+        println!("m = {}", m);
+    }
+    if !message.is_empty() {
+        let decrypted: Vec<Vec<Integer>> = decrypted.into_iter().map(|m| m.m).collect();
+        match decode_message(params, &decrypted) {
+            Ok(bytes) => println!("message = {:?}", String::from_utf8_lossy(&bytes)),
+            Err(error) => println!("message decoding failed: {}", error)
         }
-        println!();
     }
+    ciphertexts
+}
+//=======================================================================================================================
+/// NAEP-padded encryption: the message is split into blocks of `naep_capacity` bytes.
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_naep_round_trip <Q: Module, I: RingInteger> (public_key: &PublicKey<Q, I>, private_key: &PrivateKey, dr: u16, message: &str, rng: &mut ExperimentRng) -> Option<Vec<Ciphertext<Q, I>>> {
+    let params = public_key.h.params();
+    let capacity = naep_capacity(params);
+    let overhead = LENGTH_BYTES + SALT_BYTES;
+    if capacity == 0 && (!message.is_empty() || plaintext_bytes(params) < overhead) {
+        println!("\nNAEP capacity is 0 bytes: a plaintext polynomial of N = {} holds {} bytes, the length prefix and the salt \
+            take {}", params.n(), plaintext_bytes(params), overhead);
+        return None;
+    }
+    let blocks: Vec<&[u8]> = if message.is_empty() { vec![&[]] } else { message.as_bytes().chunks(capacity.max(1)).collect() };
+
+    println!("\nNTRUEncrypt (NAEP): ENCRYPT, {} bytes per block", capacity);
+    let mut ciphertexts = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        match ntru_naep_encrypt(dr, public_key, block, rng, &mut PrintObserver) {
+            Ok(e) => {
+                println!("e = {}", e);
+                ciphertexts.push(e);
+            }
+            Err(error) => {
+                println!("NAEP encryption failed: {}", error);
+                return None;
+            }
+        }
+    }
+
+    println!("\nNTRUEncrypt (NAEP): DECRYPT");
+    let mut decrypted = Vec::new();
+    for e in &ciphertexts {
+        match ntru_naep_decrypt(private_key, public_key, dr, e) {
+            Ok(block) => decrypted.extend(block),
+            Err(error) => println!("NAEP decryption failed: {}", error)
+        }
+    }
+    println!("message = {:?}", String::from_utf8_lossy(&decrypted));
+
+    // a ciphertext with a flipped coefficient is rejected
+    let mut tampered = ciphertexts[0].e.to_integers();
+    tampered[0] += I::one();
+    let tampered = Ciphertext { e: PolyXNm1::from_integers(params, &tampered) };
+    println!("tampered ciphertext: {:?}", ntru_naep_decrypt(private_key, public_key, dr, &tampered).map(|m| String::from_utf8_lossy(&m).into_owned()));
+
+    Some(ciphertexts)
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_prime_main (args: &Args, rng: &mut ExperimentRng) {
+    print!("Input p: ");
+    let p: UInteger = stdin_read();
+
+    print!("Input q: ");
+    let q: UInteger = stdin_read();
+
+    print!("Input w: ");
+    let w: usize = stdin_read();
+
+    let report = check_ntru_prime_params(p, q, w);
+    print!("{}", report);
+    if !report.is_valid() {
+        println!("invalid parameters");
+        return;
+    }
+    ntru_prime_session(&ntru_prime_params(p, q).with_inversion(args.inversion), w, rng);
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_prime_session (params: &RingParams, w: usize, rng: &mut ExperimentRng) {
+    let n = params.n();
+
+    println!("Streamlined NTRU Prime: GEN_KEYS");
+    let (public_key, private_key) = ntru_prime_gen_keys(params, w, rng, &mut PrintObserver);
+    println!("h = {}", public_key);
+
+    println!("\nStreamlined NTRU Prime: ENCAPSULATE");
+    let (ciphertext, key) = ntru_prime_encapsulate(&public_key, w, rng, &mut PrintObserver);
+    println!("c = {}", ciphertext);
+    println!("key = {}", to_hex(&key));
+
+    println!("\nStreamlined NTRU Prime: DECAPSULATE");
+    match ntru_prime_decapsulate(&private_key, &ciphertext, w) {
+        Some(decapsulated) => println!("key = {} (keys match: {})", to_hex(&decapsulated), decapsulated == key),
+        None => println!("decapsulation failure")
+    }
+
+    println!("\nStreamlined NTRU Prime: LATTICE CRYPTOANALYSIS:\nSECRET KEY ATTACK:");
+    let mut basis = ntru_prime_svp_create_lattice_basis::<LatticeInt>(&public_key.h);
+    println!("basis:");
+    print_basis(&basis, false);
+
+    println!("BKZ");
+    let start = Instant::now();
+    bkz(&mut basis, 2 * n as usize, 0.99);
+    let duration = start.elapsed();
+
+    print_basis(&basis, true);
+    println!("Execution time: {:?}", duration);
+
+    println!("\nSECRET KEY SEARCHING");
+    search_potentional_ntru_prime_key(params, &basis, w);
+
+    println!("PLAINTEXT ATTACK:");
+    let mut basis = ntru_prime_cvp_create_lattice_basis::<LatticeInt>(&public_key.h, &ciphertext.c, 1);
+    println!("basis:");
+    print_basis(&basis, false);
+    println!("BKZ");
+    let start = Instant::now();
+    bkz(&mut basis, 2 * n as usize + 1, 0.99);
+    let duration = start.elapsed();
+
+    print_basis(&basis, true);
+    println!("Execution time: {:?}", duration);
+
+    println!("\nPLAINTEXT SEARCHING");
+    search_potentional_ntru_prime_plaintext(params, &basis, w);
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_kem_session (kem_params: &NtruKemParams, rng: &mut ExperimentRng) {
+    let params = kem_params.ring_params();
+
+    // full-dimension BKZ is out of reach for the standardized N
+    print!("Input BKZ block size: ");
+    let block_size: usize = stdin_read();
+
+    println!("NTRU KEM ({}): KEYPAIR", kem_params.name());
+    let (pk, sk) = ntru_kem_keypair(kem_params, rng);
+    println!("public key: {} bytes, secret key: {} bytes", pk.len(), sk.len());
+
+    println!("\nNTRU KEM: ENCAPSULATE");
+    let (ct, key) = ntru_kem_encapsulate(kem_params, &pk, rng);
+    println!("ciphertext: {} bytes", ct.len());
+    println!("key = {}", to_hex(&key));
+
+    println!("\nNTRU KEM: DECAPSULATE");
+    let decapsulated = ntru_kem_decapsulate(kem_params, &sk, &ct);
+    println!("key = {} (keys match: {})", to_hex(&decapsulated), decapsulated == key);
+
+    let mut tampered = ct.clone();
+    tampered[0] ^= 1;
+    let rejected = ntru_kem_decapsulate(kem_params, &sk, &tampered);
+    println!("tampered ciphertext: key = {} (rejected: {})", to_hex(&rejected), rejected != key);
+
+    println!("\nNTRU KEM: LATTICE CRYPTOANALYSIS:\nSECRET KEY ATTACK:");
+    let h = ntru_kem_public_key(kem_params, &pk);
+    let mut basis = svp_create_lattice_basis::<ModQPow2, LatticeInt>(&h);
+    println!("basis: {}x{}", basis.len(), basis[0].len());
+
+    println!("BKZ");
+    let start = Instant::now();
+    bkz(&mut basis, block_size, 0.99);
+    let duration = start.elapsed();
+    println!("Execution time: {:?}", duration);
+
+    println!("\nSECRET KEY SEARCHING");
+    search_potentional_ntru_kem_key(&params, &basis);
+
+    println!("\nPLAINTEXT ATTACK:");
+    let c = ntru_kem_ciphertext(kem_params, &ct);
+    let mut basis = ntru_kem_cvp_create_lattice_basis::<LatticeInt>(&h, &c, 1);
+    println!("basis: {}x{}", basis.len(), basis[0].len());
+
+    println!("BKZ");
+    let start = Instant::now();
+    bkz(&mut basis, block_size, 0.99);
+    let duration = start.elapsed();
     println!("Execution time: {:?}", duration);
 
     println!("\nPLAINTEXT SEARCHING");
-    search_potentional_plaintext(&basis, dr);
+    search_potentional_ntru_kem_plaintext(&params, &basis);
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn ntru_kem_kat (kem_params: &NtruKemParams, path: &str) {
+    println!("NTRU KEM ({}): KNOWN ANSWER TESTS {}", kem_params.name(), path);
+    match kat::run_kat_file(kem_params, path) {
+        Ok(count) => println!("{} records passed", count),
+        Err(error) => println!("{}", error)
+    }
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn to_hex (bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 //=======================================================================================================================
 #[cfg(feature = "time-measurement")]
@@ -134,49 +753,57 @@ fn main () -> Result<(), XlsxError> {
 
     let bold_format = Format::new().set_bold();
 
+    let args = Args::from_env();
+
+    let seed = args.seed.unwrap_or_else(rand::random);
+    println!("run seed = {}", seed);
+    let mut rng = ExperimentRng::seed_from_u64(seed);
+
     print!("Input count_ex: ");
     let count_ex: usize = stdin_read();
 
-    print!("Input n: ");
-    let n: UInteger = stdin_read();
-    
-    print!("Input p: ");
-    let p: UInteger = stdin_read();
-
-    print!("Input q: ");
-    let q: UInteger = stdin_read();
+    if args.mode == Mode::Bench {
+        worksheet.write_with_format(0, 0, format!("count ex = {}, seed = {}", count_ex, seed), &bold_format)?;
+        benchmark::write_benchmarks(worksheet, &bold_format, count_ex, &mut rng)?;
+        return workbook.save(format!("count_ex {} benchmark.xlsx", count_ex));
+    }
 
-    print!("Input df: ");
-    let df: u8 = stdin_read();
+    if matches!(args.mode, Mode::FailureRate | Mode::NtruSign | Mode::Cca | Mode::Reaction | Mode::Hybrid) {
+        let Some((attack, params, weights)) = input_timed_attack(&args) else { return Ok(()); };
+        let (n, p, q) = (params.n(), params.p(), params.q());
+        worksheet.write_with_format(0, 0, format!("N = {}, P = {}, Q = {}, {}, {}, count ex = {}, seed = {}", n, p, q, params.reduction(), attack.name(), count_ex, seed), &bold_format)?;
+        if q.is_power_of_two() {
+            write_attack_times::<ModQPow2>(worksheet, &bold_format, count_ex, &attack, &params, weights, &mut rng)?;
+        }
+        else {
+            write_attack_times::<ModQ>(worksheet, &bold_format, count_ex, &attack, &params, weights, &mut rng)?;
+        }
 
-    print!("Input dg: ");
-    let dg: u8 = stdin_read();
+        let path = format!("count_ex {} N {} p {} q {} {} {}", count_ex, n, p, q, attack.name(),
+            if cfg!(feature = "center-mod") { "center-mod.xlsx" } else { "default.xlsx" });
+        workbook.save(&path)?;
+        println!("File successfully saved in \"{}\"", path);
+        return Ok(());
+    }
 
-    print!("Input dr: ");
-    let dr: u8 = stdin_read();
+    let max_mitm_entries: Option<usize> = (args.mode == Mode::Mitm).then(|| {
+        print!("Input max number of stored half-keys: ");
+        stdin_read()
+    });
 
-    // let count_ex = 10usize;
-    // let n: UInteger = 53;
-    // let p: UInteger = 3;
-    // let q: UInteger = 128;
-    // let df = 4u8;
-    // let dg = 3u8;
-    // let dr = 4u8;
+    let (params, (svp_basis, cvp_basis), mitm_target) = input_lattice_bases(&args, &mut rng);
+    let (n, p, q) = (params.n(), params.p(), params.q());
 
-    worksheet.write_with_format(0, 0, format!("N = {}, P = {}, Q = {}, count ex = {}", n, p, q, count_ex), &bold_format)?;
+    worksheet.write_with_format(0, 0, format!("N = {}, P = {}, Q = {}, {}, count ex = {}, seed = {}", n, p, q, params.reduction(), count_ex, seed), &bold_format)?;
     worksheet.write_with_format(1, NUM_COL, "№", &bold_format)?;
     worksheet.write_with_format(1, SVP_COL, "SVP", &bold_format)?;
     worksheet.write_with_format(1, CVP_COL, "CVP", &bold_format)?;
-
-    init_polynomial_ring(n, p, q);
-
-    let (h, (_, _)) = ntru_gen_keys(df, dg);
     
     let mut time_sum = Duration::new(0, 0);
     for i in 2..=(count_ex + 1) {
         worksheet.write(i as u32, NUM_COL, i as u32 - 1)?;
         
-        let mut basis = svp_create_lattice_basis(&h);
+        let mut basis = svp_basis.clone();
 
         let start = Instant::now();
         bkz(&mut basis, 2 * n as usize, 0.99);
@@ -193,13 +820,9 @@ fn main () -> Result<(), XlsxError> {
 
     println!("SVP ended ({:?})", time_sum);
 
-    let m = gen_m();
-    let m = PolyXNm1::<ModQ>::from_polynomial(m);
-    let e = ntru_encrypt(dr, &h, &m);
-
     let mut time_sum = Duration::new(0, 0);
     for i in 2..=(count_ex + 1) {
-        let mut basis = cvp_create_lattice_basis(&h, &e, 1);
+        let mut basis = cvp_basis.clone();
 
         let start = Instant::now();
         bkz(&mut basis, 2 * n as usize + 1, 0.99);
@@ -215,12 +838,41 @@ fn main () -> Result<(), XlsxError> {
 
     println!("CVP ended ({:?})", time_sum);
 
+    if let Some(max_entries) = max_mitm_entries {
+        match mitm_target {
+            Some((h, df)) if mitm_table_size(n as usize, df) <= max_entries as f64 =>
+                write_mitm_columns(worksheet, &bold_format, count_ex, &params, &h, df)?,
+            Some(_) => println!("MITM skipped: the table exceeds {} half-keys", max_entries),
+            None => println!("MITM skipped: it needs a key pair generated from NTRU parameters")
+        }
+    }
+
     let mut path = format!("count_ex {} N {} p {} q {} ", count_ex, n, p, q);
+    if let Mode::NtruKem(kem_params) = &args.mode {
+        path += kem_params.name();
+        path += " ";
+    }
+    if let Some(preset) = &args.preset {
+        path += preset.name;
+        path += " ";
+    }
+    if args.mode == Mode::NtruPrime {
+        path += "ntru-prime ";
+    }
+    else if params.reduction() == polyxnm1::Reduction::Negacyclic {
+        path += "negacyclic ";
+    }
+    if params.inversion() == polyxnm1::InversionMethod::Divsteps {
+        path += "divsteps ";
+    }
+    if max_mitm_entries.is_some() {
+        path += "mitm ";
+    }
     if cfg!(feature = "center-mod") {
-        path += &format!("center-mod.xlsx")[..];
+        path += "center-mod.xlsx";
     }
     else {
-        path += &format!("default.xlsx")[..];
+        path += "default.xlsx";
     }
 
     workbook.save(&path)?;
@@ -230,6 +882,393 @@ fn main () -> Result<(), XlsxError> {
     Ok(())
 }
 //=======================================================================================================================
+/// Times of the meet-in-the-middle search and the size of its table, next to the SVP and CVP columns.
+#[cfg(feature = "time-measurement")]
+fn write_mitm_columns (worksheet: &mut Worksheet, bold_format: &Format, count_ex: usize, params: &RingParams, h: &[Integer], df: u16) -> Result<(), XlsxError> {
+    const MITM_COL: u16 = 3;
+    const MITM_MEMORY_COL: u16 = 4;
+
+    worksheet.write_with_format(1, MITM_COL, "MITM", bold_format)?;
+    worksheet.write_with_format(1, MITM_MEMORY_COL, "MITM table, MiB", bold_format)?;
+
+    let mut time_sum = Duration::new(0, 0);
+    for i in 2..=(count_ex + 1) {
+        let start = Instant::now();
+        let report = odlyzko_search(params, h, df);
+        let duration = start.elapsed();
+        println!("MITM {}, time: {}, {}", i - 1, duration.as_secs_f32(), report);
+
+        worksheet.write(i as u32, MITM_COL, duration.as_secs_f32())?;
+        worksheet.write(i as u32, MITM_MEMORY_COL, report.table_bytes as f64 / (1 << 20) as f64)?;
+        time_sum += duration;
+    }
+
+    worksheet.write(count_ex as u32 + 2, MITM_COL, time_sum.as_secs_f32())?;
+    worksheet.write(count_ex as u32 + 3, MITM_COL, time_sum.as_secs_f32() / count_ex as f32)?;
+
+    println!("MITM ended ({:?})", time_sum);
+    Ok(())
+}
+//=======================================================================================================================
+/// Attack modes without lattice bases, timed run by run on fresh key pairs.
+#[cfg(feature = "time-measurement")]
+enum TimedAttack {
+    FailureRate { trials: usize },
+    NtruSign { signatures: usize },
+    Cca { max_queries: usize },
+    Reaction { max_queries: usize },
+    Hybrid { block_size: usize, guessed: usize }
+}
+
+#[cfg(feature = "time-measurement")]
+impl TimedAttack {
+    fn name (&self) -> &'static str {
+        match self {
+            TimedAttack::FailureRate { .. } => "failure-rate",
+            TimedAttack::NtruSign { .. } => "ntru-sign",
+            TimedAttack::Cca { .. } => "cca",
+            TimedAttack::Reaction { .. } => "reaction",
+            TimedAttack::Hybrid { .. } => "hybrid"
+        }
+    }
+
+    /// Headers of the figures `run_attack` reports next to the time of a run.
+    fn outcomes (&self) -> &'static [&'static str] {
+        match self {
+            TimedAttack::FailureRate { .. } => &["failures"],
+            TimedAttack::NtruSign { .. } => &["secret rows"],
+            TimedAttack::Cca { .. } | TimedAttack::Reaction { .. } => &["queries", "key found"],
+            TimedAttack::Hybrid { .. } => &["collisions", "key found"]
+        }
+    }
+}
+//=======================================================================================================================
+/// The attack of `args.mode` with its ring and weights (df, dg, dr), from the same input as the interactive mode.
+#[cfg(feature = "time-measurement")]
+fn input_timed_attack (args: &Args) -> Option<(TimedAttack, RingParams, (u16, u16, u16))> {
+    if args.mode == Mode::NtruSign {
+        print!("Input n: ");
+        let n: UInteger = stdin_read();
+
+        print!("Input q: ");
+        let q: UInteger = stdin_read();
+
+        print!("Input df: ");
+        let df: u16 = stdin_read();
+
+        print!("Input dg: ");
+        let dg: u16 = stdin_read();
+
+        print!("Input number of signatures: ");
+        let signatures: usize = stdin_read();
+
+        let params = ntru_sign_params(n, q).with_inversion(args.inversion);
+        return Some((TimedAttack::NtruSign { signatures }, params, (df, dg, 0)));
+    }
+
+    let ntru_params = input_ntru_params(args);
+    let attack = match args.mode {
+        Mode::FailureRate => {
+            print!("Input number of trials: ");
+            TimedAttack::FailureRate { trials: stdin_read() }
+        }
+        Mode::Cca => {
+            print!("Input max number of oracle queries: ");
+            TimedAttack::Cca { max_queries: stdin_read() }
+        }
+        Mode::Reaction => {
+            print!("Input max number of oracle queries: ");
+            TimedAttack::Reaction { max_queries: stdin_read() }
+        }
+        Mode::Hybrid => {
+            print!("Input BKZ block size: ");
+            let block_size: usize = stdin_read();
+
+            print!("Input number of guessed coefficients: ");
+            let guessed: usize = stdin_read();
+
+            print!("Input max number of stored half-guesses: ");
+            let max_entries: usize = stdin_read();
+
+            let table_size = hybrid_table_size(ntru_params.n as usize, ntru_params.df, guessed);
+            if table_size > max_entries as f64 {
+                println!("the table of {:.3e} half-guesses exceeds the limit", table_size);
+                return None;
+            }
+            TimedAttack::Hybrid { block_size, guessed }
+        }
+        _ => unreachable!("{:?} is not a timed attack", args.mode)
+    };
+
+    let params = checked_params(&ntru_params, args)?;
+    Some((attack, params, (ntru_params.df, ntru_params.dg, ntru_params.dr)))
+}
+//=======================================================================================================================
+/// Times of `count_ex` runs of an attack, each on a fresh key pair, with the figures of their outcomes.
+#[cfg(feature = "time-measurement")]
+fn write_attack_times <Q: Module> (worksheet: &mut Worksheet, bold_format: &Format, count_ex: usize, attack: &TimedAttack, params: &RingParams, weights: (u16, u16, u16), rng: &mut ExperimentRng) -> Result<(), XlsxError> {
+    const NUM_COL: u16 = 0;
+    const TIME_COL: u16 = 1;
+    const OUTCOME_COL: u16 = 2;
+
+    worksheet.write_with_format(1, NUM_COL, "№", bold_format)?;
+    worksheet.write_with_format(1, TIME_COL, format!("{}, s", attack.name()), bold_format)?;
+    for (col, outcome) in attack.outcomes().iter().enumerate() {
+        worksheet.write_with_format(1, OUTCOME_COL + col as u16, *outcome, bold_format)?;
+    }
+
+    let mut time_sum = Duration::new(0, 0);
+    for i in 2..=(count_ex + 1) {
+        let (duration, outcomes) = run_attack::<Q>(attack, params, weights, rng);
+        println!("{} {}, time: {}, {:?}", attack.name(), i - 1, duration.as_secs_f32(), outcomes);
+
+        worksheet.write(i as u32, NUM_COL, i as u32 - 1)?;
+        worksheet.write(i as u32, TIME_COL, duration.as_secs_f32())?;
+        for (col, outcome) in outcomes.into_iter().enumerate() {
+            worksheet.write(i as u32, OUTCOME_COL + col as u16, outcome)?;
+        }
+        time_sum += duration;
+    }
+
+    worksheet.write(count_ex as u32 + 2, NUM_COL, "time_sum")?;
+    worksheet.write(count_ex as u32 + 2, TIME_COL, time_sum.as_secs_f32())?;
+    worksheet.write(count_ex as u32 + 3, NUM_COL, "average")?;
+    worksheet.write(count_ex as u32 + 3, TIME_COL, time_sum.as_secs_f32() / count_ex as f32)?;
+
+    println!("{} ended ({:?})", attack.name(), time_sum);
+    Ok(())
+}
+//=======================================================================================================================
+/// One run of an attack on a fresh key pair: the time of the attack itself, without key generation, and the figures
+/// named by `TimedAttack::outcomes`.
+#[cfg(feature = "time-measurement")]
+fn run_attack <Q: Module> (attack: &TimedAttack, params: &RingParams, (df, dg, dr): (u16, u16, u16), rng: &mut ExperimentRng) -> (Duration, Vec<f64>) {
+    match *attack {
+        TimedAttack::FailureRate { trials } => {
+            let start = Instant::now();
+            let report = estimate_decryption_failures::<Q>(params, df, dg, dr, trials, rng);
+            (start.elapsed(), vec![report.failures as f64])
+        }
+        TimedAttack::NtruSign { signatures } => {
+            let (public_key, secret_key) = ntru_sign_gen_keys::<Q>(params, df, dg, rng);
+
+            let start = Instant::now();
+            let transcript: Vec<Vec<Integer>> = (0..signatures).map(|i| {
+                let message = (i as u64).to_be_bytes();
+                ntru_sign_error_vector(&public_key.h, &message, &ntru_sign::<Q>(params, &secret_key, &message))
+            }).collect();
+            let rows = learn_parallelepiped(&transcript, 4 * params.n() as usize, rng);
+            let duration = start.elapsed();
+
+            let secret = [
+                ("(f, g)", [secret_key.f.clone(), secret_key.g.clone()].concat()),
+                ("(F, G)", [secret_key.big_f, secret_key.big_g].concat())
+            ];
+            (duration, vec![count_secret_rows(&rows, &secret) as f64])
+        }
+        TimedAttack::Cca { max_queries } => {
+            let (public_key, private_key) = ntru_gen_keys::<Q, Integer>(params, df, dg, rng, &mut SilentObserver);
+            let mut oracle = DecryptionOracle::new(&private_key);
+
+            let start = Instant::now();
+            let recovered = jaulmes_joux_attack(&public_key, df, &mut oracle, max_queries, rng);
+            let duration = start.elapsed();
+            let found = recovered.is_some_and(|key| private_key_rotation::<Q>(params, &key, &private_key).is_some());
+            (duration, vec![oracle.queries() as f64, found as u8 as f64])
+        }
+        TimedAttack::Reaction { max_queries } => {
+            let (public_key, private_key) = ntru_gen_keys::<Q, Integer>(params, df, dg, rng, &mut SilentObserver);
+            let mut oracle = FailureOracle::new(&private_key);
+
+            let start = Instant::now();
+            let report = reaction_attack(&public_key, (df, dg, dr), &mut oracle, max_queries, rng);
+            let duration = start.elapsed();
+            let found = report.key.is_some_and(|key| private_key_rotation::<Q>(params, &key, &private_key).is_some());
+            (duration, vec![report.queries as f64, found as u8 as f64])
+        }
+        TimedAttack::Hybrid { block_size, guessed } => {
+            let (public_key, private_key) = ntru_gen_keys::<Q, Integer>(params, df, dg, rng, &mut SilentObserver);
+
+            let start = Instant::now();
+            let report = hybrid_attack(&public_key, df, guessed, block_size);
+            let duration = start.elapsed();
+            let found = report.key.is_some_and(|key| private_key_rotation::<Q>(params, &key, &private_key).is_some());
+            (duration, vec![report.collisions as f64, found as u8 as f64])
+        }
+    }
+}
+//=======================================================================================================================
+#[cfg(feature = "time-measurement")]
+fn input_lattice_bases (args: &Args, rng: &mut ExperimentRng) -> (RingParams, LatticeBases, Option<MitmTarget>) {
+    match &args.mode {
+        Mode::NtruKem(kem_params) => {
+            let (params, bases) = ntru_kem_lattice_bases(kem_params, rng);
+            return (params, bases, None);
+        }
+        Mode::NtruPrime => {
+            let (params, bases) = input_ntru_prime_lattice_bases(args, rng);
+            return (params, bases, None);
+        }
+        Mode::Load(prefix) => {
+            let (params, bases) = load_lattice_bases(prefix);
+            return (params, bases, None);
+        }
+        _ => ()
+    }
+
+    let ntru_params = input_ntru_params(args);
+    let Some(params) = checked_params(&ntru_params, args) else { std::process::exit(1); };
+    let NtruParams { df, dg, dr, .. } = ntru_params;
+
+    let (bases, h) = match args.integer {
+        IntegerType::I32 => gen_ring_lattice_bases::<i32>(&params, df, dg, dr, args, rng),
+        IntegerType::I64 => gen_ring_lattice_bases::<i64>(&params, df, dg, dr, args, rng),
+        IntegerType::I128 => gen_ring_lattice_bases::<i128>(&params, df, dg, dr, args, rng),
+        IntegerType::BigInt => gen_ring_lattice_bases::<BigInt>(&params, df, dg, dr, args, rng)
+    };
+    (params, bases, h.map(|h| (h, df)))
+}
+//=======================================================================================================================
+#[cfg(feature = "time-measurement")]
+fn input_ntru_prime_lattice_bases (args: &Args, rng: &mut ExperimentRng) -> (RingParams, LatticeBases) {
+    print!("Input p: ");
+    let p: UInteger = stdin_read();
+
+    print!("Input q: ");
+    let q: UInteger = stdin_read();
+
+    print!("Input w: ");
+    let w: usize = stdin_read();
+
+    let report = check_ntru_prime_params(p, q, w);
+    print!("{}", report);
+    if !report.is_valid() {
+        std::process::exit(1);
+    }
+    let params = ntru_prime_params(p, q).with_inversion(args.inversion);
+
+    let (public_key, _) = ntru_prime_gen_keys(&params, w, rng, &mut SilentObserver);
+    let (ciphertext, _) = ntru_prime_encapsulate(&public_key, w, rng, &mut SilentObserver);
+    let h = &public_key.h;
+    (params, (ntru_prime_svp_create_lattice_basis(h), ntru_prime_cvp_create_lattice_basis(h, &ciphertext.c, 1)))
+}
+//=======================================================================================================================
+#[cfg(feature = "time-measurement")]
+fn ntru_kem_lattice_bases (kem_params: &NtruKemParams, rng: &mut ExperimentRng) -> (RingParams, LatticeBases) {
+    let (pk, _) = ntru_kem_keypair(kem_params, rng);
+    let (ct, _) = ntru_kem_encapsulate(kem_params, &pk, rng);
+
+    let h = ntru_kem_public_key(kem_params, &pk);
+    let c = ntru_kem_ciphertext(kem_params, &ct);
+    (kem_params.ring_params(), (svp_create_lattice_basis(&h), ntru_kem_cvp_create_lattice_basis(&h, &c, 1)))
+}
+//=======================================================================================================================
+#[cfg(feature = "time-measurement")]
+fn load_lattice_bases (prefix: &str) -> (RingParams, LatticeBases) {
+    let params = load_params(&format!("{}.params", prefix)).unwrap_or_else(|error| panic!("cannot load {}.params: {}", prefix, error));
+
+    let bases = if params.q().is_power_of_two() {
+        let (h, e) = load_instance::<ModQPow2>(prefix).unwrap_or_else(|error| panic!("cannot load instance {}: {}", prefix, error));
+        (svp_create_lattice_basis(&h), cvp_create_lattice_basis(&h, &e, 1))
+    }
+    else {
+        let (h, e) = load_instance::<ModQ>(prefix).unwrap_or_else(|error| panic!("cannot load instance {}: {}", prefix, error));
+        (svp_create_lattice_basis(&h), cvp_create_lattice_basis(&h, &e, 1))
+    };
+    (params, bases)
+}
+//=======================================================================================================================
+#[cfg(feature = "time-measurement")]
+fn gen_ring_lattice_bases <I: RingInteger> (params: &RingParams, df: u16, dg: u16, dr: u16, args: &Args, rng: &mut ExperimentRng) -> (LatticeBases, Option<Vec<Integer>>) {
+    if params.q().is_power_of_two() {
+        gen_lattice_bases::<ModQPow2, I>(params, df, dg, dr, args, rng)
+    }
+    else {
+        gen_lattice_bases::<ModQ, I>(params, df, dg, dr, args, rng)
+    }
+}
+//=======================================================================================================================
+/// Bases of a fresh key pair and ciphertext, and the coefficients of h if they fit `Integer`.
+#[cfg(feature = "time-measurement")]
+fn gen_lattice_bases <Q: Module, I: RingInteger> (params: &RingParams, df: u16, dg: u16, dr: u16, args: &Args, rng: &mut ExperimentRng) -> (LatticeBases, Option<Vec<Integer>>) {
+    let (public_key, private_key) = ntru_gen_keys::<Q, I>(params, df, dg, rng, &mut SilentObserver);
+
+    let m = gen_m(params, rng);
+    let e = ntru_encrypt(dr, &public_key, &m, rng, &mut SilentObserver);
+
+    if let Some((prefix, format)) = &args.save {
+        save_instance(prefix, *format, &public_key, &private_key, &e);
+    }
+
+    let bases = (svp_create_lattice_basis(&public_key.h), cvp_create_lattice_basis(&public_key.h, &e.e, 1));
+    (bases, public_key.h.change_integer::<Integer>().ok().map(|h| h.to_integers()))
+}
+//=======================================================================================================================
+#[cfg(not(feature = "time-measurement"))]
+fn print_basis (basis: &[Vec<LatticeInt>], numbered: bool) {
+    for (i, vec) in basis.iter().enumerate() {
+        if numbered {
+            print!("{}:\t", i);
+        }
+        for x in vec {
+            print!("{} ", x);
+        }
+        println!();
+    }
+}
+//=======================================================================================================================
+/// The preset named on the command line (`cargo run -- ees401ep1`), otherwise n, p, q, df, dg and dr from stdin.
+fn input_ntru_params (args: &Args) -> NtruParams {
+    if let Some(preset) = args.preset {
+        let NtruParams { name, n, p, q, df, dg, dr } = preset;
+        println!("{}: n = {}, p = {}, q = {}, df = {}, dg = {}, dr = {}", name, n, p, q, df, dg, dr);
+        return preset;
+    }
+
+    print!("Input n: ");
+    let n: UInteger = stdin_read();
+
+    print!("Input p: ");
+    let p: UInteger = stdin_read();
+
+    print!("Input q: ");
+    let q: UInteger = stdin_read();
+
+    print!("Input df: ");
+    let df: u16 = stdin_read();
+
+    print!("Input dg: ");
+    let dg: u16 = stdin_read();
+
+    print!("Input dr: ");
+    let dr: u16 = stdin_read();
+
+    NtruParams { name: "custom", n, p, q, df, dg, dr }
+}
+//=======================================================================================================================
+/// Prints the errors, warnings and attack estimates of the parameters in the ring chosen on the command line;
+/// `None` if they cannot be used.
+fn checked_params (params: &NtruParams, args: &Args) -> Option<RingParams> {
+    let report = check_params(params, args.reduction, args.integer);
+    print!("{}", report);
+    if !report.is_valid() {
+        println!("invalid parameters");
+        return None;
+    }
+    let NtruParams { n, p, q, .. } = *params;
+    RingParams::try_with_integer(n, p, q, args.reduction, args.integer).ok().map(|params| params.with_inversion(args.inversion))
+}
+//=======================================================================================================================
+/// Public key `<prefix>.pub` and ciphertext `<prefix>.ct` of one parameter set.
+fn load_instance <Q: Module> (prefix: &str) -> Result<(PolyXNm1<Q>, PolyXNm1<Q>), FormatError> {
+    let h = load_public_key::<Q>(&format!("{}.pub", prefix))?;
+    let e = load_ciphertext::<Q>(&format!("{}.ct", prefix))?;
+    if h.params() != e.params() {
+        return Err(FormatError::Malformed("public key and ciphertext have different parameters".to_string()));
+    }
+    Ok((h, e))
+}
+//=======================================================================================================================
 fn stdin_read<T: FromStr> () -> T where <T as FromStr>::Err:Debug {
     io::stdout().flush().unwrap();
     let mut str = String::new();
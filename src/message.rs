@@ -0,0 +1,148 @@
+use crate::polyxnm1::{Integer, RingParams, UInteger};
+use crate::polyxnm1::service::mod_center;
+use std::fmt;
+
+/// Bytes of the big-endian length prefix of an encoded message.
+pub const LENGTH_BYTES: usize = 4;
+//=======================================================================================================================
+#[derive(Debug, PartialEq, Eq)]
+pub enum MessageError {
+    /// The trit pair (-1, -1) does not encode any 3 bits.
+    InvalidTrits { index: usize },
+    /// The length prefix exceeds the decoded data.
+    InvalidLength { length: usize }
+}
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageError::InvalidTrits { index } => write!(f, "invalid trit pair at coefficient {}", index),
+            MessageError::InvalidLength { length } => write!(f, "message length {} exceeds the decoded data", length)
+        }
+    }
+}
+//=======================================================================================================================
+/// Splits `length || message` (32-bit big endian length) into plaintext polynomials with centered coefficients mod p.
+/// For p = 3 every 3 bits are packed into 2 trits as in IEEE 1363.1, otherwise every coefficient holds
+/// floor(log2 p) bits. Every polynomial has N coefficients, the last one is padded with zeros.
+pub fn encode_message (params: &RingParams, message: &[u8]) -> Vec<Vec<Integer>> {
+    let n = params.n() as usize;
+    let p = params.p();
+    assert!(p >= 2, "message encoding: p = {p} has no digits");
+
+    let framed: Vec<u8> = (message.len() as u32).to_be_bytes().into_iter().chain(message.iter().copied()).collect();
+    let bits: Vec<u8> = framed.iter().flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1)).collect();
+
+    let digits: Vec<UInteger> = if p == 3 {
+        bits.chunks(3).flat_map(|chunk| {
+            let value = chunk.iter().chain([0, 0].iter()).take(3).fold(0, |acc, &bit| 2 * acc + bit as UInteger);
+            [value / 3, value % 3]
+        }).collect()
+    }
+    else {
+        bits.chunks(bits_per_digit(p)).map(|chunk| {
+            chunk.iter().fold(0, |acc, &bit| 2 * acc + bit as UInteger) << (bits_per_digit(p) - chunk.len())
+        }).collect()
+    };
+
+    digits.chunks(n).map(|chunk| (0..n).map(|i| chunk.get(i).map_or(0, |&d| mod_center(d as Integer, p))).collect()).collect()
+}
+//=======================================================================================================================
+/// Inverse of `encode_message` on polynomials of N coefficients: reads the length prefix and drops the padding.
+pub fn decode_message (params: &RingParams, polynomials: &[Vec<Integer>]) -> Result<Vec<u8>, MessageError> {
+    let n = params.n() as usize;
+    let p = params.p();
+
+    let digits: Vec<UInteger> = polynomials.iter().flat_map(|m| {
+        assert_eq!(m.len(), n, "message decoding: a polynomial of {} coefficients, expected N = {}", m.len(), n);
+        m.iter().map(|&x| x.rem_euclid(p as Integer) as UInteger)
+    }).collect();
+
+    let mut bits = Vec::with_capacity(2 * digits.len());
+    if p == 3 {
+        for (i, pair) in digits.chunks_exact(2).enumerate() {
+            let value = 3 * pair[0] + pair[1];
+            if value > 7 {
+                return Err(MessageError::InvalidTrits { index: 2 * i });
+            }
+            bits.extend((0..3).rev().map(|j| ((value >> j) & 1) as u8));
+        }
+    }
+    else {
+        let k = bits_per_digit(p);
+        for &digit in &digits {
+            bits.extend((0..k).rev().map(|j| ((digit >> j) & 1) as u8));
+        }
+    }
+
+    let bytes: Vec<u8> = bits.chunks_exact(8).map(|chunk| chunk.iter().fold(0, |acc, &bit| 2 * acc + bit)).collect();
+    if bytes.len() < LENGTH_BYTES {
+        return Err(MessageError::InvalidLength { length: 0 });
+    }
+
+    let (length, data) = bytes.split_at(LENGTH_BYTES);
+    let length = u32::from_be_bytes(length.try_into().unwrap()) as usize;
+    if length > data.len() {
+        return Err(MessageError::InvalidLength { length });
+    }
+    Ok(data[..length].to_vec())
+}
+//=======================================================================================================================
+/// Whole bytes held by one plaintext polynomial: 3 bits per 2 trits for p = 3, floor(log2 p) bits per coefficient
+/// otherwise. The first polynomial of `encode_message` also holds the length prefix.
+pub fn plaintext_bytes (params: &RingParams) -> usize {
+    let n = params.n() as usize;
+    let p = params.p();
+    let bits = if p == 3 { 3 * (n / 2) } else { n * bits_per_digit(p) };
+    bits / 8
+}
+//=======================================================================================================================
+#[inline(always)]
+fn bits_per_digit (p: UInteger) -> usize {
+    p.ilog2() as usize
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips () {
+        let message: Vec<u8> = (0..=255).collect();
+        for p in [2, 3, 5, 7, 17] {
+            for n in [11, 12, 107] {
+                let params = RingParams::new(n, p, 67);
+                for length in [0, 1, 5, 19, 256] {
+                    let encoded = encode_message(&params, &message[..length]);
+                    assert!(encoded.iter().all(|m| m.len() == n as usize));
+                    assert!(encoded.iter().flatten().all(|&x| x.unsigned_abs() <= p / 2));
+                    assert_eq!(decode_message(&params, &encoded).unwrap(), &message[..length], "p = {}, N = {}", p, n);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn one_polynomial_holds_plaintext_bytes () {
+        for p in [2, 3, 5] {
+            let params = RingParams::new(107, p, 67);
+            let fits = plaintext_bytes(&params) - LENGTH_BYTES;
+            assert_eq!(encode_message(&params, &vec![0xa5; fits]).len(), 1);
+            assert_eq!(encode_message(&params, &vec![0xa5; fits + 1]).len(), 2);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_encodings () {
+        let params = RingParams::new(11, 3, 67);
+        // the trit pair (2, 2) = (-1, -1) encodes 8
+        assert_eq!(decode_message(&params, &[vec![0, 0, -1, -1, 0, 0, 0, 0, 0, 0, 0]]), Err(MessageError::InvalidTrits { index: 2 }));
+        // 3 * 5 bits hold only one byte, less than the length prefix
+        assert_eq!(decode_message(&params, &[vec![0; 11]]), Err(MessageError::InvalidLength { length: 0 }));
+
+        let mut encoded = encode_message(&RingParams::new(11, 2, 67), b"ab");
+        encoded.truncate(encoded.len() - 1);
+        assert_eq!(decode_message(&RingParams::new(11, 2, 67), &encoded), Err(MessageError::InvalidLength { length: 2 }));
+    }
+}
+//=======================================================================================================================
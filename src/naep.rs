@@ -0,0 +1,181 @@
+use crate::message::{LENGTH_BYTES, decode_message, encode_message, plaintext_bytes};
+use crate::ntru::{Ciphertext, NtruObserver, PrivateKey, PublicKey, SilentObserver, ntru_decrypt};
+use crate::polyxnm1::{Integer, PolyXNm1, RingParams, UInteger, zp::*};
+use crate::polyxnm1::integer::RingInteger;
+use crate::polyxnm1::service::mod_center;
+use polynomial_ring::Polynomial;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Random salt appended to every message, so that equal messages give unrelated blinding polynomials.
+pub const SALT_BYTES: usize = 8;
+//=======================================================================================================================
+#[derive(Debug, PartialEq, Eq)]
+pub enum PaddingError {
+    /// message || salt does not fit into one plaintext polynomial.
+    MessageTooLong { length: usize, capacity: usize },
+    /// The re-derived blinding polynomial does not reproduce the ciphertext (or the plaintext is malformed).
+    InvalidCiphertext
+}
+
+impl fmt::Display for PaddingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaddingError::MessageTooLong { length, capacity } => write!(f, "message of {} bytes exceeds the capacity of {} bytes", length, capacity),
+            PaddingError::InvalidCiphertext => write!(f, "invalid ciphertext")
+        }
+    }
+}
+//=======================================================================================================================
+/// Longest message that fits into one padded plaintext polynomial; 0 also when not even an empty message fits.
+pub fn naep_capacity (params: &RingParams) -> usize {
+    plaintext_bytes(params).saturating_sub(LENGTH_BYTES + SALT_BYTES)
+}
+//=======================================================================================================================
+/// NAEP encryption: M = Encode(message || salt), r = G(M, h) with dr coefficients 1 and -1, R = p * r * h and
+/// e = R + (M + H(R) mod p).
+pub fn ntru_naep_encrypt <Q: Module, I: RingInteger> (dr: u16, key: &PublicKey<Q, I>, message: &[u8], rng: &mut impl RngCore, observer: &mut impl NtruObserver) -> Result<Ciphertext<Q, I>, PaddingError> {
+    let h = &key.h;
+    let params = h.params();
+    let capacity = naep_capacity(params);
+    if message.len() + LENGTH_BYTES + SALT_BYTES > plaintext_bytes(params) {
+        return Err(PaddingError::MessageTooLong { length: message.len(), capacity });
+    }
+
+    let mut salt = [0u8; SALT_BYTES];
+    rng.fill_bytes(&mut salt);
+    let padded: Vec<u8> = message.iter().chain(&salt).copied().collect();
+    let m = encode_message(params, &padded).remove(0);
+
+    let r = gen_r(params, &m, h, dr);
+    observer.observe("r", &Polynomial::new(r.clone()));
+    let big_r = PolyXNm1::from_small(params, &[params.p() as Integer]) * PolyXNm1::from_small(params, &r) * h;
+
+    let masked = add_mod_p(params, &m, &mask(params, &big_r), 1);
+    Ok(Ciphertext { e: big_r + PolyXNm1::from_small(params, &masked) })
+}
+//=======================================================================================================================
+/// NAEP decryption: m' = Decrypt(e), R = e - m', M = m' - H(R) mod p; the ciphertext is accepted only if
+/// p * G(M, h) * h = R.
+pub fn ntru_naep_decrypt <Q: Module, I: RingInteger> (key: &PrivateKey, public_key: &PublicKey<Q, I>, dr: u16, cipher: &Ciphertext<Q, I>) -> Result<Vec<u8>, PaddingError> {
+    let (h, e) = (&public_key.h, &cipher.e);
+    let params = e.params();
+
+    let masked = ntru_decrypt(key, cipher, &mut SilentObserver).m;
+    let big_r = e - PolyXNm1::from_small(params, &masked);
+    let m = add_mod_p(params, &masked, &mask(params, &big_r), -1);
+
+    let r = gen_r(params, &m, h, dr);
+    let expected_r = PolyXNm1::from_small(params, &[params.p() as Integer]) * PolyXNm1::from_small(params, &r) * h;
+    if expected_r != big_r {
+        return Err(PaddingError::InvalidCiphertext);
+    }
+
+    let mut padded = decode_message(params, &[m]).map_err(|_| PaddingError::InvalidCiphertext)?;
+    if padded.len() < SALT_BYTES {
+        return Err(PaddingError::InvalidCiphertext);
+    }
+    padded.truncate(padded.len() - SALT_BYTES);
+    Ok(padded)
+}
+//=======================================================================================================================
+/// G(M, h): ternary polynomial with dr coefficients 1 and dr coefficients -1, positions drawn from SHA-256 in
+/// counter mode. The coefficients of h enter as 64-bit words, the same for every ring integer.
+fn gen_r <Q: Module, I: RingInteger> (params: &RingParams, m: &[Integer], h: &PolyXNm1<Q, I>, dr: u16) -> Vec<Integer> {
+    let n = params.n() as usize;
+    assert!(2 * dr as usize <= n, "NAEP: 2 * dr = {} exceeds n = {}", 2 * dr as usize, n);
+
+    let seed: Vec<u8> = m.iter().map(|&x| x.rem_euclid(params.p() as Integer) as u8)
+        .chain(h.to_integers().iter().flat_map(|x| x.low_u64().to_be_bytes()))
+        .collect();
+
+    // 16-bit samples below the largest multiple of n are unbiased indices
+    let limit = (1 << 16) / n * n;
+    let mut r = vec![0 as Integer; n];
+    let mut placed = 0;
+    for counter in 0u32.. {
+        for pair in hash_block(b'G', counter, &seed).chunks_exact(2) {
+            let sample = u16::from_be_bytes([pair[0], pair[1]]) as usize;
+            if placed == 2 * dr as usize || sample >= limit || r[sample % n] != 0 {
+                continue;
+            }
+            r[sample % n] = if placed < dr as usize { 1 } else { -1 };
+            placed += 1;
+        }
+        if placed == 2 * dr as usize {
+            break;
+        }
+    }
+    r
+}
+//=======================================================================================================================
+/// H(R): polynomial with coefficients uniform mod p, from SHA-256 of R in counter mode.
+fn mask <Q: Module, I: RingInteger> (params: &RingParams, big_r: &PolyXNm1<Q, I>) -> Vec<Integer> {
+    let n = params.n() as usize;
+    let p = params.p();
+    let seed: Vec<u8> = big_r.to_integers().iter().flat_map(|x| x.low_u64().to_be_bytes()).collect();
+
+    // bytes below the largest multiple of p are unbiased digits
+    let limit = 256 / p * p;
+    let mut digits = Vec::with_capacity(n);
+    for counter in 0u32.. {
+        digits.extend(hash_block(b'H', counter, &seed).into_iter()
+            .filter(|&b| (b as UInteger) < limit)
+            .map(|b| b as Integer % p as Integer));
+        if digits.len() >= n {
+            break;
+        }
+    }
+    digits.truncate(n);
+    digits
+}
+//=======================================================================================================================
+/// SHA-256(label || counter || seed).
+#[inline(always)]
+fn hash_block (label: u8, counter: u32, seed: &[u8]) -> [u8; 32] {
+    Sha256::new().chain_update([label]).chain_update(counter.to_be_bytes()).chain_update(seed).finalize().into()
+}
+//=======================================================================================================================
+/// a + sign * b with centered coefficients mod p.
+fn add_mod_p (params: &RingParams, a: &[Integer], b: &[Integer], sign: Integer) -> Vec<Integer> {
+    a.iter().zip(b).map(|(&x, &y)| mod_center(x + sign * y, params.p())).collect()
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ntru::ntru_gen_keys;
+    use crate::presets::{NTRU_107, NtruParams, TOY_11};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn capacity () {
+        assert_eq!(naep_capacity(&RingParams::new(TOY_11.n, TOY_11.p, TOY_11.q)), 0);
+        // 3 bits per 2 trits: 159 bits, 19 bytes
+        assert_eq!(naep_capacity(&RingParams::new(NTRU_107.n, NTRU_107.p, NTRU_107.q)), 19 - LENGTH_BYTES - SALT_BYTES);
+    }
+
+    #[test]
+    fn encrypt_decrypt () {
+        let NtruParams { n, p, q, df, dg, dr, .. } = NTRU_107;
+        let params = RingParams::new(n, p, q);
+        let mut rng = ChaCha20Rng::seed_from_u64(5);
+        let (public_key, private_key) = ntru_gen_keys::<ModQ, Integer>(&params, df, dg, &mut rng, &mut SilentObserver);
+
+        for message in [&b""[..], b"naep", b"seven b"] {
+            let cipher = ntru_naep_encrypt(dr, &public_key, message, &mut rng, &mut SilentObserver).unwrap();
+            assert_eq!(ntru_naep_decrypt(&private_key, &public_key, dr, &cipher).unwrap(), message);
+
+            let mut coeffs = cipher.e.to_integers();
+            coeffs[3] += 1;
+            let tampered = Ciphertext { e: PolyXNm1::from_integers(&params, &coeffs) };
+            assert_eq!(ntru_naep_decrypt(&private_key, &public_key, dr, &tampered), Err(PaddingError::InvalidCiphertext));
+        }
+
+        assert_eq!(ntru_naep_encrypt(dr, &public_key, b"eight by", &mut rng, &mut SilentObserver).err(),
+            Some(PaddingError::MessageTooLong { length: 8, capacity: 7 }));
+    }
+}
+//=======================================================================================================================
@@ -1,170 +1,239 @@
-use crate::polyxnm1::{Integer, N, PolyXNm1, zp::*};
+use crate::polyxnm1::{Integer, PolyXNm1, RingParams, zp::*};
+use crate::polyxnm1::integer::RingInteger;
+use crate::polyxnm1::inverse::find_inv_polynomial;
 use crate::polyxnm1::service::mod_center;
-use crate::polyxnm1::P;
-use polynomial_ring::{Polynomial, polynomial};
+use crate::ternary::sample_ternary;
+use polynomial_ring::Polynomial;
 use rand::Rng;
+use std::fmt;
+
+/// Message coefficients are mod_center(x, p) for x uniform in 0..MESSAGE_RANGE.
+const MESSAGE_RANGE: Integer = 10;
 //=======================================================================================================================
-#[allow(unused_variables)]
-pub fn ntru_gen_keys (df: u8, dg: u8) -> (PolyXNm1<ModQ>, (Polynomial<Integer>, PolyXNm1<ModP>)) {
-    let (f, fp, fq) = gen_f_fp_fq(df);
-    
-    let g = gen_polynomial(dg, dg);
-    if cfg!(not(feature = "time-measurement")) {
-        println!("g = {}", g.to_string());
+/// Public key h = fq * g, in a ring of the ring integer `I`.
+pub struct PublicKey<Q: Module, I: RingInteger = Integer> {
+    pub h: PolyXNm1<Q, I>
+}
+//=======================================================================================================================
+/// Private key f (N coefficients) with its inverse fp mod p; both fit `Integer` whatever the ring integer of q.
+pub struct PrivateKey {
+    pub f: Vec<Integer>,
+    pub fp: PolyXNm1<ModP>
+}
+//=======================================================================================================================
+/// Ciphertext e = p * r * h + m.
+pub struct Ciphertext<Q: Module, I: RingInteger = Integer> {
+    pub e: PolyXNm1<Q, I>
+}
+//=======================================================================================================================
+/// Message polynomial: N coefficients centered mod p.
+#[derive(Clone, PartialEq)]
+pub struct Plaintext {
+    pub m: Vec<Integer>
+}
+//=======================================================================================================================
+impl <Q: Module, I: RingInteger> Clone for PublicKey<Q, I> {
+    fn clone(&self) -> Self {
+        PublicKey { h: self.h.clone() }
     }
+}
 
-    let g = PolyXNm1::from_polynomial(g);
-    let h = fq * g;
-    
-    (h, (f, fp))
+impl Clone for PrivateKey {
+    fn clone(&self) -> Self {
+        PrivateKey { f: self.f.clone(), fp: self.fp.clone() }
+    }
+}
+
+impl <Q: Module, I: RingInteger> Clone for Ciphertext<Q, I> {
+    fn clone(&self) -> Self {
+        Ciphertext { e: self.e.clone() }
+    }
 }
 //=======================================================================================================================
-#[allow(unused_variables)]
-pub fn ntru_encrypt (dr: u8, h: &PolyXNm1<ModQ>, message: &PolyXNm1<ModQ>) -> PolyXNm1<ModQ> {
-    let r = gen_polynomial(dr, dr);
-    if cfg!(not(feature = "time-measurement")) {
-        println!("r = {}", r.to_string());
+impl <Q: Module, I: RingInteger> fmt::Display for PublicKey<Q, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.h)
+    }
+}
+
+impl <Q: Module, I: RingInteger> fmt::Display for Ciphertext<Q, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.e)
+    }
+}
+
+impl fmt::Display for Plaintext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Polynomial::new(self.m.clone()))
+    }
+}
+//=======================================================================================================================
+/// Receives the intermediate values of key generation, encryption and decryption by name: "f" for every candidate,
+/// "fp", "fq", "g", "r" and the centered product "a" = f * e.
+pub trait NtruObserver {
+    fn observe (&mut self, name: &str, value: &dyn fmt::Display);
+}
+
+/// Discards all intermediate values.
+pub struct SilentObserver;
+
+impl NtruObserver for SilentObserver {
+    #[inline(always)]
+    fn observe (&mut self, _name: &str, _value: &dyn fmt::Display) {}
+}
+
+/// Prints every intermediate value as "name = value".
+#[cfg_attr(feature = "time-measurement", allow(dead_code))]
+pub struct PrintObserver;
+
+impl NtruObserver for PrintObserver {
+    fn observe (&mut self, name: &str, value: &dyn fmt::Display) {
+        println!("{} = {}", name, value);
     }
+}
+//=======================================================================================================================
+pub fn ntru_gen_keys <Q: Module, I: RingInteger> (params: &RingParams, df: u16, dg: u16, rng: &mut impl Rng, observer: &mut impl NtruObserver) -> (PublicKey<Q, I>, PrivateKey) {
+    let (f, fp, fq) = gen_f_fp_fq::<Q, I>(params, df, rng, observer);
+    
+    let g = gen_polynomial(params, dg, dg, rng);
+    observer.observe("g", &Polynomial::new(g.clone()));
 
-    let r = PolyXNm1::<ModQ>::from_polynomial(r);
+    let h = fq * PolyXNm1::from_small(params, &g);
     
-    let p = PolyXNm1::from_polynomial(polynomial![ModP::get() as Integer]);
+    (PublicKey { h }, PrivateKey { f, fp })
+}
+//=======================================================================================================================
+pub fn ntru_encrypt <Q: Module, I: RingInteger> (dr: u16, key: &PublicKey<Q, I>, message: &Plaintext, rng: &mut impl Rng, observer: &mut impl NtruObserver) -> Ciphertext<Q, I> {
+    let r = gen_polynomial(key.h.params(), dr, dr, rng);
+    observer.observe("r", &Polynomial::new(r.clone()));
 
-    p * r * h + message
+    ntru_encrypt_with_blinding(key, &r, message)
 }
 //=======================================================================================================================
-#[cfg(not(feature = "time-measurement"))]
-pub fn ntru_decrypt ((f, fp): (&Polynomial<Integer>, &PolyXNm1<ModP>), cipher: &PolyXNm1<ModQ>) -> Polynomial<Integer> {
-    let a = PolyXNm1::<ModQ>::from_polynomial(f.clone()) * cipher;
+/// e = p * r * h + m for a given blinding polynomial r.
+pub fn ntru_encrypt_with_blinding <Q: Module, I: RingInteger> (key: &PublicKey<Q, I>, r: &[Integer], message: &Plaintext) -> Ciphertext<Q, I> {
+    let params = key.h.params();
+    let r = PolyXNm1::<Q, I>::from_small(params, r);
     
-    let q = ModQ::get();
-    let a: Vec<Integer> = a.to_polynomial().coeffs().into_iter().map(|x| mod_center(x.get(), q)).collect();
+    let p = PolyXNm1::from_small(params, &[params.p() as Integer]);
+    let m = PolyXNm1::from_small(params, &message.m);
+
+    Ciphertext { e: p * r * &key.h + m }
+}
+//=======================================================================================================================
+#[cfg_attr(feature = "time-measurement", allow(dead_code))]
+pub fn ntru_decrypt <Q: Module, I: RingInteger> (key: &PrivateKey, cipher: &Ciphertext<Q, I>, observer: &mut impl NtruObserver) -> Plaintext {
+    let params = cipher.e.params();
+    let a = PolyXNm1::<Q, I>::from_small(params, &key.f) * &cipher.e;
     
-    let m = PolyXNm1::from_polynomial(Polynomial::new(a)) * fp;
+    let q = params.q();
+    let a: Vec<I> = a.coeffs().iter().map(|x| mod_center(x.get(), q)).collect();
+    observer.observe("a", &Polynomial::new(a.clone()));
+
+    // a mod p fits `Integer`, the ring integer of fp
+    let a: Vec<Integer> = PolyXNm1::<ModP, I>::from_integers(params, &a).coeffs().iter().map(|x| x.get().to_integer()).collect();
+    let m = PolyXNm1::from_integers(params, &a) * &key.fp;
     
-    let p = ModP::get();
-    let m: Vec<Integer> = m.to_polynomial().coeffs().into_iter().map(|x| mod_center(x.get(), p)).collect();
+    let p = params.p();
+    let m: Vec<Integer> = m.coeffs().iter().map(|x| mod_center(x.get(), p)).collect();
     
-    Polynomial::new(m)
+    Plaintext { m }
 }
 //=======================================================================================================================
-#[allow(dead_code, unused_variables)]
-fn gen_f_fp_fq (df: u8) -> (Polynomial<Integer>, PolyXNm1<ModP>, PolyXNm1<ModQ>) {
-    let mut f;
-    let mut fp;
-    let fq;
+fn gen_f_fp_fq <Q: Module, I: RingInteger> (params: &RingParams, df: u16, rng: &mut impl Rng, observer: &mut impl NtruObserver) -> (Vec<Integer>, PolyXNm1<ModP>, PolyXNm1<Q, I>) {
     loop {
-        f = gen_polynomial(df, df - 1);
-        if cfg!(not(feature = "time-measurement")) {
-            println!("f = {}", f.to_string());
-        }
-
-        let f_fp = PolyXNm1::<ModP>::from_polynomial(f.clone());
-        fp = find_inv_polynomial::<ModP>(&f_fp);
-        if fp == None {
-            continue;
-        }
+        let f = gen_polynomial(params, df, df - 1, rng);
+        observer.observe("f", &Polynomial::new(f.clone()));
 
-        let f_fq = PolyXNm1::<ModQ>::from_polynomial(f.clone());
-        let f2 = find_inv_polynomial(&f_fq.change_module::<Mod2>());
+        let f_fp = PolyXNm1::<ModP>::from_integers(params, &f);
+        let fp = match find_inv_polynomial(&f_fp) {
+            Ok(fp) => fp,
+            Err(_) => continue
+        };
 
-        if f2 == None {
-            continue;
-        }
-        fq = f2_to_fq(&f_fq, &f2.unwrap());
+        let f_fq = PolyXNm1::<Q, I>::from_small(params, &f);
+        let fq = match find_inv_polynomial(&f_fq) {
+            Ok(fq) => fq,
+            Err(_) => continue
+        };
 
-        if cfg!(not(feature = "time-measurement")) {
-            println!("fp = {}", fp.as_mut().unwrap().to_string());
-            println!("fq = {}", fq.to_string());
-        }
+        observer.observe("fp", &fp);
+        observer.observe("fq", &fq);
 
-        break;
+        return (f, fp, fq);
     }
-    (f, fp.unwrap(), fq)
 }
 //=======================================================================================================================
-#[allow(dead_code)]
-fn gen_polynomial (mut d_pos: u8, mut d_neg: u8) -> Polynomial<Integer> {
-    let n = *N.get().unwrap() as usize;
-    let mut polynomial = vec![0 as Integer; n];
-
-    let mut i = 0usize;
-    while d_pos != 0 {
-        if i == n { i = 0; }
-        polynomial[i] = rand::random_bool(0.5) as Integer;
-        if polynomial[i] == 1 { d_pos -= 1; }
-        i += 1;
-    }
-
-    i = 0usize;
-    while d_neg != 0 {
-        if i == n { i = 0; }
-        if polynomial[i] == 0 {
-            polynomial[i] = -(rand::random_bool(0.5) as Integer);
-            if polynomial[i] == -1 { d_neg -= 1; }
-        }
-        i += 1;
-    }
-    Polynomial::new(polynomial)
+pub fn gen_polynomial (params: &RingParams, d_pos: u16, d_neg: u16, rng: &mut impl Rng) -> Vec<Integer> {
+    sample_ternary(params.n() as usize, d_pos as usize, d_neg as usize, rng)
 }
 //=======================================================================================================================
-pub fn gen_m () -> Polynomial<Integer> {
-    let n = *N.get().unwrap() as usize;
-    let mut m = vec![0 as Integer; n];
-
-    let mut rng = rand::rng();
+pub fn gen_m (params: &RingParams, rng: &mut impl Rng) -> Plaintext {
+    let n = params.n() as usize;
 
-    for i in 0..n {
-        m[i] = mod_center(rng.random_range(0..10), *P.get().unwrap());
-    }
+    let m = (0..n).map(|_| mod_center(rng.random_range(0..MESSAGE_RANGE), params.p())).collect();
 
-    Polynomial::new(m)
+    Plaintext { m }
 }
 //=======================================================================================================================
-fn find_inv_polynomial <M: Module> (f: &PolyXNm1<M>) -> Option<PolyXNm1<M>> {
-    // 1. initialization
-    let mut r0 = PolyXNm1::get_xnm1();
-    let mut r1 = f.clone();
-    let mut t0 = PolyXNm1::from_polynomial(polynomial![0 as Integer]);
-    let mut t1 = PolyXNm1::from_polynomial(polynomial![1 as Integer]);
-
-    // 2. search GCD via r1
-    while r1.coeffs_len() != 0 {
-        let r2_div = &r0 / &r1;
-        let r2_mod = r0 % &r1;
-        let t2 = t0 - r2_div * &t1;
-
-        r0 = r1;
-        r1 = r2_mod;
-        t0 = t1;
-        t1 = t2;
-    }
-
-    if r0.coeffs_len() > 1 {
-        None
-    }
-    else {
-        let c = r0.get_little_coeff();
-        match find_inv(&c.get(), &M::get()) {
-            None => None,
-            Some(inv_c) => Some(PolyXNm1::from_polynomial(polynomial![inv_c]) * &t0)
+/// Distribution of one coefficient of `gen_m` as (value, probability) pairs.
+pub fn message_coeff_distribution (params: &RingParams) -> Vec<(Integer, f64)> {
+    let mut distribution: Vec<(Integer, f64)> = Vec::new();
+    for x in 0..MESSAGE_RANGE {
+        let m = mod_center(x, params.p());
+        match distribution.iter_mut().find(|(value, _)| *value == m) {
+            Some((_, prob)) => *prob += 1.0 / MESSAGE_RANGE as f64,
+            None => distribution.push((m, 1.0 / MESSAGE_RANGE as f64))
         }
-        
     }
+    distribution
 }
 //=======================================================================================================================
-fn f2_to_fq(f: &PolyXNm1<ModQ>, f2: &PolyXNm1<Mod2>) -> PolyXNm1<ModQ> {
-    let mut fq = f2.change_module::<ModQ>();
-
-    let q = ModQ::get();
-    let two = PolyXNm1::<ModQ>::from_polynomial(polynomial![2 as Integer]);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polyxnm1::{Reduction, UInteger};
+    use crate::polyxnm1::integer::IntegerType;
+    use crate::presets::TOY_53;
+    use num_bigint::BigInt;
+    use num_traits::Signed;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    /// h, e and the decrypted message of a seeded run, with the coefficients as `BigInt`.
+    fn seeded_run <Q: Module, I: RingInteger> (params: &RingParams, seed: u64) -> (Vec<BigInt>, Vec<BigInt>, Vec<Integer>, Vec<Integer>) {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let (public_key, private_key) = ntru_gen_keys::<Q, I>(params, TOY_53.df, TOY_53.dg, &mut rng, &mut SilentObserver);
+        let m = gen_m(params, &mut rng);
+        let e = ntru_encrypt(TOY_53.dr, &public_key, &m, &mut rng, &mut SilentObserver);
+        let decrypted = ntru_decrypt(&private_key, &e, &mut SilentObserver);
+        let to_bigint = |x: Vec<I>| x.iter().map(|x| x.convert::<BigInt>()).collect();
+        (to_bigint(public_key.h.to_integers()), to_bigint(e.e.to_integers()), m.m, decrypted.m)
+    }
 
-    let mut i = 2;
-    while i <= q {
-        i *= i;
-        fq = &fq * (&two - f * fq.change_module::<ModQ>());
+    #[test]
+    fn ring_integers_give_the_same_run () {
+        for reduction in [Reduction::Cyclic, Reduction::Negacyclic] {
+            let params = RingParams::with_reduction(TOY_53.n, TOY_53.p, TOY_53.q, reduction);
+            let expected = seeded_run::<ModQPow2, i64>(&params, 7);
+            assert_eq!(seeded_run::<ModQPow2, i32>(&params, 7), expected);
+            assert_eq!(seeded_run::<ModQPow2, BigInt>(&params, 7), expected);
+
+            let params = RingParams::with_reduction(TOY_53.n, TOY_53.p, 127, reduction);
+            let expected = seeded_run::<ModQ, i64>(&params, 7);
+            assert_eq!(seeded_run::<ModQ, i128>(&params, 7), expected);
+            assert_eq!(seeded_run::<ModQ, BigInt>(&params, 7), expected);
+        }
     }
 
-    fq
+    #[test]
+    fn bigint_ring_holds_a_module_beyond_integer () {
+        let q: UInteger = 1 << (UInteger::BITS - 1);
+        let params = RingParams::try_with_integer(TOY_53.n, TOY_53.p, q, Reduction::Cyclic, IntegerType::BigInt).unwrap();
+        assert!(params.check_integer::<Integer>().is_err());
+        let (h, _, m, decrypted) = seeded_run::<ModQPow2, BigInt>(&params, 3);
+        assert!(h.iter().any(|x| x.abs() > BigInt::from(Integer::MAX / 4)));
+        assert_eq!(decrypted, m);
+    }
 }
-//=======================================================================================================================
\ No newline at end of file
+//=======================================================================================================================
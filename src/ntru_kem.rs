@@ -0,0 +1,472 @@
+use crate::polyxnm1::{Integer, InversionMethod, PolyXNm1, RingParams, UInteger, Wide, zp::*};
+use crate::polyxnm1::inverse::{InverseError, find_inv_polynomial};
+use crate::polyxnm1::service::{mod_center, read_bits, write_bits};
+use rand::RngCore;
+use sha3::{Digest, Sha3_256};
+
+pub type SharedKey = [u8; SHARED_KEY_BYTES];
+
+const SHARED_KEY_BYTES: usize = 32;
+const PRF_KEY_BYTES: usize = 32;
+//=======================================================================================================================
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NtruKemVariant {
+    Hps,
+    Hrss
+}
+//=======================================================================================================================
+/// Parameter set of the NIST round 3 NTRU KEM: R = Z[x]/(x^N-1), S = Z[x]/Phi_N with Phi_N = (x^N-1)/(x-1),
+/// p = 3 and q = 2^log_q.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NtruKemParams {
+    name: &'static str,
+    variant: NtruKemVariant,
+    n: UInteger,
+    log_q: u32,
+    inversion: InversionMethod
+}
+
+pub const NTRU_HPS2048509: NtruKemParams = kem_params("hps2048509", NtruKemVariant::Hps, 509, 11);
+pub const NTRU_HPS2048677: NtruKemParams = kem_params("hps2048677", NtruKemVariant::Hps, 677, 11);
+pub const NTRU_HPS4096821: NtruKemParams = kem_params("hps4096821", NtruKemVariant::Hps, 821, 12);
+pub const NTRU_HRSS701: NtruKemParams = kem_params("hrss701", NtruKemVariant::Hrss, 701, 13);
+
+pub const NTRU_KEM_PARAMS: [NtruKemParams; 4] = [NTRU_HPS2048509, NTRU_HPS2048677, NTRU_HPS4096821, NTRU_HRSS701];
+//=======================================================================================================================
+const fn kem_params (name: &'static str, variant: NtruKemVariant, n: UInteger, log_q: u32) -> NtruKemParams {
+    NtruKemParams { name, variant, n, log_q, inversion: InversionMethod::Euclid }
+}
+//=======================================================================================================================
+impl NtruKemParams {
+    pub fn from_name (name: &str) -> Option<NtruKemParams> {
+        NTRU_KEM_PARAMS.iter().find(|params| params.name == name).copied()
+    }
+
+    /// The same parameter set with the keys inverted by `inversion`.
+    #[inline(always)]
+    pub fn with_inversion (self, inversion: InversionMethod) -> NtruKemParams {
+        NtruKemParams { inversion, ..self }
+    }
+
+    #[inline(always)]
+    pub fn name (&self) -> &'static str {
+        self.name
+    }
+
+    #[inline(always)]
+    pub fn q (&self) -> UInteger {
+        1 << self.log_q
+    }
+
+    pub fn ring_params (&self) -> RingParams {
+        RingParams::new(self.n, 3, self.q()).with_inversion(self.inversion)
+    }
+//=======================================================================================================================
+    /// HPS: number of nonzero coefficients of g and m.
+    #[inline(always)]
+    fn weight (&self) -> usize {
+        self.q() as usize / 8 - 2
+    }
+
+    #[inline(always)]
+    fn sample_iid_bytes (&self) -> usize {
+        self.n as usize - 1
+    }
+
+    #[inline(always)]
+    fn sample_fixed_type_bytes (&self) -> usize {
+        (30 * (self.n as usize - 1)).div_ceil(8)
+    }
+
+    pub fn sample_fg_bytes (&self) -> usize {
+        match self.variant {
+            NtruKemVariant::Hps => self.sample_iid_bytes() + self.sample_fixed_type_bytes(),
+            NtruKemVariant::Hrss => 2 * self.sample_iid_bytes()
+        }
+    }
+
+    #[inline(always)]
+    pub fn sample_rm_bytes (&self) -> usize {
+        self.sample_fg_bytes()
+    }
+
+    #[cfg_attr(feature = "time-measurement", allow(dead_code))]
+    #[inline(always)]
+    fn pack_trinary_bytes (&self) -> usize {
+        (self.n as usize - 1).div_ceil(5)
+    }
+
+    #[cfg_attr(feature = "time-measurement", allow(dead_code))]
+    #[inline(always)]
+    fn owcpa_secret_key_bytes (&self) -> usize {
+        2 * self.pack_trinary_bytes() + self.public_key_bytes()
+    }
+
+    pub fn public_key_bytes (&self) -> usize {
+        (self.log_q as usize * (self.n as usize - 1)).div_ceil(8)
+    }
+
+    #[cfg_attr(feature = "time-measurement", allow(dead_code))]
+    pub fn secret_key_bytes (&self) -> usize {
+        self.owcpa_secret_key_bytes() + PRF_KEY_BYTES
+    }
+
+    #[cfg_attr(feature = "time-measurement", allow(dead_code))]
+    pub fn ciphertext_bytes (&self) -> usize {
+        self.public_key_bytes()
+    }
+}
+//=======================================================================================================================
+/// KEM key generation: returns (public key, secret key) in the packed format of the reference implementation.
+pub fn ntru_kem_keypair (params: &NtruKemParams, rng: &mut impl RngCore) -> (Vec<u8>, Vec<u8>) {
+    let mut seed = vec![0u8; params.sample_fg_bytes()];
+    rng.fill_bytes(&mut seed);
+    let (pk, mut sk) = owcpa_keypair(params, &seed);
+
+    let mut prf_key = [0u8; PRF_KEY_BYTES];
+    rng.fill_bytes(&mut prf_key);
+    sk.extend_from_slice(&prf_key);
+
+    (pk, sk)
+}
+//=======================================================================================================================
+/// Encapsulation: returns (ciphertext, shared key), the key being SHA3-256 of the packed (r, m).
+pub fn ntru_kem_encapsulate (params: &NtruKemParams, pk: &[u8], rng: &mut impl RngCore) -> (Vec<u8>, SharedKey) {
+    assert_eq!(pk.len(), params.public_key_bytes(), "NTRU KEM: wrong public key length");
+
+    let mut rm_seed = vec![0u8; params.sample_rm_bytes()];
+    rng.fill_bytes(&mut rm_seed);
+    let (r, m) = sample_rm(params, &rm_seed);
+
+    let mut rm = pack_trinary(params, &r);
+    rm.extend(pack_trinary(params, &m));
+    let key = sha3_256(&[&rm]);
+
+    (owcpa_encrypt(params, &r, &m, pk), key)
+}
+//=======================================================================================================================
+/// Decapsulation with implicit rejection: an invalid ciphertext gives SHA3-256(prf key || ciphertext).
+#[cfg_attr(feature = "time-measurement", allow(dead_code))]
+pub fn ntru_kem_decapsulate (params: &NtruKemParams, sk: &[u8], ct: &[u8]) -> SharedKey {
+    assert_eq!(sk.len(), params.secret_key_bytes(), "NTRU KEM: wrong secret key length");
+    assert_eq!(ct.len(), params.ciphertext_bytes(), "NTRU KEM: wrong ciphertext length");
+
+    let (rm, fail) = owcpa_decrypt(params, ct, sk);
+    let key = sha3_256(&[&rm]);
+    let rejection_key = sha3_256(&[&sk[params.owcpa_secret_key_bytes()..], ct]);
+
+    let mask = (fail as u8).wrapping_neg();
+    let mut result = [0u8; SHARED_KEY_BYTES];
+    for ((x, &k), &k_reject) in result.iter_mut().zip(&key).zip(&rejection_key) {
+        *x = k ^ (mask & (k ^ k_reject));
+    }
+    result
+}
+//=======================================================================================================================
+/// Public key h = 3g / f as an element of R/q, for the lattice attacks: (f, 3g) lies in its NTRU lattice.
+pub fn ntru_kem_public_key (params: &NtruKemParams, pk: &[u8]) -> PolyXNm1<ModQPow2> {
+    PolyXNm1::from_integers(&params.ring_params(), &unpack_sum_zero(params, pk))
+}
+//=======================================================================================================================
+/// Ciphertext c = r * h + Lift(m) as an element of R/q.
+pub fn ntru_kem_ciphertext (params: &NtruKemParams, ct: &[u8]) -> PolyXNm1<ModQPow2> {
+    PolyXNm1::from_integers(&params.ring_params(), &unpack_sum_zero(params, ct))
+}
+//=======================================================================================================================
+/// The public key packs h without its last coefficient; the secret key is f, 1/f in S/3 and 1/h in S/q.
+fn owcpa_keypair (params: &NtruKemParams, seed: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let ring = params.ring_params();
+    let (f, g) = sample_fg(params, seed);
+
+    // 2 and 3 have order N-1 mod N for the four parameter sets, so Phi_N is irreducible mod 2 and mod 3 and every
+    // nonzero polynomial of degree at most N-2 is invertible in S/2, S/3 and (by Hensel lifting) S/q
+    let f_p = inverse_mod_phi(&PolyXNm1::<ModP>::from_integers(&ring, &f))
+        .expect("NTRU KEM: f is nonzero of degree at most N-2 and Phi_N is irreducible mod 3");
+
+    // g = 3g (HPS) or g = 3 * (x-1) * g (HRSS)
+    let n = ring.n() as usize;
+    let g: Vec<Integer> = match params.variant {
+        NtruKemVariant::Hps => g.iter().map(|&x| 3 * x).collect(),
+        NtruKemVariant::Hrss => (0..n).map(|i| 3 * (g[(i + n - 1) % n] - g[i])).collect()
+    };
+
+    let mut sk = pack_trinary(params, &f);
+    sk.extend(pack_trinary(params, &f_p.to_integers()));
+
+    let f = PolyXNm1::<ModQPow2>::from_integers(&ring, &f);
+    let g = PolyXNm1::<ModQPow2>::from_integers(&ring, &g);
+    let gf = &g * &f;
+    // g is odd times a nonzero g' of degree at most N-2 (HPS) or times (x-1) * g', a unit mod (2, Phi_N) as Phi_N(1) = N
+    let inv_gf = inverse_mod_phi(&gf).expect("NTRU KEM: g * f is a product of units mod (2, Phi_N)");
+
+    let inv_h = mod_phi(&(&inv_gf * &f * &f));
+    let h = inv_gf * &g * &g;
+
+    sk.extend(pack_q(params, &inv_h.to_integers()));
+
+    (pack_q(params, &h.to_integers()), sk)
+}
+//=======================================================================================================================
+/// c = r * h + Lift(m) in R/q, packed without its last coefficient (c(1) = 0).
+fn owcpa_encrypt (params: &NtruKemParams, r: &[Integer], m: &[Integer], pk: &[u8]) -> Vec<u8> {
+    let ring = params.ring_params();
+    let h = PolyXNm1::<ModQPow2>::from_integers(&ring, &unpack_sum_zero(params, pk));
+    let r = PolyXNm1::from_integers(&ring, r);
+
+    let c = r * h + PolyXNm1::from_integers(&ring, &lift(params, m));
+    pack_q(params, &c.to_integers())
+}
+//=======================================================================================================================
+/// Returns the packed (r, m) and whether the ciphertext is invalid. Instead of re-encrypting, it is checked
+/// that m and r = (c - Lift(m)) / h mod (q, Phi_N) belong to the message space, which is equivalent since c(1) = 0.
+#[cfg_attr(feature = "time-measurement", allow(dead_code))]
+fn owcpa_decrypt (params: &NtruKemParams, ct: &[u8], sk: &[u8]) -> (Vec<u8>, bool) {
+    let ring = params.ring_params();
+    let q = params.q();
+    let trinary_bytes = params.pack_trinary_bytes();
+
+    let c = PolyXNm1::<ModQPow2>::from_integers(&ring, &unpack_sum_zero(params, ct));
+    let f = PolyXNm1::<ModQPow2>::from_integers(&ring, &unpack_trinary(params, &sk[..trinary_bytes]));
+
+    // m = (c * f mod (3, Phi_N)) / f in S/3
+    let cf: Vec<Integer> = (&c * &f).coeffs().iter().map(|x| mod_center(x.get(), q)).collect();
+    let mf = mod_phi(&PolyXNm1::<ModP>::from_integers(&ring, &cf));
+    let f_p = PolyXNm1::<ModP>::from_integers(&ring, &unpack_trinary(params, &sk[trinary_bytes..2 * trinary_bytes]));
+    let m = to_centered(&mod_phi(&(mf * f_p)), 3);
+
+    let mut fail = !check_ciphertext(params, ct);
+    if params.variant == NtruKemVariant::Hps {
+        fail |= !check_m(params, &m);
+    }
+
+    // r = (c - Lift(m)) / h in S/q
+    let b = c - PolyXNm1::from_integers(&ring, &lift(params, &m));
+    let inv_h = PolyXNm1::<ModQPow2>::from_integers(&ring, &unpack_q(params, &sk[2 * trinary_bytes..params.owcpa_secret_key_bytes()]));
+    let r = to_centered(&mod_phi(&(b * inv_h)), q);
+    fail |= !r.iter().all(|&x| (-1..=1).contains(&x));
+
+    let mut rm = pack_trinary(params, &r);
+    rm.extend(pack_trinary(params, &m));
+    (rm, fail)
+}
+//=======================================================================================================================
+/// Lift(m) = m (HPS) or (x-1) * (m / (x-1) mod (3, Phi_N)) (HRSS), so that Lift(m) = 0 mod (x-1) in both cases.
+fn lift (params: &NtruKemParams, m: &[Integer]) -> Vec<Integer> {
+    match params.variant {
+        NtruKemVariant::Hps => m.to_vec(),
+        NtruKemVariant::Hrss => {
+            let ring = params.ring_params();
+            let n = ring.n() as usize;
+
+            let mut x_minus_1 = vec![0 as Integer; n];
+            x_minus_1[0] = -1;
+            x_minus_1[1] = 1;
+            let inv = inverse_mod_phi(&PolyXNm1::<ModP>::from_integers(&ring, &x_minus_1)).expect("NTRU KEM: x-1 is not invertible in S/3");
+            let b = to_centered(&mod_phi(&(PolyXNm1::from_integers(&ring, m) * inv)), 3);
+
+            (0..n).map(|i| b[(i + n - 1) % n] - b[i]).collect()
+        }
+    }
+}
+//=======================================================================================================================
+/// Reduction of an element of Z_m[x]/(x^N-1) modulo Phi_N: x^(N-1) = -(1 + x + ... + x^(N-2)).
+fn mod_phi <M: Module> (a: &PolyXNm1<M>) -> PolyXNm1<M> {
+    let coeffs = a.to_integers();
+    let last = coeffs[coeffs.len() - 1];
+    PolyXNm1::from_integers(a.params(), &coeffs.iter().map(|&x| x - last).collect::<Vec<Integer>>())
+}
+//=======================================================================================================================
+/// Inverse modulo (m, Phi_N): a + c * Phi_N with a(1) + c * N = 1 equals a modulo Phi_N and 1 modulo x-1, so it is
+/// invertible in Z_m[x]/(x^N-1) iff a is invertible modulo Phi_N (N has to be invertible modulo m).
+fn inverse_mod_phi <M: Module> (a: &PolyXNm1<M>) -> Result<PolyXNm1<M>, InverseError> {
+    let params = a.params();
+    let module = M::get(params);
+    let coeffs = a.to_integers();
+
+    let a_1: Wide = coeffs.iter().map(|&x| x as Wide).sum();
+    let n_inv = find_inv(&((params.n() % module) as Integer), &module).expect("NTRU KEM: N is not invertible modulo m");
+    let c = ((1 - a_1).rem_euclid(module as Wide) * n_inv as Wide).rem_euclid(module as Wide) as Integer;
+
+    let shifted: Vec<Integer> = coeffs.iter().map(|&x| x + c).collect();
+    Ok(mod_phi(&find_inv_polynomial(&PolyXNm1::from_integers(params, &shifted))?))
+}
+//=======================================================================================================================
+#[inline(always)]
+fn to_centered <M: Module> (a: &PolyXNm1<M>, module: UInteger) -> Vec<Integer> {
+    a.coeffs().iter().map(|x| mod_center(x.get(), module)).collect()
+}
+//=======================================================================================================================
+/// The unused high bits of the last ciphertext byte must be zero.
+#[cfg_attr(feature = "time-measurement", allow(dead_code))]
+fn check_ciphertext (params: &NtruKemParams, ct: &[u8]) -> bool {
+    let used_bits = (params.log_q as usize * (params.n as usize - 1)) % 8;
+    used_bits == 0 || ct[ct.len() - 1] >> used_bits == 0
+}
+//=======================================================================================================================
+/// HPS message space: as many coefficients 1 as -1, weight(m) = q/8 - 2.
+#[cfg_attr(feature = "time-measurement", allow(dead_code))]
+fn check_m (params: &NtruKemParams, m: &[Integer]) -> bool {
+    let ones = m.iter().filter(|&&x| x == 1).count();
+    let minus_ones = m.iter().filter(|&&x| x == -1).count();
+    ones == minus_ones && ones + minus_ones == params.weight()
+}
+//=======================================================================================================================
+fn sample_fg (params: &NtruKemParams, uniform_bytes: &[u8]) -> (Vec<Integer>, Vec<Integer>) {
+    let (f_bytes, g_bytes) = uniform_bytes.split_at(params.sample_iid_bytes());
+    match params.variant {
+        NtruKemVariant::Hps => (sample_iid(params, f_bytes), sample_fixed_type(params, g_bytes)),
+        NtruKemVariant::Hrss => (sample_iid_plus(params, f_bytes), sample_iid_plus(params, g_bytes))
+    }
+}
+//=======================================================================================================================
+fn sample_rm (params: &NtruKemParams, uniform_bytes: &[u8]) -> (Vec<Integer>, Vec<Integer>) {
+    let (r_bytes, m_bytes) = uniform_bytes.split_at(params.sample_iid_bytes());
+    match params.variant {
+        NtruKemVariant::Hps => (sample_iid(params, r_bytes), sample_fixed_type(params, m_bytes)),
+        NtruKemVariant::Hrss => (sample_iid(params, r_bytes), sample_iid(params, m_bytes))
+    }
+}
+//=======================================================================================================================
+/// Ternary polynomial of degree at most N-2: every byte gives a coefficient byte mod 3.
+fn sample_iid (params: &NtruKemParams, uniform_bytes: &[u8]) -> Vec<Integer> {
+    let mut r: Vec<Integer> = uniform_bytes[..params.sample_iid_bytes()].iter().map(|&b| mod_center((b % 3) as Integer, 3)).collect();
+    r.push(0);
+    r
+}
+//=======================================================================================================================
+/// HRSS: `sample_iid` with the signs of the even coefficients flipped if needed, so that <x * r, r> >= 0.
+fn sample_iid_plus (params: &NtruKemParams, uniform_bytes: &[u8]) -> Vec<Integer> {
+    let mut r = sample_iid(params, uniform_bytes);
+    let correlation: Integer = r.windows(2).map(|w| w[0] * w[1]).sum();
+    if correlation < 0 {
+        for x in r.iter_mut().step_by(2) {
+            *x = -*x;
+        }
+    }
+    r
+}
+//=======================================================================================================================
+/// HPS: ternary polynomial with exactly (q/16 - 1) coefficients 1 and -1 each, placed by sorting 30-bit random
+/// keys labelled with the coefficient in their two low bits.
+fn sample_fixed_type (params: &NtruKemParams, uniform_bytes: &[u8]) -> Vec<Integer> {
+    let n = params.n as usize;
+    let weight = params.weight();
+
+    let mut keys: Vec<i32> = (0..n - 1).map(|i| (read_bits(uniform_bytes, 30 * i, 30) << 2) as u32 as i32).collect();
+    for (i, key) in keys.iter_mut().enumerate().take(weight) {
+        *key |= if i < weight / 2 { 1 } else { 2 };
+    }
+    keys.sort_unstable();
+
+    let mut r: Vec<Integer> = keys.into_iter().map(|key| mod_center((key & 3) as Integer, 3)).collect();
+    r.push(0);
+    r
+}
+//=======================================================================================================================
+/// Five coefficients of degree < N-1 per byte, base 3 with digits in {0, 1, 2}.
+fn pack_trinary (params: &NtruKemParams, a: &[Integer]) -> Vec<u8> {
+    a[..params.n as usize - 1].chunks(5).map(|chunk| {
+        chunk.iter().rev().fold(0u32, |c, &x| 3 * c + x.rem_euclid(3) as u32) as u8
+    }).collect()
+}
+//=======================================================================================================================
+#[cfg_attr(feature = "time-measurement", allow(dead_code))]
+fn unpack_trinary (params: &NtruKemParams, bytes: &[u8]) -> Vec<Integer> {
+    let n = params.n as usize;
+    let mut a: Vec<Integer> = (0..n - 1).map(|i| {
+        let digit = bytes[i / 5] as u32 / 3u32.pow((i % 5) as u32) % 3;
+        mod_center(digit as Integer, 3)
+    }).collect();
+    a.push(0);
+    a
+}
+//=======================================================================================================================
+/// Coefficients of degree < N-1 reduced into [0, q), log_q bits each, least significant bit first.
+fn pack_q (params: &NtruKemParams, a: &[Integer]) -> Vec<u8> {
+    let q = params.q() as Integer;
+    let log_q = params.log_q as usize;
+
+    let mut bytes = vec![0u8; params.public_key_bytes()];
+    for (i, &x) in a[..params.n as usize - 1].iter().enumerate() {
+        write_bits(&mut bytes, log_q * i, log_q, x.rem_euclid(q) as u64);
+    }
+    bytes
+}
+//=======================================================================================================================
+fn unpack_q (params: &NtruKemParams, bytes: &[u8]) -> Vec<Integer> {
+    let log_q = params.log_q as usize;
+    let mut a: Vec<Integer> = (0..params.n as usize - 1).map(|i| read_bits(bytes, log_q * i, log_q) as Integer).collect();
+    a.push(0);
+    a
+}
+//=======================================================================================================================
+/// Public keys and ciphertexts are divisible by x-1, so the omitted last coefficient is minus the sum of the others.
+fn unpack_sum_zero (params: &NtruKemParams, bytes: &[u8]) -> Vec<Integer> {
+    let q = params.q() as Wide;
+    let mut a = unpack_q(params, bytes);
+    let sum: Wide = a.iter().map(|&x| x as Wide).sum();
+    let n = a.len();
+    a[n - 1] = (-sum).rem_euclid(q) as Integer;
+    a
+}
+//=======================================================================================================================
+fn sha3_256 (parts: &[&[u8]]) -> SharedKey {
+    let mut hasher = Sha3_256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+//=======================================================================================================================
+#[cfg(all(test, not(feature = "time-measurement")))]
+mod tests {
+    use super::*;
+    use num_traits::ToPrimitive;
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn phi_n_is_irreducible_mod_2_and_3 () {
+        for params in NTRU_KEM_PARAMS {
+            let n = params.n.to_u64().unwrap();
+            for l in [2u64, 3] {
+                let order = (1..n).find(|&k| (0..k).fold(1, |x, _| x * l % n) == 1).unwrap();
+                assert_eq!(order, n - 1, "{}: order of {} mod N", params.name, l);
+            }
+        }
+    }
+
+    #[test]
+    fn encapsulate_decapsulate () {
+        let mut rng = ChaCha20Rng::seed_from_u64(3);
+        for params in [NTRU_HPS2048509, NTRU_HRSS701] {
+            let (pk, sk) = ntru_kem_keypair(&params, &mut rng);
+            assert_eq!((pk.len(), sk.len()), (params.public_key_bytes(), params.secret_key_bytes()));
+
+            let (ct, key) = ntru_kem_encapsulate(&params, &pk, &mut rng);
+            assert_eq!(ct.len(), params.ciphertext_bytes());
+            assert_eq!(ntru_kem_decapsulate(&params, &sk, &ct), key);
+
+            let mut tampered = ct.clone();
+            tampered[1] ^= 1;
+            assert_ne!(ntru_kem_decapsulate(&params, &sk, &tampered), key);
+        }
+    }
+
+    #[test]
+    fn sample_fixed_type_has_exact_weight () {
+        let mut rng = ChaCha20Rng::seed_from_u64(4);
+        for params in NTRU_KEM_PARAMS.into_iter().filter(|params| params.variant == NtruKemVariant::Hps) {
+            for _ in 0..10 {
+                let mut uniform_bytes = vec![0u8; params.sample_fixed_type_bytes()];
+                rng.fill_bytes(&mut uniform_bytes);
+                let r = sample_fixed_type(&params, &uniform_bytes);
+                let count = |value| r.iter().filter(|&&x| x == value).count();
+                assert_eq!(r.len(), params.n as usize);
+                assert_eq!(r[r.len() - 1], 0);
+                assert_eq!((count(1), count(-1)), (params.weight() / 2, params.weight() / 2), "{}", params.name);
+            }
+        }
+    }
+}
+//=======================================================================================================================
@@ -0,0 +1,168 @@
+use crate::polyxnm1::{Integer, PolyXNm1, Reduction, RingParams, UInteger, zp::*};
+use crate::polyxnm1::inverse::find_inv_polynomial;
+use crate::polyxnm1::service::mod_center;
+use crate::ntru::NtruObserver;
+use crate::ternary::{sample_fixed_weight, sample_iid_ternary};
+use polynomial_ring::Polynomial;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+use std::fmt;
+
+pub type SessionKey = [u8; 32];
+//=======================================================================================================================
+/// Public key h = g / (3f) in R/q.
+pub struct NtruPrimePublicKey {
+    pub h: PolyXNm1<ModQ>
+}
+//=======================================================================================================================
+/// Private key f of weight w with the inverse of g in R/3.
+#[cfg_attr(feature = "time-measurement", allow(dead_code))]
+pub struct NtruPrimePrivateKey {
+    pub f: PolyXNm1<ModQ>,
+    pub g_inv: PolyXNm1<ModP>
+}
+//=======================================================================================================================
+/// Ciphertext c = Round(h * r).
+pub struct NtruPrimeCiphertext {
+    pub c: PolyXNm1<ModQ>
+}
+//=======================================================================================================================
+impl fmt::Display for NtruPrimePublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.h)
+    }
+}
+
+impl fmt::Display for NtruPrimeCiphertext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.c)
+    }
+}
+//=======================================================================================================================
+/// Rings of Streamlined NTRU Prime: R/q = Z_q[x]/(x^p-x-1) and R/3, with p and q prime
+/// (checked by `check_ntru_prime_params`).
+pub fn ntru_prime_params (p: UInteger, q: UInteger) -> RingParams {
+    RingParams::with_reduction(p, 3, q, Reduction::NtruPrime)
+}
+//=======================================================================================================================
+/// Public key h = g / (3f) in R/q; private key (f, 1/g in R/3). f has weight w, g is small and invertible in R/3.
+/// The observer receives g and every candidate f.
+pub fn ntru_prime_gen_keys (params: &RingParams, w: usize, rng: &mut impl Rng, observer: &mut impl NtruObserver) -> (NtruPrimePublicKey, NtruPrimePrivateKey) {
+    let (g, g_inv) = gen_g_ginv(params, rng);
+    observer.observe("g", &Polynomial::new(g.clone()));
+
+    loop {
+        let f = gen_short(params, w, rng);
+        observer.observe("f", &Polynomial::new(f.clone()));
+
+        let f = PolyXNm1::<ModQ>::from_integers(params, &f);
+        let f3 = &f * &PolyXNm1::from_integers(params, &[3]);
+        let f3_inv = match find_inv_polynomial(&f3) {
+            Ok(f3_inv) => f3_inv,
+            Err(_) => continue
+        };
+
+        let h = PolyXNm1::from_integers(params, &g) * f3_inv;
+        return (NtruPrimePublicKey { h }, NtruPrimePrivateKey { f, g_inv });
+    }
+}
+//=======================================================================================================================
+/// Ciphertext c = Round(h * r) for a random short r of weight w, passed to the observer; the session key is the hash
+/// of r.
+pub fn ntru_prime_encapsulate (public_key: &NtruPrimePublicKey, w: usize, rng: &mut impl Rng, observer: &mut impl NtruObserver) -> (NtruPrimeCiphertext, SessionKey) {
+    let params = public_key.h.params();
+    let r = gen_short(params, w, rng);
+    observer.observe("r", &Polynomial::new(r.clone()));
+
+    let hr = &public_key.h * PolyXNm1::from_integers(params, &r);
+    let q = params.q();
+    let c: Vec<Integer> = hr.coeffs().iter().map(|x| round(mod_center(x.get(), q))).collect();
+
+    (NtruPrimeCiphertext { c: PolyXNm1::from_integers(params, &c) }, hash_session(&r))
+}
+//=======================================================================================================================
+/// 3f * c = g * r + 3f * (rounding error) has small coefficients, so r = (3f * c mod 3) / g in R/3.
+/// `None` if the recovered r does not have weight w (decryption failure or a malformed ciphertext).
+#[cfg(not(feature = "time-measurement"))]
+pub fn ntru_prime_decapsulate (private_key: &NtruPrimePrivateKey, ciphertext: &NtruPrimeCiphertext, w: usize) -> Option<SessionKey> {
+    let params = ciphertext.c.params();
+    let e = &private_key.f * &ciphertext.c * PolyXNm1::from_integers(params, &[3]);
+
+    let q = params.q();
+    let e: Vec<Integer> = e.coeffs().iter().map(|x| mod_center(x.get(), q)).collect();
+
+    let r = PolyXNm1::from_integers(params, &e) * &private_key.g_inv;
+    let r: Vec<Integer> = r.coeffs().iter().map(|x| mod_center(x.get(), 3)).collect();
+
+    if r.iter().filter(|&&x| x != 0).count() != w {
+        return None;
+    }
+    Some(hash_session(&r))
+}
+//=======================================================================================================================
+/// Nearest multiple of 3 of a centered residue.
+#[inline(always)]
+fn round (a: Integer) -> Integer {
+    a - mod_center(a, 3)
+}
+//=======================================================================================================================
+fn gen_g_ginv (params: &RingParams, rng: &mut impl Rng) -> (Vec<Integer>, PolyXNm1<ModP>) {
+    let n = params.n() as usize;
+    loop {
+        let g = sample_iid_ternary(n, rng);
+        if let Ok(g_inv) = find_inv_polynomial(&PolyXNm1::<ModP>::from_integers(params, &g)) {
+            return (g, g_inv);
+        }
+    }
+}
+//=======================================================================================================================
+/// Random element of {-1, 0, 1}^N with exactly w nonzero coefficients.
+fn gen_short (params: &RingParams, w: usize, rng: &mut impl Rng) -> Vec<Integer> {
+    let n = params.n() as usize;
+    assert!(w <= n, "NTRU Prime: weight w = {w} exceeds p = {n}");
+    sample_fixed_weight(n, w, rng)
+}
+//=======================================================================================================================
+/// SHA-512 of a domain byte followed by r packed four coefficients (r_i + 1) per byte, cut to 32 bytes.
+fn hash_session (r: &[Integer]) -> SessionKey {
+    let mut hasher = Sha512::new();
+    hasher.update([1u8]);
+    for chunk in r.chunks(4) {
+        let byte = chunk.iter().enumerate().fold(0u8, |acc, (i, &x)| acc | (((x + 1) as u8) << (2 * i)));
+        hasher.update([byte]);
+    }
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&hasher.finalize()[..32]);
+    key
+}
+//=======================================================================================================================
+#[cfg(all(test, not(feature = "time-measurement")))]
+mod tests {
+    use super::*;
+    use crate::ntru::SilentObserver;
+    use crate::params_check::check_ntru_prime_params;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn encapsulate_decapsulate () {
+        let params = ntru_prime_params(47, 1033);
+        let mut rng = ChaCha20Rng::seed_from_u64(9);
+        let (public_key, private_key) = ntru_prime_gen_keys(&params, 12, &mut rng, &mut SilentObserver);
+        for _ in 0..8 {
+            let (ciphertext, key) = ntru_prime_encapsulate(&public_key, 12, &mut rng, &mut SilentObserver);
+            assert_eq!(ntru_prime_decapsulate(&private_key, &ciphertext, 12), Some(key));
+        }
+    }
+
+    #[test]
+    fn parameters_must_be_prime () {
+        assert!(check_ntru_prime_params(47, 1033, 12).is_valid());
+        assert!(!check_ntru_prime_params(48, 1033, 12).is_valid());
+        assert!(!check_ntru_prime_params(47, 1024, 12).is_valid());
+        assert!(!check_ntru_prime_params(47, 3, 12).is_valid());
+        assert!(!check_ntru_prime_params(47, 1033, 48).is_valid());
+    }
+}
+//=======================================================================================================================
@@ -0,0 +1,292 @@
+use crate::polyxnm1::{Integer, PolyXNm1, Reduction, RingParams, UInteger, Wide, zp::*};
+use crate::polyxnm1::convolution::ring_product;
+use crate::polyxnm1::inverse::find_inv_polynomial;
+use crate::polyxnm1::service::mod_center;
+use crate::ternary::sample_ternary;
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive, Zero};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+/// Honest signatures have ||(s, s*h - m)||^2 ~ N/12 * (||f||^2 + ||g||^2 + ||F||^2 + ||G||^2); the verifier
+/// accepts up to this multiple of the square root.
+const NORM_BOUND_FACTOR: f64 = 1.5;
+//=======================================================================================================================
+/// Public NTRUSign basis ((1, h), (0, q)) and the norm bound of the verifier.
+pub struct NtruSignPublicKey<Q: Module> {
+    pub h: PolyXNm1<Q>,
+    #[cfg_attr(feature = "time-measurement", allow(dead_code))]
+    pub norm_bound: f64
+}
+//=======================================================================================================================
+/// Secret NTRUSign basis ((f, g), (F, G)) with f*G - g*F = q.
+pub struct NtruSignSecretKey {
+    pub f: Vec<Integer>,
+    pub g: Vec<Integer>,
+    pub big_f: Vec<Integer>,
+    pub big_g: Vec<Integer>
+}
+//=======================================================================================================================
+/// Rings of NTRUSign: Z_q[x]/(x^N-1) only, p is not used (1 keeps `RingParams` consistent for any q).
+pub fn ntru_sign_params (n: UInteger, q: UInteger) -> RingParams {
+    RingParams::with_reduction(n, 1, q, Reduction::Cyclic)
+}
+//=======================================================================================================================
+/// f in T(df, df-1) and g in T(dg, dg-1), so that f(1) = g(1) = 1; the pair is redrawn until f is invertible mod q
+/// and the resultants of f and g with x^N-1 are coprime, which is what the completion to (F, G) needs, and until
+/// the reduced completion satisfies f*G - g*F = q with F and G fitting `Integer`.
+pub fn ntru_sign_gen_keys <Q: Module> (params: &RingParams, df: u16, dg: u16, rng: &mut impl Rng) -> (NtruSignPublicKey<Q>, NtruSignSecretKey) {
+    assert_eq!(params.reduction(), Reduction::Cyclic, "NTRUSign: only x^N-1 is supported");
+    assert!(df > 0 && dg > 0, "NTRUSign: df and dg must be at least 1");
+    let n = params.n() as usize;
+    let q = BigInt::from_u64(params.q().to_u64().unwrap()).unwrap();
+
+    loop {
+        let f = sample_ternary(n, df as usize, df as usize - 1, rng);
+        let g = sample_ternary(n, dg as usize, dg as usize - 1, rng);
+
+        let Ok(fq) = find_inv_polynomial(&PolyXNm1::<Q>::from_integers(params, &f)) else { continue };
+        let (Some((r_f, rho_f)), Some((r_g, rho_g))) = (resultant(&f), resultant(&g)) else { continue };
+
+        // alpha * R_f + beta * R_g = 1 gives f * (q alpha rho_f) - g * (-q beta rho_g) = q
+        let (gcd, alpha, beta) = extended_gcd(&r_f, &r_g);
+        if gcd.abs() != BigInt::one() {
+            continue;
+        }
+        let (alpha, beta) = if gcd.is_negative() { (-alpha, -beta) } else { (alpha, beta) };
+        let big_f: Vec<BigInt> = rho_g.iter().map(|x| -(&q * &beta * x)).collect();
+        let big_g: Vec<BigInt> = rho_f.iter().map(|x| &q * &alpha * x).collect();
+
+        let (big_f, big_g) = reduce_completion(&f, &g, big_f, big_g);
+        if !is_completion(&f, &g, &big_f, &big_g, &q) {
+            continue;
+        }
+        let (Some(big_f), Some(big_g)) = (to_integers(&big_f), to_integers(&big_g)) else { continue };
+
+        let h = fq * PolyXNm1::from_integers(params, &g);
+        let squared_norm: Integer = [&f, &g, &big_f, &big_g].iter().flat_map(|v| v.iter()).map(|x| x * x).sum();
+        let norm_bound = NORM_BOUND_FACTOR * (n as f64 / 12.0 * squared_norm as f64).sqrt();
+
+        return (NtruSignPublicKey { h, norm_bound }, NtruSignSecretKey { f, g, big_f, big_g });
+    }
+}
+//=======================================================================================================================
+/// Babai rounding of (0, m) in the secret basis: x = round(-m*F / q), y = round(m*f / q), s = x*f + y*F.
+pub fn ntru_sign <Q: Module> (params: &RingParams, key: &NtruSignSecretKey, message: &[u8]) -> Vec<Integer> {
+    let m = hash_message(params, message);
+    let q = params.q() as f64;
+
+    let x: Vec<Integer> = cyclic_product(&m, &key.big_f).iter().map(|&c| (-(c as f64) / q).round() as Integer).collect();
+    let y: Vec<Integer> = cyclic_product(&m, &key.f).iter().map(|&c| (c as f64 / q).round() as Integer).collect();
+
+    cyclic_product(&x, &key.f).iter().zip(cyclic_product(&y, &key.big_f)).map(|(&a, b)| (a + b) as Integer).collect()
+}
+//=======================================================================================================================
+#[cfg_attr(feature = "time-measurement", allow(dead_code))]
+pub fn ntru_sign_verify <Q: Module> (key: &NtruSignPublicKey<Q>, message: &[u8], signature: &[Integer]) -> bool {
+    let error = ntru_sign_error_vector(&key.h, message, signature);
+    let squared_norm: f64 = error.iter().map(|&x| (x as f64) * (x as f64)).sum();
+    squared_norm.sqrt() <= key.norm_bound
+}
+//=======================================================================================================================
+/// (s, s*h - m) with the second half centered mod q: the distance from (0, m) to the signature lattice point. For
+/// Babai rounding it is a uniform point of the parallelepiped spanned by the rotations of (f, g) and (F, G).
+pub fn ntru_sign_error_vector <Q: Module> (h: &PolyXNm1<Q>, message: &[u8], signature: &[Integer]) -> Vec<Integer> {
+    let params = h.params();
+    let m = hash_message(params, message);
+    let t = PolyXNm1::<Q>::from_integers(params, signature) * h;
+
+    let q = params.q();
+    signature.iter().copied()
+        .chain(t.to_integers().iter().zip(&m).map(|(&t, &m)| mod_center(t - m, q)))
+        .collect()
+}
+//=======================================================================================================================
+/// Coefficients in [0, q) from SHA-256(counter || message), 4 bytes per coefficient.
+fn hash_message (params: &RingParams, message: &[u8]) -> Vec<Integer> {
+    let n = params.n() as usize;
+    let q = params.q().to_u64().unwrap();
+
+    (0u32..).flat_map(|counter| {
+        let block: [u8; 32] = Sha256::new().chain_update(counter.to_be_bytes()).chain_update(message).finalize().into();
+        block.chunks_exact(4).map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]) as u64 % q).collect::<Vec<_>>()
+    }).take(n).map(|x| Integer::from_u64(x).unwrap()).collect()
+}
+//=======================================================================================================================
+/// Exact product in Z[x]/(x^N-1).
+fn cyclic_product (a: &[Integer], b: &[Integer]) -> Vec<Wide> {
+    ring_product(a, b, a.len(), Reduction::Cyclic)
+}
+//=======================================================================================================================
+fn big_cyclic_product (a: &[BigInt], b: &[Integer]) -> Vec<BigInt> {
+    let n = a.len();
+    let mut result = vec![BigInt::zero(); n];
+    for (i, x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate().filter(|&(_, &y)| y != 0) {
+            result[(i + j) % n] += x * BigInt::from(y);
+        }
+    }
+    result
+}
+//=======================================================================================================================
+/// R = Res(f, x^N-1) (up to sign) and rho with f * rho = R in Z[x]/(x^N-1): the determinant and the adjugate column
+/// of the circulant matrix of f, by fraction-free (Bareiss) elimination. `None` if f is not invertible over Q.
+fn resultant (f: &[Integer]) -> Option<(BigInt, Vec<BigInt>)> {
+    let n = f.len();
+    // column j holds x^j * f; the last column is the right-hand side e_0
+    let mut a: Vec<Vec<BigInt>> = (0..n).map(|i| {
+        (0..n).map(|j| BigInt::from(f[(i + n - j) % n])).chain([BigInt::from((i == 0) as Integer)]).collect()
+    }).collect();
+
+    let mut previous = BigInt::one();
+    for k in 0..n {
+        let pivot = (k..n).find(|&i| !a[i][k].is_zero())?;
+        a.swap(k, pivot);
+        for i in k + 1..n {
+            for j in k + 1..=n {
+                a[i][j] = (&a[k][k] * &a[i][j] - &a[i][k] * &a[k][j]) / &previous;
+            }
+            a[i][k] = BigInt::zero();
+        }
+        previous = a[k][k].clone();
+    }
+
+    // back substitution for rho = R * A^-1 * e_0, all divisions are exact
+    let r = a[n - 1][n - 1].clone();
+    let mut rho = vec![BigInt::zero(); n];
+    for i in (0..n).rev() {
+        let sum: BigInt = (i + 1..n).map(|j| &a[i][j] * &rho[j]).sum();
+        rho[i] = (&r * &a[i][n] - sum) / &a[i][i];
+    }
+    Some((r, rho))
+}
+//=======================================================================================================================
+/// (gcd, x, y) with a*x + b*y = gcd.
+fn extended_gcd (a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    let (mut old_r, mut r) = (a.clone(), b.clone());
+    let (mut old_x, mut x) = (BigInt::one(), BigInt::zero());
+    let (mut old_y, mut y) = (BigInt::zero(), BigInt::one());
+    while !r.is_zero() {
+        let quotient = &old_r / &r;
+        (old_r, r) = (r.clone(), &old_r - &quotient * &r);
+        (old_x, x) = (x.clone(), &old_x - &quotient * &x);
+        (old_y, y) = (y.clone(), &old_y - &quotient * &y);
+    }
+    (old_r, old_x, old_y)
+}
+//=======================================================================================================================
+/// Size reduction of (F, G) against (f, g): (F, G) -= k * (f, g) with k = round((F*f' + G*g') / (f*f' + g*g')),
+/// where f'(x) = f(1/x). The quotient is taken in the Fourier domain in floating point from the top 53 bits of
+/// (F, G), so large completions shrink by about 50 bits per round.
+fn reduce_completion (f: &[Integer], g: &[Integer], mut big_f: Vec<BigInt>, mut big_g: Vec<BigInt>) -> (Vec<BigInt>, Vec<BigInt>) {
+    let f_hat = dft(&f.iter().map(|&x| x as f64).collect::<Vec<_>>());
+    let g_hat = dft(&g.iter().map(|&x| x as f64).collect::<Vec<_>>());
+
+    loop {
+        let bits = big_f.iter().chain(&big_g).map(|x| x.bits()).max().unwrap_or(0);
+        let shift = bits.saturating_sub(53) as usize;
+        let scaled = |v: &[BigInt]| -> Vec<f64> { v.iter().map(|x| (x >> shift).to_f64().unwrap()).collect() };
+        let (big_f_hat, big_g_hat) = (dft(&scaled(&big_f)), dft(&scaled(&big_g)));
+
+        let k_hat: Vec<(f64, f64)> = (0..f.len()).map(|j| {
+            let numerator = add(mul(big_f_hat[j], conj(f_hat[j])), mul(big_g_hat[j], conj(g_hat[j])));
+            let denominator = norm_sqr(f_hat[j]) + norm_sqr(g_hat[j]);
+            (numerator.0 / denominator, numerator.1 / denominator)
+        }).collect();
+        let k: Vec<BigInt> = inverse_dft(&k_hat).iter().map(|&x| BigInt::from_f64(x.round()).unwrap()).collect();
+        if k.iter().all(Zero::is_zero) {
+            return (big_f, big_g);
+        }
+
+        let k: Vec<BigInt> = k.into_iter().map(|x| x << shift).collect();
+        let reduced_f: Vec<BigInt> = big_f.iter().zip(big_cyclic_product(&k, f)).map(|(x, y)| x - y).collect();
+        let reduced_g: Vec<BigInt> = big_g.iter().zip(big_cyclic_product(&k, g)).map(|(x, y)| x - y).collect();
+        // rounding errors near the optimum must not make the loop cycle
+        if squared_norm(&reduced_f, &reduced_g) >= squared_norm(&big_f, &big_g) {
+            return (big_f, big_g);
+        }
+        (big_f, big_g) = (reduced_f, reduced_g);
+    }
+}
+//=======================================================================================================================
+/// f*G - g*F = q in Z[x]/(x^N-1).
+fn is_completion (f: &[Integer], g: &[Integer], big_f: &[BigInt], big_g: &[BigInt], q: &BigInt) -> bool {
+    let lhs: Vec<BigInt> = big_cyclic_product(big_g, f).iter().zip(big_cyclic_product(big_f, g)).map(|(a, b)| a - b).collect();
+    lhs[0] == *q && lhs[1..].iter().all(Zero::is_zero)
+}
+//=======================================================================================================================
+#[inline(always)]
+fn squared_norm (a: &[BigInt], b: &[BigInt]) -> BigInt {
+    a.iter().chain(b).map(|x| x * x).sum()
+}
+//=======================================================================================================================
+fn to_integers (v: &[BigInt]) -> Option<Vec<Integer>> {
+    v.iter().map(|x| x.to_i64().and_then(Integer::from_i64)).collect()
+}
+//=======================================================================================================================
+/// a_hat_j = a(w^j) for w = exp(2 pi i / N); quadratic, the rings of the experiments are small.
+fn dft (a: &[f64]) -> Vec<(f64, f64)> {
+    let n = a.len();
+    (0..n).map(|j| a.iter().enumerate().fold((0.0, 0.0), |acc, (i, &x)| add(acc, scale(root(i * j, n), x)))).collect()
+}
+//=======================================================================================================================
+fn inverse_dft (a_hat: &[(f64, f64)]) -> Vec<f64> {
+    let n = a_hat.len();
+    (0..n).map(|i| a_hat.iter().enumerate().fold(0.0, |acc, (j, &x)| acc + mul(x, conj(root(i * j, n))).0) / n as f64).collect()
+}
+//=======================================================================================================================
+#[inline(always)]
+fn root (k: usize, n: usize) -> (f64, f64) {
+    let angle = 2.0 * std::f64::consts::PI * (k % n) as f64 / n as f64;
+    (angle.cos(), angle.sin())
+}
+
+#[inline(always)]
+fn add (a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+#[inline(always)]
+fn mul (a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+#[inline(always)]
+fn scale (a: (f64, f64), x: f64) -> (f64, f64) {
+    (a.0 * x, a.1 * x)
+}
+
+#[inline(always)]
+fn conj (a: (f64, f64)) -> (f64, f64) {
+    (a.0, -a.1)
+}
+
+#[inline(always)]
+fn norm_sqr (a: (f64, f64)) -> f64 {
+    a.0 * a.0 + a.1 * a.1
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn signatures_verify () {
+        let params = ntru_sign_params(11, 64);
+        let mut rng = ChaCha20Rng::seed_from_u64(5);
+        for _ in 0..3 {
+            let (public_key, secret_key) = ntru_sign_gen_keys::<ModQPow2>(&params, 4, 4, &mut rng);
+            let to_big = |v: &[Integer]| -> Vec<BigInt> { v.iter().map(|&x| BigInt::from(x)).collect() };
+            let (big_f, big_g) = (to_big(&secret_key.big_f), to_big(&secret_key.big_g));
+            assert!(is_completion(&secret_key.f, &secret_key.g, &big_f, &big_g, &BigInt::from(64)));
+            assert!(!is_completion(&secret_key.f, &secret_key.g, &big_g, &big_f, &BigInt::from(64)));
+
+            let signature = ntru_sign::<ModQPow2>(&params, &secret_key, b"message");
+            assert!(ntru_sign_verify(&public_key, b"message", &signature));
+            assert!(!ntru_sign_verify(&public_key, b"massage", &signature));
+        }
+    }
+}
+//=======================================================================================================================
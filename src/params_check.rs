@@ -0,0 +1,368 @@
+use crate::ntru::message_coeff_distribution;
+use crate::polyxnm1::{Integer, Reduction, RingError, RingParams, UInteger};
+use crate::polyxnm1::integer::IntegerType;
+use crate::presets::NtruParams;
+use num_traits::ToPrimitive;
+use std::f64::consts::{E, PI};
+use std::fmt;
+
+/// Block size from which the fits of delta_beta and of the enumeration cost hold.
+const MIN_FIT_BETA: usize = 40;
+//=======================================================================================================================
+/// Problem found by `check_params`. Errors make the parameter set unusable, warnings only weaken it.
+#[derive(Debug, PartialEq)]
+pub enum ParamsIssue {
+    /// p < 2 or q <= p.
+    InvalidModuli { p: UInteger, q: UInteger },
+    /// `RingParams::try_with_integer` rejects the ring.
+    Ring(RingError),
+    /// The ternary polynomial needs `count` nonzero coefficients but has only N.
+    WeightTooLarge { name: &'static str, count: usize, n: UInteger },
+    /// df = 0: f would need -1 coefficients equal to -1.
+    ZeroDf,
+    /// dg = 0 gives h = 0, dr = 0 gives e = m.
+    ZeroWeight { name: &'static str },
+    /// max |p*r*g + f*m| can exceed (q-1)/2; `probability` is the normal approximation per message.
+    DecryptionFailure { bound: Integer, q: UInteger, probability: f64 },
+    /// N is not prime (x^N-1, x^N-x-1) or not a power of two (x^N+1).
+    UnsuitableN { n: UInteger, reduction: Reduction },
+    /// The reduction polynomial splits mod `modulus` into irreducible factors of small `degree`.
+    SmallFactors { modulus: UInteger, degree: u64, count: u64 },
+    /// Streamlined NTRU Prime needs a prime degree p and a prime modulus q.
+    NotPrime { name: &'static str, value: UInteger }
+}
+//=======================================================================================================================
+impl ParamsIssue {
+    pub fn is_error (&self) -> bool {
+        matches!(self, ParamsIssue::InvalidModuli { .. } | ParamsIssue::Ring(_) |
+            ParamsIssue::WeightTooLarge { .. } | ParamsIssue::ZeroDf | ParamsIssue::NotPrime { .. })
+    }
+}
+//=======================================================================================================================
+impl fmt::Display for ParamsIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamsIssue::InvalidModuli { p, q } => write!(f, "p = {} and q = {} must satisfy 2 <= p < q", p, q),
+            ParamsIssue::Ring(error) => write!(f, "{}", error),
+            ParamsIssue::WeightTooLarge { name, count, n } => write!(f, "{} needs {} nonzero coefficients, but N = {}", name, count, n),
+            ParamsIssue::ZeroDf => write!(f, "df must be at least 1"),
+            ParamsIssue::ZeroWeight { name } => write!(f, "{} = 0 makes the scheme trivial", name),
+            ParamsIssue::DecryptionFailure { bound, q, probability } =>
+                write!(f, "max |p*r*g + f*m| can reach {} > (q-1)/2 = {}, decryption fails with probability ~{:.1e} \
+                    (see `cargo run -- failure-rate`)", bound, (q - 1) / 2, probability),
+            ParamsIssue::UnsuitableN { n, reduction: Reduction::Negacyclic } => write!(f, "N = {} is not a power of two, x^N+1 is not cyclotomic", n),
+            ParamsIssue::UnsuitableN { n, reduction } => write!(f, "N = {} is composite, {} has factors of small degree", n, reduction),
+            ParamsIssue::SmallFactors { modulus, degree, count } =>
+                write!(f, "the reduction polynomial splits mod {} into {} factors of degree {}", modulus, count, degree),
+            ParamsIssue::NotPrime { name, value } => write!(f, "{} = {} is not prime", name, value)
+        }
+    }
+}
+//=======================================================================================================================
+/// Heuristic cost of one lattice attack of `cryptoanalysis`.
+pub struct AttackEstimate {
+    pub name: &'static str,
+    /// Dimension of the basis.
+    pub dimension: usize,
+    /// Norm of the vector the attack looks for.
+    pub target_norm: f64,
+    /// Smallest BKZ block size that finds it (2016 estimate), at most the dimension.
+    pub block_size: usize,
+    /// log2 of the enumeration nodes of one BKZ-`block_size` block, `None` for small (polynomial time) blocks.
+    pub log2_cost: Option<f64>,
+    /// The same for the block size the experiments use (the whole basis).
+    pub log2_cost_full: Option<f64>
+}
+//=======================================================================================================================
+pub struct ParamsReport {
+    pub issues: Vec<ParamsIssue>,
+    /// Empty when the parameters have errors.
+    pub attacks: Vec<AttackEstimate>
+}
+//=======================================================================================================================
+impl ParamsReport {
+    pub fn is_valid (&self) -> bool {
+        !self.issues.iter().any(ParamsIssue::is_error)
+    }
+}
+//=======================================================================================================================
+/// Validates the classic NTRU parameters before any ring is built: the ring itself, with coefficients of type
+/// `integer`, through `RingParams::try_with_integer`, and the weights, since the ternary sampler panics if a weight
+/// exceeds N.
+pub fn check_params (params: &NtruParams, reduction: Reduction, integer: IntegerType) -> ParamsReport {
+    let NtruParams { n, p, q, df, dg, dr, .. } = *params;
+    let mut issues = Vec::new();
+
+    let params = RingParams::try_with_integer(n, p, q, reduction, integer);
+    if p < 2 || q <= p {
+        issues.push(ParamsIssue::InvalidModuli { p, q });
+    }
+    else if let Err(error) = params {
+        issues.push(ParamsIssue::Ring(error));
+    }
+
+    if df == 0 {
+        issues.push(ParamsIssue::ZeroDf);
+    }
+    for (name, count) in [("f", (2 * df as usize).saturating_sub(1)), ("g", 2 * dg as usize), ("r", 2 * dr as usize)] {
+        if count > n as usize {
+            issues.push(ParamsIssue::WeightTooLarge { name, count, n });
+        }
+    }
+    for (name, weight) in [("dg", dg), ("dr", dr)] {
+        if weight == 0 {
+            issues.push(ParamsIssue::ZeroWeight { name });
+        }
+    }
+    let params = match params {
+        Ok(params) if !issues.iter().any(ParamsIssue::is_error) => params,
+        _ => return ParamsReport { issues, attacks: Vec::new() }
+    };
+    let max_m = message_coeff_distribution(&params).iter().map(|&(m, _)| m.abs()).max().unwrap_or(0);
+    let bound = p as Integer * 2 * dr.min(dg) as Integer + (2 * df as Integer - 1) * max_m;
+    if bound as UInteger > (q - 1) / 2 {
+        issues.push(ParamsIssue::DecryptionFailure { bound, q, probability: gaussian_failure_probability(&params, df, dg, dr) });
+    }
+
+    issues.extend(ring_issues(&params));
+
+    ParamsReport { issues, attacks: attack_estimates(&params, df, dg, dr) }
+}
+//=======================================================================================================================
+/// Validates Streamlined NTRU Prime parameters before the ring is built: the degree p and the modulus q must be
+/// prime, q > 3 since the small ring is R/3, and f and r need w <= p nonzero coefficients.
+pub fn check_ntru_prime_params (p: UInteger, q: UInteger, w: usize) -> ParamsReport {
+    let mut issues = Vec::new();
+    for (name, value) in [("p", p), ("q", q)] {
+        if !value.to_u64().is_some_and(is_prime) {
+            issues.push(ParamsIssue::NotPrime { name, value });
+        }
+    }
+    if q <= 3 {
+        issues.push(ParamsIssue::InvalidModuli { p: 3, q });
+    }
+    else if let Err(error) = RingParams::try_new(p, 3, q, Reduction::NtruPrime) {
+        issues.push(ParamsIssue::Ring(error));
+    }
+    if w > p as usize {
+        issues.push(ParamsIssue::WeightTooLarge { name: "f", count: w, n: p });
+    }
+    if w == 0 {
+        issues.push(ParamsIssue::ZeroWeight { name: "w" });
+    }
+    ParamsReport { issues, attacks: Vec::new() }
+}
+//=======================================================================================================================
+/// Normal approximation of the decryption failure probability: Var(a_i) = p^2 * (2dr)(2dg)/N + (2df-1) * E[m_i^2]; a coefficient fails when |a_i| > (q-1)/2.
+pub fn gaussian_failure_probability (params: &RingParams, df: u16, dg: u16, dr: u16) -> f64 {
+    let n = params.n() as f64;
+    let p = params.p() as f64;
+    let m_square: f64 = message_coeff_distribution(params).iter().map(|&(x, prob)| (x * x) as f64 * prob).sum();
+
+    let variance = p * p * 4.0 * dr as f64 * dg as f64 / n + (2.0 * df as f64 - 1.0) * m_square;
+    // continuity correction: the first failing value is (q-1)/2 + 1
+    let threshold = (params.q() as f64 - 1.0) / 2.0 + 0.5;
+    let coeff_failure = erfc(threshold / (2.0 * variance).sqrt());
+    union_over_coeffs(coeff_failure, params.n() as f64)
+}
+//=======================================================================================================================
+/// 1 - (1 - P(coefficient fails))^N.
+#[inline(always)]
+pub fn union_over_coeffs (coeff_failure: f64, n: f64) -> f64 {
+    -(n * (-coeff_failure).ln_1p()).exp_m1()
+}
+//=======================================================================================================================
+/// Complementary error function, Chebyshev fit with fractional error below 1.2e-7 (Numerical Recipes, erfcc).
+fn erfc (x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = [-1.26551223, 1.00002368, 0.37409196, 0.09678418, -0.18628806, 0.27886807, -1.13520398, 1.48851587, -0.82215223, 0.17087277]
+        .iter().rev().fold(0.0, |acc, &c| acc * t + c);
+    let result = t * (-z * z + poly).exp();
+    if x >= 0.0 { result } else { 2.0 - result }
+}
+//=======================================================================================================================
+/// For prime N every irreducible factor of (x^N-1)/(x-1) mod l has degree ord_N(l); for N = 2^k every irreducible
+/// factor of x^N+1 mod odd l has degree ord_2N(l). Few large factors keep f invertible with high probability.
+fn ring_issues (params: &RingParams) -> Vec<ParamsIssue> {
+    let n = params.n().to_u64().unwrap();
+    let reduction = params.reduction();
+    let suitable_n = match reduction {
+        Reduction::Cyclic | Reduction::NtruPrime => is_prime(n),
+        Reduction::Negacyclic => n.is_power_of_two()
+    };
+    if !suitable_n {
+        return vec![ParamsIssue::UnsuitableN { n: params.n(), reduction }];
+    }
+
+    let mut moduli = vec![params.p()];
+    if params.q().is_multiple_of(2) {
+        moduli.push(2);
+    }
+
+    let mut issues = Vec::new();
+    for modulus in moduli {
+        let (degree, count) = match reduction {
+            // l = 0 mod N: x^N-1 = (x-1)^N mod l
+            Reduction::Cyclic if modulus.to_u64().unwrap() % n == 0 => (1, n),
+            Reduction::Cyclic => {
+                let degree = multiplicative_order(modulus.to_u64().unwrap(), n);
+                (degree, (n - 1) / degree)
+            },
+            Reduction::Negacyclic if modulus % 2 == 1 => {
+                let degree = multiplicative_order(modulus.to_u64().unwrap(), 2 * n);
+                (degree, n / degree)
+            },
+            _ => continue
+        };
+        if 2 * degree < n - 1 {
+            issues.push(ParamsIssue::SmallFactors { modulus, degree, count });
+        }
+    }
+    issues
+}
+//=======================================================================================================================
+/// SVP attack on (f, g) in the 2N-dimensional basis of `svp_create_lattice_basis` and CVP attack on (r, -m, 1) in the
+/// (2N+1)-dimensional embedding of `cvp_create_lattice_basis`; both lattices have volume q^N.
+fn attack_estimates (params: &RingParams, df: u16, dg: u16, dr: u16) -> Vec<AttackEstimate> {
+    let n = params.n() as usize;
+    let m_square: f64 = message_coeff_distribution(params).iter().map(|&(x, prob)| (x * x) as f64 * prob).sum();
+    let log_volume = n as f64 * (params.q() as f64).log2();
+
+    [
+        ("secret key (SVP)", 2 * n, (2.0 * df as f64 - 1.0 + 2.0 * dg as f64).sqrt()),
+        ("plaintext (CVP)", 2 * n + 1, (2.0 * dr as f64 + n as f64 * m_square + 1.0).sqrt())
+    ].into_iter().map(|(name, dimension, target_norm)| {
+        let d = dimension as f64;
+        // sqrt(beta/d) * ||v|| <= delta_beta^(2beta-d) * vol^(1/d)
+        let block_size = (2..=dimension).find(|&beta| {
+            let beta_f = beta as f64;
+            (beta_f / d).sqrt().log2() + target_norm.log2() <= (2.0 * beta_f - d) * root_hermite_factor(beta).log2() + log_volume / d
+        }).unwrap_or(dimension);
+
+        AttackEstimate { name, dimension, target_norm, block_size, log2_cost: enumeration_cost(block_size), log2_cost_full: enumeration_cost(dimension) }
+    }).collect()
+}
+//=======================================================================================================================
+/// delta_beta = ((beta / 2 pi e) * (pi beta)^(1/beta))^(1 / 2(beta-1)) for beta >= 40, interpolated from
+/// LLL (delta = 1.0219) below, where the formula does not hold.
+fn root_hermite_factor (beta: usize) -> f64 {
+    const LLL_DELTA: f64 = 1.0219;
+    if beta < MIN_FIT_BETA {
+        let t = (beta as f64 - 2.0) / (MIN_FIT_BETA as f64 - 2.0);
+        return LLL_DELTA + t * (root_hermite_factor(MIN_FIT_BETA) - LLL_DELTA);
+    }
+    let beta = beta as f64;
+    ((beta / (2.0 * PI * E)) * (PI * beta).powf(1.0 / beta)).powf(1.0 / (2.0 * (beta - 1.0)))
+}
+//=======================================================================================================================
+/// log2 of the nodes of full enumeration in dimension beta: beta log2(beta) / 2e - 0.995 beta + 16.25.
+fn enumeration_cost (beta: usize) -> Option<f64> {
+    if beta < MIN_FIT_BETA {
+        return None;
+    }
+    let beta = beta as f64;
+    Some(beta * beta.log2() / (2.0 * E) - 0.995 * beta + 16.25)
+}
+//=======================================================================================================================
+fn is_prime (n: u64) -> bool {
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| !n.is_multiple_of(d))
+}
+//=======================================================================================================================
+/// Smallest k > 0 with a^k = 1 mod m, for a coprime to m.
+fn multiplicative_order (a: u64, m: u64) -> u64 {
+    let a = a % m;
+    let mut x = a;
+    let mut k = 1;
+    while x != 1 {
+        x = (x as u128 * a as u128 % m as u128) as u64;
+        k += 1;
+    }
+    k
+}
+//=======================================================================================================================
+impl fmt::Display for ParamsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            let severity = if issue.is_error() { "error" } else { "warning" };
+            writeln!(f, "{}: {}", severity, issue)?;
+        }
+        let cost = |log2_cost: Option<f64>| log2_cost.map_or("polynomial".to_string(), |c| format!("~2^{:.1} enumeration nodes", c));
+        for attack in &self.attacks {
+            writeln!(f, "{} attack: dimension {}, target norm {:.2}, BKZ block size {} ({}), full enumeration ({})",
+                attack.name, attack.dimension, attack.target_norm, attack.block_size, cost(attack.log2_cost), cost(attack.log2_cost_full))?;
+        }
+        Ok(())
+    }
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presets::EES401EP1;
+
+    fn check (params: NtruParams, reduction: Reduction) -> ParamsReport {
+        check_params(&params, reduction, IntegerType::default())
+    }
+
+    fn errors (report: &ParamsReport) -> Vec<&ParamsIssue> {
+        report.issues.iter().filter(|issue| issue.is_error()).collect()
+    }
+
+    #[test]
+    fn accepts_a_standard_set () {
+        let report = check(EES401EP1, Reduction::Cyclic);
+        assert!(report.is_valid());
+        assert_eq!(report.issues, Vec::new());
+        assert_eq!(report.attacks.len(), 2);
+    }
+
+    #[test]
+    fn rejects_invalid_sets () {
+        let base = NtruParams { name: "test", n: 31, p: 3, q: 128, df: 5, dg: 4, dr: 4 };
+
+        let report = check(NtruParams { df: 0, ..base }, Reduction::Cyclic);
+        assert_eq!(errors(&report), [&ParamsIssue::ZeroDf]);
+        assert!(report.attacks.is_empty());
+
+        let report = check(NtruParams { dg: 16, ..base }, Reduction::Cyclic);
+        assert_eq!(errors(&report), [&ParamsIssue::WeightTooLarge { name: "g", count: 32, n: 31 }]);
+
+        let report = check(NtruParams { q: 129, ..base }, Reduction::Cyclic);
+        assert_eq!(errors(&report), [&ParamsIssue::Ring(RingError::NotCoprime { p: 3, q: 129 })]);
+
+        let report = check(NtruParams { q: 3, ..base }, Reduction::Cyclic);
+        assert_eq!(errors(&report), [&ParamsIssue::InvalidModuli { p: 3, q: 3 }]);
+    }
+
+    #[test]
+    fn checks_the_ring_integer () {
+        let q = 1 << (UInteger::BITS - 1);
+        let params = NtruParams { name: "test", n: 31, p: 3, q, df: 5, dg: 4, dr: 4 };
+        let overflow = RingError::IntegerOverflow { n: 31, module: q, integer: IntegerType::default().name() };
+        assert_eq!(errors(&check(params, Reduction::Cyclic)), [&ParamsIssue::Ring(overflow)]);
+        assert!(check_params(&params, Reduction::Cyclic, IntegerType::BigInt).is_valid());
+    }
+
+    #[test]
+    fn warns_about_weak_rings () {
+        let base = NtruParams { name: "test", n: 33, p: 3, q: 128, df: 5, dg: 4, dr: 4 };
+        let report = check(base, Reduction::Cyclic);
+        assert!(report.is_valid());
+        assert!(report.issues.contains(&ParamsIssue::UnsuitableN { n: 33, reduction: Reduction::Cyclic }));
+        assert!(check(NtruParams { n: 32, ..base }, Reduction::Negacyclic).issues.is_empty());
+
+        // ord_31(2) = 5: x^31 - 1 splits mod 2 into x - 1 and six factors of degree 5
+        let report = check(NtruParams { n: 31, p: 2, q: 127, ..base }, Reduction::Cyclic);
+        assert_eq!(report.issues, [ParamsIssue::SmallFactors { modulus: 2, degree: 5, count: 6 }]);
+    }
+
+    #[test]
+    fn multiplicative_orders () {
+        assert_eq!(multiplicative_order(2, 11), 10);
+        assert_eq!(multiplicative_order(3, 107), 53);
+        assert_eq!(multiplicative_order(2, 31), 5);
+        assert_eq!(multiplicative_order(3, 64), 16);
+    }
+}
+//=======================================================================================================================
@@ -0,0 +1,530 @@
+use crate::polyxnm1::{Reduction, RingError, UInteger};
+use crate::polyxnm1::integer::{RingInteger, WideInteger};
+use num_traits::Euclid;
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+
+// Intermediate coefficients are accumulated in `RingInteger::Wide` without modular reduction: Toom-Cook
+// evaluations grow the operands before the exact interpolation division. `convolution_fits` bounds all of them.
+
+// Measured with `cargo run --release --features time-measurement -- bench` (i32 backend): Karatsuba is faster than
+// schoolbook from about 64 coefficients and Toom-Cook-4 slightly faster than Karatsuba from about 700. Toom-Cook-3
+// never clearly beats both, so `Auto` does not choose it.
+const KARATSUBA_THRESHOLD: usize = 64;
+const TOOM4_THRESHOLD: usize = 700;
+//=======================================================================================================================
+/// Multiplication algorithm of the top level; `Auto` chooses by the operand length, and the recursive
+/// products below the top level always do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConvolutionMethod {
+    Auto,
+    Schoolbook,
+    Karatsuba,
+    ToomCook3,
+    ToomCook4
+}
+
+#[cfg_attr(not(any(test, feature = "time-measurement")), allow(dead_code))]
+pub const CONVOLUTION_METHODS: [ConvolutionMethod; 4] = [
+    ConvolutionMethod::Schoolbook, ConvolutionMethod::Karatsuba, ConvolutionMethod::ToomCook3, ConvolutionMethod::ToomCook4
+];
+
+impl ConvolutionMethod {
+    /// Method of `Auto` for operands of `len` coefficients.
+    fn for_len (len: usize) -> ConvolutionMethod {
+        if len < KARATSUBA_THRESHOLD {
+            ConvolutionMethod::Schoolbook
+        }
+        else if len < TOOM4_THRESHOLD {
+            ConvolutionMethod::Karatsuba
+        }
+        else {
+            ConvolutionMethod::ToomCook4
+        }
+    }
+}
+//=======================================================================================================================
+/// Whether `ring_convolution` of two polynomials of `n` residues mod `module` keeps every intermediate in `I::Wide`.
+/// `RingParams::check_integer` rejects rings where it does not.
+pub fn convolution_fits <I: RingInteger> (n: usize, module: UInteger) -> bool {
+    module >= 1 && I::holds(module) && I::wide_holds(intermediate_bound(ConvolutionMethod::Auto, n, (module - 1) as f64))
+}
+//=======================================================================================================================
+/// Bound on the absolute value of every intermediate of `method` for operands of `len` coefficients with absolute
+/// values up to `x`; a reduced coefficient sums at most 2 * len products.
+fn intermediate_bound (method: ConvolutionMethod, len: usize, x: f64) -> f64 {
+    let product = 2.0 * len as f64 * x * x;
+    match method {
+        ConvolutionMethod::Auto => intermediate_bound(ConvolutionMethod::for_len(len), len, x),
+        ConvolutionMethod::Schoolbook => product,
+        // (a0 + a1) * (b0 + b1) doubles the operands of one half product; z0, z1 and z2 overlap
+        ConvolutionMethod::Karatsuba => intermediate_bound(ConvolutionMethod::Auto, len.div_ceil(2), 2.0 * x).max(2.0 * product),
+        ConvolutionMethod::ToomCook3 => toom_cook_bound(&TOOM3, len, x),
+        ConvolutionMethod::ToomCook4 => toom_cook_bound(&TOOM4, len, x)
+    }
+}
+
+fn toom_cook_bound (scheme: &ToomScheme, len: usize, x: f64) -> f64 {
+    let m = len.div_ceil(scheme.parts);
+    let evaluation = scheme.points.iter().map(|point| match point {
+        None => x,
+        Some(point) => x * (0..scheme.parts as i32).map(|i| (point.abs() as f64).powi(i)).sum::<f64>()
+    }).fold(0.0, f64::max);
+    let values = intermediate_bound(ConvolutionMethod::Auto, m, evaluation);
+    let row = scheme.interpolation.iter().map(|row| row.iter().map(|&c| c.abs() as f64).sum::<f64>()).fold(0.0, f64::max);
+    let parts = (2 * scheme.parts - 1) as f64;
+    (row * values).max(2.0 * parts * row / scheme.denominator as f64 * values)
+}
+//=======================================================================================================================
+/// Product of `a` and `b` in Z_module[x]/(reduction polynomial) with exactly `n` coefficients, each reduced into
+/// [0, module).
+pub fn ring_convolution <I: RingInteger> (a: &[I], b: &[I], n: usize, reduction: Reduction, module: UInteger) -> Vec<I> {
+    ring_convolution_with(ConvolutionMethod::Auto, a, b, n, reduction, module)
+}
+//=======================================================================================================================
+pub fn ring_convolution_with <I: RingInteger> (method: ConvolutionMethod, a: &[I], b: &[I], n: usize, reduction: Reduction, module: UInteger) -> Vec<I> {
+    assert!(convolution_fits::<I>(n, module), "{}", RingError::IntegerOverflow { n: n as UInteger, module, integer: I::NAME });
+    let module = I::wide_module(module);
+    reduced_product(method, &widen(a), &widen(b), n, reduction).into_iter().map(|x| I::narrow(x.rem_euclid(&module))).collect()
+}
+//=======================================================================================================================
+/// Exact product of `a` and `b` in Z[x]/(reduction polynomial), without any modular reduction.
+pub fn ring_product <I: RingInteger> (a: &[I], b: &[I], n: usize, reduction: Reduction) -> Vec<I::Wide> {
+    reduced_product(ConvolutionMethod::Auto, &widen(a), &widen(b), n, reduction)
+}
+//=======================================================================================================================
+/// Product in Z_{2^BITS}[x]/(reduction polynomial) computed with wrapping arithmetic on `std::num::Wrapping`
+/// coefficients of BITS bits, for power-of-two modules that divide 2^BITS (see `RingInteger::wrapping_convolution`).
+/// The caller masks the result; Toom-Cook is not used because its interpolation divides by 2.
+pub fn ring_convolution_wrapping <T: Coefficient> (a: &[T], b: &[T], n: usize, reduction: Reduction) -> Vec<T> {
+    let mut result = vec![T::default(); n];
+    let out = &mut Reduced(&mut result, reduction);
+    if a.len().min(b.len()) < KARATSUBA_THRESHOLD {
+        schoolbook(a, b, out);
+    }
+    else {
+        karatsuba(a, b, linear_product_wrapping, out);
+    }
+    result
+}
+//=======================================================================================================================
+/// The top level of the product writes every coefficient of the linear product straight into the `n` coefficients
+/// of the result, so the 2N-1 coefficients of the linear product are never stored.
+fn reduced_product <W: WideInteger> (method: ConvolutionMethod, a: &[W], b: &[W], n: usize, reduction: Reduction) -> Vec<W> {
+    let method = match method {
+        ConvolutionMethod::Auto => ConvolutionMethod::for_len(a.len().min(b.len())),
+        method => method
+    };
+
+    let mut result = vec![W::zero(); n];
+    product_into(method, a, b, &mut Reduced(&mut result, reduction));
+    result
+}
+//=======================================================================================================================
+/// Destination of the coefficients of a product: the linear product, or the product reduced on the fly.
+trait Accumulator<T: Coefficient> {
+    /// Adds x * x^k.
+    fn add (&mut self, k: usize, x: T);
+    /// Adds x * x^offset * b.
+    fn add_row (&mut self, offset: usize, x: T, b: &[T]);
+}
+
+/// Linear product; the terms past its degree that Karatsuba and Toom-Cook produce from the zero padding are zero.
+struct Linear<'a, T>(&'a mut [T]);
+
+/// Product with exactly `n` = len coefficients: x^N = 1 for `Cyclic`, x^N = -1 for `Negacyclic` and x^N = x + 1
+/// for `NtruPrime`.
+struct Reduced<'a, T>(&'a mut [T], Reduction);
+
+impl <T: Coefficient> Accumulator<T> for Linear<'_, T> {
+    #[inline(always)]
+    fn add (&mut self, k: usize, x: T) {
+        if let Some(r) = self.0.get_mut(k) {
+            *r += x;
+        }
+    }
+
+    #[inline(always)]
+    fn add_row (&mut self, offset: usize, x: T, b: &[T]) {
+        for (r, y) in self.0[offset..].iter_mut().zip(b) {
+            *r += x.clone() * y.clone();
+        }
+    }
+}
+
+impl <T: Coefficient> Accumulator<T> for Reduced<'_, T> {
+    #[inline(always)]
+    fn add (&mut self, k: usize, x: T) {
+        let n = self.0.len();
+        if k < n {
+            self.0[k] += x;
+            return;
+        }
+        match self.1 {
+            Reduction::Cyclic => self.0[k % n] += x,
+            Reduction::Negacyclic if (k / n) % 2 == 1 => self.0[k % n] -= x,
+            Reduction::Negacyclic => self.0[k % n] += x,
+            Reduction::NtruPrime => {
+                self.add(k - n, x.clone());
+                self.add(k - n + 1, x);
+            }
+        }
+    }
+
+    /// Rows that end below x^(2N-1) are added as two slices: below x^N and the reduced part above it.
+    #[inline(always)]
+    fn add_row (&mut self, offset: usize, x: T, b: &[T]) {
+        let n = self.0.len();
+        if offset >= n || offset + b.len() >= 2 * n {
+            for (j, y) in b.iter().enumerate() {
+                self.add(offset + j, x.clone() * y.clone());
+            }
+            return;
+        }
+
+        let (low, high) = b.split_at((n - offset).min(b.len()));
+        for (r, y) in self.0[offset..].iter_mut().zip(low) {
+            *r += x.clone() * y.clone();
+        }
+        match self.1 {
+            Reduction::Cyclic => for (r, y) in self.0.iter_mut().zip(high) {
+                *r += x.clone() * y.clone();
+            },
+            Reduction::Negacyclic => for (r, y) in self.0.iter_mut().zip(high) {
+                *r -= x.clone() * y.clone();
+            },
+            Reduction::NtruPrime => for (j, y) in high.iter().enumerate() {
+                let xy = x.clone() * y.clone();
+                self.0[j] += xy.clone();
+                self.0[j + 1] += xy;
+            }
+        }
+    }
+}
+//=======================================================================================================================
+/// Reduces a polynomial of any degree to `n` coefficients: x^N = 1 for `Cyclic`, x^N = -1 for `Negacyclic`
+/// and x^N = x + 1 for `NtruPrime`.
+pub fn fold <T: Coefficient> (v: &[T], n: usize, reduction: Reduction) -> Vec<T> {
+    if reduction == Reduction::NtruPrime {
+        let mut result = v.to_vec();
+        result.resize(result.len().max(n), T::default());
+        for i in (n..result.len()).rev() {
+            let x = result[i].clone();
+            result[i - n] += x.clone();
+            result[i - n + 1] += x;
+        }
+        result.truncate(n);
+        return result;
+    }
+
+    let mut result = vec![T::default(); n];
+    for (i, x) in v.iter().enumerate() {
+        if reduction == Reduction::Negacyclic && (i / n) % 2 == 1 {
+            result[i % n] -= x.clone();
+        }
+        else {
+            result[i % n] += x.clone();
+        }
+    }
+    result
+}
+//=======================================================================================================================
+fn product_into <W: WideInteger> (method: ConvolutionMethod, a: &[W], b: &[W], out: &mut impl Accumulator<W>) {
+    match method {
+        ConvolutionMethod::Auto | ConvolutionMethod::Schoolbook => schoolbook(a, b, out),
+        ConvolutionMethod::Karatsuba => karatsuba(a, b, linear_product, out),
+        ConvolutionMethod::ToomCook3 => toom_cook(a, b, &TOOM3, out),
+        ConvolutionMethod::ToomCook4 => toom_cook(a, b, &TOOM4, out)
+    }
+}
+//=======================================================================================================================
+/// Linear product of the recursion, with the method of `ConvolutionMethod::Auto`.
+fn linear_product <W: WideInteger> (a: &[W], b: &[W]) -> Vec<W> {
+    let mut result = vec![W::zero(); (a.len() + b.len()).saturating_sub(1)];
+    product_into(ConvolutionMethod::for_len(a.len().min(b.len())), a, b, &mut Linear(&mut result));
+    result
+}
+//=======================================================================================================================
+fn linear_product_wrapping <T: Coefficient> (a: &[T], b: &[T]) -> Vec<T> {
+    let mut result = vec![T::default(); (a.len() + b.len()).saturating_sub(1)];
+    let out = &mut Linear(&mut result);
+    if a.len().min(b.len()) < KARATSUBA_THRESHOLD {
+        schoolbook(a, b, out);
+    }
+    else {
+        karatsuba(a, b, linear_product_wrapping, out);
+    }
+    result
+}
+//=======================================================================================================================
+fn schoolbook <T: Coefficient> (a: &[T], b: &[T], out: &mut impl Accumulator<T>) {
+    for (i, x) in a.iter().enumerate() {
+        out.add_row(i, x.clone(), b);
+    }
+}
+//=======================================================================================================================
+/// `product` multiplies the halves, so the recursion keeps choosing the method by size.
+fn karatsuba <T: Coefficient> (a: &[T], b: &[T], product: fn(&[T], &[T]) -> Vec<T>, out: &mut impl Accumulator<T>) {
+    if a.is_empty() || b.is_empty() {
+        return;
+    }
+
+    // a = a0 + x^m * a1, b = b0 + x^m * b1
+    let m = a.len().max(b.len()).div_ceil(2);
+    let (a0, a1) = split_at_padded(a, m);
+    let (b0, b1) = split_at_padded(b, m);
+
+    let z0 = product(&a0, &b0);
+    let z2 = product(&a1, &b1);
+    let mut z1 = product(&add_vec(&a0, &a1), &add_vec(&b0, &b1));
+    for (i, x) in z1.iter_mut().enumerate() {
+        *x -= z0.get(i).cloned().unwrap_or_default();
+        *x -= z2.get(i).cloned().unwrap_or_default();
+    }
+
+    for (i, x) in z0.into_iter().enumerate() {
+        out.add(i, x);
+    }
+    for (i, x) in z1.into_iter().enumerate() {
+        out.add(i + m, x);
+    }
+    for (i, x) in z2.into_iter().enumerate() {
+        out.add(i + 2 * m, x);
+    }
+}
+//=======================================================================================================================
+/// Toom-Cook-k: both operands are split into `parts` blocks, evaluated at `2 * parts - 1` points,
+/// multiplied pointwise and interpolated back. `None` stands for the point at infinity.
+/// `interpolation / denominator` is the inverse of the Vandermonde matrix of `points`.
+struct ToomScheme {
+    parts: usize,
+    points: &'static [Option<i64>],
+    interpolation: &'static [&'static [i64]],
+    denominator: i64
+}
+
+const TOOM3: ToomScheme = ToomScheme {
+    parts: 3,
+    points: &[Some(0), Some(1), Some(-1), Some(-2), None],
+    interpolation: &[
+        &[ 6, 0,  0,  0,   0],
+        &[ 3, 2, -6,  1, -12],
+        &[-6, 3,  3,  0,  -6],
+        &[-3, 1,  3, -1,  12],
+        &[ 0, 0,  0,  0,   6],
+    ],
+    denominator: 6
+};
+
+const TOOM4: ToomScheme = ToomScheme {
+    parts: 4,
+    points: &[Some(0), Some(1), Some(-1), Some(2), Some(-2), Some(3), None],
+    interpolation: &[
+        &[ 120,   0,   0,   0,  0,  0,     0],
+        &[ -40, 120, -60, -30,  6,  4, -1440],
+        &[-150,  80,  80,  -5, -5,  0,   480],
+        &[  50, -70,  -5,  35, -5, -5,  1800],
+        &[  30, -20, -20,   5,  5,  0,  -600],
+        &[ -10,  10,   5,  -5, -1,  1,  -360],
+        &[   0,   0,   0,   0,  0,  0,   120],
+    ],
+    denominator: 120
+};
+//=======================================================================================================================
+fn toom_cook <W: WideInteger> (a: &[W], b: &[W], scheme: &ToomScheme, out: &mut impl Accumulator<W>) {
+    if a.is_empty() || b.is_empty() {
+        return;
+    }
+
+    let k = scheme.parts;
+    let m = a.len().max(b.len()).div_ceil(k);
+    let a_parts = split_padded(a, m, k);
+    let b_parts = split_padded(b, m, k);
+
+    // 1. evaluation and pointwise multiplication
+    let values: Vec<Vec<W>> = scheme.points.iter().map(|&point| {
+        let a_value = evaluate(&a_parts, point);
+        let b_value = evaluate(&b_parts, point);
+        let mut value = linear_product(&a_value, &b_value);
+        value.resize(2 * m - 1, W::zero());
+        value
+    }).collect();
+
+    // 2. interpolation
+    let denominator = W::from(scheme.denominator);
+    for (t, row) in scheme.interpolation.iter().enumerate() {
+        for i in 0..(2 * m - 1) {
+            let mut sum = W::zero();
+            for (&coeff, value) in row.iter().zip(&values) {
+                if coeff != 0 {
+                    sum += W::from(coeff) * value[i].clone();
+                }
+            }
+            debug_assert!((sum.clone() % denominator.clone()).is_zero(), "Toom-Cook: inexact interpolation");
+            out.add(t * m + i, sum / denominator.clone());
+        }
+    }
+}
+//=======================================================================================================================
+fn evaluate <W: WideInteger> (parts: &[Vec<W>], point: Option<i64>) -> Vec<W> {
+    match point {
+        None => parts[parts.len() - 1].clone(),
+        Some(x) => {
+            let x = W::from(x);
+            let mut result = vec![W::zero(); parts[0].len()];
+            for part in parts.iter().rev() {
+                for (r, c) in result.iter_mut().zip(part) {
+                    *r = r.clone() * x.clone() + c.clone();
+                }
+            }
+            result
+        }
+    }
+}
+//=======================================================================================================================
+#[inline(always)]
+fn widen <I: RingInteger> (v: &[I]) -> Vec<I::Wide> {
+    v.iter().map(I::widen).collect()
+}
+//=======================================================================================================================
+fn split_at_padded <T: Coefficient> (v: &[T], m: usize) -> (Vec<T>, Vec<T>) {
+    let mut parts = split_padded(v, m, 2);
+    let high = parts.pop().unwrap();
+    let low = parts.pop().unwrap();
+    (low, high)
+}
+//=======================================================================================================================
+fn split_padded <T: Coefficient> (v: &[T], m: usize, count: usize) -> Vec<Vec<T>> {
+    (0..count).map(|i| {
+        let mut part: Vec<T> = v.iter().skip(i * m).take(m).cloned().collect();
+        part.resize(m, T::default());
+        part
+    }).collect()
+}
+//=======================================================================================================================
+fn add_vec <T: Coefficient> (a: &[T], b: &[T]) -> Vec<T> {
+    a.iter().zip(b).map(|(x, y)| x.clone() + y.clone()).collect()
+}
+//=======================================================================================================================
+pub trait Coefficient: Clone + Default + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + AddAssign + SubAssign {}
+impl <T: Clone + Default + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + AddAssign + SubAssign> Coefficient for T {}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polyxnm1::{Integer, RingParams, Wide};
+    use num_bigint::BigInt;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    const REDUCTIONS: [Reduction; 3] = [Reduction::Cyclic, Reduction::Negacyclic, Reduction::NtruPrime];
+
+    /// Linear schoolbook product folded by `fold`, independent of the `Reduced` accumulator.
+    fn reference (a: &[Integer], b: &[Integer], n: usize, reduction: Reduction) -> Vec<Wide> {
+        let mut linear = vec![0 as Wide; 2 * n - 1];
+        for (i, &x) in a.iter().enumerate() {
+            for (j, &y) in b.iter().enumerate() {
+                linear[i + j] += x as Wide * y as Wide;
+            }
+        }
+        fold(&linear, n, reduction)
+    }
+
+    fn random (n: usize, range: std::ops::Range<Integer>, rng: &mut ChaCha20Rng) -> Vec<Integer> {
+        (0..n).map(|_| rng.random_range(range.clone())).collect()
+    }
+
+    #[test]
+    fn methods_agree_with_schoolbook () {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        for reduction in REDUCTIONS {
+            for n in [2, 3, 7, 11, 64, 97, 128, 211] {
+                for module in [2048, 257] {
+                    let (a, b) = (random(n, 0..module, &mut rng), random(n, 0..module, &mut rng));
+                    let expected: Vec<Integer> = reference(&a, &b, n, reduction).into_iter()
+                        .map(|x| x.rem_euclid(module as Wide) as Integer)
+                        .collect();
+                    for method in [ConvolutionMethod::Auto].into_iter().chain(CONVOLUTION_METHODS) {
+                        let product = ring_convolution_with(method, &a, &b, n, reduction, module as UInteger);
+                        assert_eq!(product, expected, "{:?}, N = {}, {:?}, m = {}", method, n, reduction, module);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn auto_crosses_the_thresholds () {
+        let mut rng = ChaCha20Rng::seed_from_u64(3);
+        for n in [KARATSUBA_THRESHOLD - 1, KARATSUBA_THRESHOLD, TOOM4_THRESHOLD - 1, TOOM4_THRESHOLD + 3] {
+            let (a, b) = (random(n, -1..2, &mut rng), random(n, -1024..1024, &mut rng));
+            for reduction in REDUCTIONS {
+                let expected = reference(&a, &b, n, reduction);
+                assert_eq!(ring_product(&a, &b, n, reduction), expected, "N = {}, {:?}", n, reduction);
+
+                let mask = (1 << 11) - 1;
+                let wrapped: Vec<Wide> = Integer::wrapping_convolution(&a, &b, n, reduction).into_iter().map(|x| (x & mask) as Wide).collect();
+                assert_eq!(wrapped, expected.iter().map(|&x| x & mask as Wide).collect::<Vec<Wide>>(), "N = {}, {:?}", n, reduction);
+            }
+        }
+    }
+
+    #[test]
+    fn largest_fitting_module_does_not_overflow () {
+        let mut rng = ChaCha20Rng::seed_from_u64(4);
+        for n in [11, 64, 97, 701, 821] {
+            assert!(!convolution_fits::<Integer>(n, Integer::MAX as UInteger + 1));
+            let (mut low, mut high) = (2 as UInteger, Integer::MAX as UInteger);
+            while low < high {
+                let middle = low + (high - low).div_ceil(2);
+                if convolution_fits::<Integer>(n, middle) { low = middle } else { high = middle - 1 }
+            }
+            let module = low as Integer;
+            let top = vec![module - 1; n];
+            let (a, b) = (random(n, 0..module, &mut rng), random(n, 0..module, &mut rng));
+            for reduction in REDUCTIONS {
+                for (a, b) in [(&top, &top), (&a, &b)] {
+                    let expected: Vec<Integer> = reference(a, b, n, reduction).into_iter()
+                        .map(|x| x.rem_euclid(module as Wide) as Integer)
+                        .collect();
+                    assert_eq!(ring_convolution(a, b, n, reduction, low), expected, "N = {}, {:?}, m = {}", n, reduction, module);
+                }
+            }
+        }
+    }
+
+    /// The same product in every ring integer, for a module that fits all of them.
+    fn assert_integer_agrees <I: RingInteger> (a: &[Integer], b: &[Integer], n: usize, reduction: Reduction, expected: &[Wide]) {
+        let (a, b): (Vec<I>, Vec<I>) = (a.iter().map(|&x| I::from_integer(x)).collect(), b.iter().map(|&x| I::from_integer(x)).collect());
+        for module in [2048, 257] {
+            let product: Vec<Integer> = ring_convolution(&a, &b, n, reduction, module).iter().map(I::to_integer).collect();
+            let reduced: Vec<Integer> = expected.iter().map(|&x| x.rem_euclid(module as Wide) as Integer).collect();
+            assert_eq!(product, reduced, "{}, N = {}, {:?}, m = {}", I::NAME, n, reduction, module);
+        }
+
+        let mask = I::from_integer((1 << 11) - 1);
+        let wrapped: Vec<Integer> = I::wrapping_convolution(&a, &b, n, reduction).into_iter().map(|x| (x & mask.clone()).to_integer()).collect();
+        assert_eq!(wrapped, expected.iter().map(|&x| x.rem_euclid(1 << 11) as Integer).collect::<Vec<Integer>>(), "{}, N = {}, {:?}", I::NAME, n, reduction);
+    }
+
+    #[test]
+    fn ring_integers_agree () {
+        let mut rng = ChaCha20Rng::seed_from_u64(5);
+        for n in [11, 97, TOOM4_THRESHOLD + 3] {
+            let (a, b) = (random(n, -1..2, &mut rng), random(n, 0..2048, &mut rng));
+            for reduction in REDUCTIONS {
+                let expected = reference(&a, &b, n, reduction);
+                assert_integer_agrees::<i32>(&a, &b, n, reduction, &expected);
+                assert_integer_agrees::<i64>(&a, &b, n, reduction, &expected);
+                assert_integer_agrees::<i128>(&a, &b, n, reduction, &expected);
+                assert_integer_agrees::<BigInt>(&a, &b, n, reduction, &expected);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow the")]
+    fn rings_too_large_for_the_backend_are_rejected () {
+        RingParams::with_reduction(11, 3, Integer::MAX as UInteger + 1, Reduction::Cyclic);
+    }
+}
+//=======================================================================================================================
@@ -0,0 +1,234 @@
+use crate::polyxnm1::{Integer, Reduction, RingError, RingParams, UInteger};
+use crate::polyxnm1::convolution::{Coefficient, ring_convolution_wrapping, ring_product};
+use num_bigint::BigInt;
+use num_traits::{CheckedAdd, CheckedMul, CheckedSub, Euclid, FromPrimitive, One, Signed, ToPrimitive, Zero};
+use std::fmt;
+use std::num::Wrapping;
+use std::ops::{BitAnd, BitOr, BitXor};
+
+//=======================================================================================================================
+/// Coefficients of `Zp` and `PolyXNm1`, the way `LatticeInteger` is for lattice bases: i32, i64, i128 or `BigInt`.
+/// Rings of different integer types can be used side by side; `RingParams::check_integer` tells whether the
+/// products of a ring fit a type.
+pub trait RingInteger: Coefficient + Ord + Signed + Euclid + FromPrimitive + ToPrimitive + CheckedAdd + CheckedSub
+    + CheckedMul + BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self> + fmt::Display + fmt::Debug {
+    /// Holds a product of two residues (and sums of such products) before the reduction.
+    type Wide: WideInteger;
+
+    /// Name on the command line and in error messages.
+    const NAME: &'static str;
+
+    fn widen (&self) -> Self::Wide;
+
+    /// Inverse of `widen` for values that fit `Self`.
+    fn narrow (wide: Self::Wide) -> Self;
+
+    /// Whether `Wide` holds every value up to `bound` in absolute value.
+    fn wide_holds (bound: f64) -> bool;
+
+    /// -1 (all bits set) for a negative value, 0 otherwise.
+    fn negative_mask (&self) -> Self;
+
+    /// The 64 low bits of the two's complement, as `x as u64` gives them for a primitive.
+    fn low_u64 (&self) -> u64;
+
+    /// Product in Z_{2^k}[x]/(reduction polynomial) for every power-of-two module 2^k that fits `Self`; the caller
+    /// masks the result.
+    fn wrapping_convolution (a: &[Self], b: &[Self], n: usize, reduction: Reduction) -> Vec<Self>;
+
+    #[inline(always)]
+    fn holds (module: UInteger) -> bool {
+        module.to_u128().and_then(Self::from_u128).is_some()
+    }
+
+    /// `RingParams::check_integer` makes sure that the modules of a ring fit.
+    #[inline(always)]
+    fn from_module (module: UInteger) -> Self {
+        module.to_u128().and_then(Self::from_u128).expect("module does not fit the ring integer")
+    }
+
+    #[inline(always)]
+    fn wide_module (module: UInteger) -> Self::Wide {
+        Self::from_module(module).widen()
+    }
+
+    #[inline(always)]
+    fn from_integer (value: Integer) -> Self {
+        value.to_i128().and_then(Self::from_i128).expect("Integer does not fit the ring integer")
+    }
+
+    /// For values known to fit `Integer`, such as residues mod p.
+    #[inline(always)]
+    fn to_integer (&self) -> Integer {
+        self.to_i128().and_then(Integer::from_i128).expect("ring integer does not fit Integer")
+    }
+
+    /// The same value as another ring integer type.
+    fn convert <J: RingInteger> (&self) -> J {
+        self.to_i128().and_then(J::from_i128).or_else(|| self.to_u128().and_then(J::from_u128))
+            .expect("value does not fit the ring integer")
+    }
+}
+//=======================================================================================================================
+pub trait WideInteger: Coefficient + Ord + Signed + Euclid + From<i64> + BitAnd<Output = Self> + fmt::Debug {}
+impl <T: Coefficient + Ord + Signed + Euclid + From<i64> + BitAnd<Output = T> + fmt::Debug> WideInteger for T {}
+//=======================================================================================================================
+/// Primitives with a primitive twice as wide; the power-of-two products wrap around in the unsigned type.
+macro_rules! primitive_ring_integer {
+    ($integer:ty, $unsigned:ty, $wide:ty) => {
+        impl RingInteger for $integer {
+            type Wide = $wide;
+
+            const NAME: &'static str = stringify!($integer);
+
+            #[inline(always)]
+            fn widen (&self) -> $wide {
+                <$wide>::from(*self)
+            }
+
+            #[inline(always)]
+            fn narrow (wide: $wide) -> $integer {
+                wide as $integer
+            }
+
+            #[inline(always)]
+            fn wide_holds (bound: f64) -> bool {
+                bound < <$wide>::MAX as f64
+            }
+
+            #[inline(always)]
+            fn negative_mask (&self) -> $integer {
+                *self >> (<$integer>::BITS - 1)
+            }
+
+            #[inline(always)]
+            fn low_u64 (&self) -> u64 {
+                *self as u64
+            }
+
+            fn wrapping_convolution (a: &[$integer], b: &[$integer], n: usize, reduction: Reduction) -> Vec<$integer> {
+                let wrap = |v: &[$integer]| -> Vec<Wrapping<$unsigned>> { v.iter().map(|&x| Wrapping(x as $unsigned)).collect() };
+                ring_convolution_wrapping(&wrap(a), &wrap(b), n, reduction).into_iter().map(|x| x.0 as $integer).collect()
+            }
+        }
+    };
+}
+
+primitive_ring_integer!(i32, u32, i64);
+primitive_ring_integer!(i64, u64, i128);
+
+/// There is no primitive type wider than i128, so products only fit for modules below 2^63 (less for long
+/// polynomials).
+impl RingInteger for i128 {
+    type Wide = i128;
+
+    const NAME: &'static str = "i128";
+
+    #[inline(always)]
+    fn widen (&self) -> i128 {
+        *self
+    }
+
+    #[inline(always)]
+    fn narrow (wide: i128) -> i128 {
+        wide
+    }
+
+    #[inline(always)]
+    fn wide_holds (bound: f64) -> bool {
+        bound < i128::MAX as f64
+    }
+
+    #[inline(always)]
+    fn negative_mask (&self) -> i128 {
+        *self >> (i128::BITS - 1)
+    }
+
+    #[inline(always)]
+    fn low_u64 (&self) -> u64 {
+        *self as u64
+    }
+
+    fn wrapping_convolution (a: &[i128], b: &[i128], n: usize, reduction: Reduction) -> Vec<i128> {
+        let wrap = |v: &[i128]| -> Vec<Wrapping<u128>> { v.iter().map(|&x| Wrapping(x as u128)).collect() };
+        ring_convolution_wrapping(&wrap(a), &wrap(b), n, reduction).into_iter().map(|x| x.0 as i128).collect()
+    }
+}
+
+/// Every module fits; products are exact, so the power-of-two rings are reduced only by the mask.
+impl RingInteger for BigInt {
+    type Wide = BigInt;
+
+    const NAME: &'static str = "bigint";
+
+    #[inline(always)]
+    fn widen (&self) -> BigInt {
+        self.clone()
+    }
+
+    #[inline(always)]
+    fn narrow (wide: BigInt) -> BigInt {
+        wide
+    }
+
+    #[inline(always)]
+    fn wide_holds (_bound: f64) -> bool {
+        true
+    }
+
+    /// Not constant time, unlike the masks of the primitives.
+    fn negative_mask (&self) -> BigInt {
+        if self.is_negative() { -BigInt::one() } else { BigInt::zero() }
+    }
+
+    fn low_u64 (&self) -> u64 {
+        (self & BigInt::from(u64::MAX)).to_u64().unwrap()
+    }
+
+    fn wrapping_convolution (a: &[BigInt], b: &[BigInt], n: usize, reduction: Reduction) -> Vec<BigInt> {
+        ring_product(a, b, n, reduction)
+    }
+}
+//=======================================================================================================================
+/// Ring integer of an experiment, chosen on the command line (`cargo run -- integer bigint`); `Integer` by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegerType {
+    I32,
+    I64,
+    I128,
+    BigInt
+}
+
+pub const INTEGER_TYPES: [IntegerType; 4] = [IntegerType::I32, IntegerType::I64, IntegerType::I128, IntegerType::BigInt];
+
+impl IntegerType {
+    pub fn name (self) -> &'static str {
+        match self {
+            IntegerType::I32 => i32::NAME,
+            IntegerType::I64 => i64::NAME,
+            IntegerType::I128 => i128::NAME,
+            IntegerType::BigInt => BigInt::NAME
+        }
+    }
+
+    pub fn from_name (name: &str) -> Option<IntegerType> {
+        INTEGER_TYPES.into_iter().find(|integer| integer.name() == name)
+    }
+
+    /// `RingParams::check_integer` for this type.
+    pub fn check (self, params: &RingParams) -> Result<(), RingError> {
+        match self {
+            IntegerType::I32 => params.check_integer::<i32>(),
+            IntegerType::I64 => params.check_integer::<i64>(),
+            IntegerType::I128 => params.check_integer::<i128>(),
+            IntegerType::BigInt => params.check_integer::<BigInt>()
+        }
+    }
+}
+
+impl Default for IntegerType {
+    fn default () -> IntegerType {
+        IntegerType::from_name(Integer::NAME).unwrap()
+    }
+}
+//=======================================================================================================================
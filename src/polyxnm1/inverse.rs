@@ -0,0 +1,285 @@
+use crate::polyxnm1::{InversionMethod, PolyXNm1, Reduction, UInteger, UWide};
+use crate::polyxnm1::integer::RingInteger;
+use crate::polyxnm1::zp::*;
+use num_traits::{Euclid, Zero};
+use polynomial_ring::Polynomial;
+use std::fmt;
+
+//=======================================================================================================================
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InverseError {
+    /// f has a common factor with the reduction polynomial modulo the prime `module`, which divides the module of the ring.
+    NotInvertible { module: UInteger }
+}
+
+impl fmt::Display for InverseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InverseError::NotInvertible { module } => write!(f, "polynomial is not invertible modulo {} and the reduction polynomial", module)
+        }
+    }
+}
+//=======================================================================================================================
+/// Inverse of f in Z_m[x]/(reduction polynomial) for any module m = p1^k1 * ... * ps^ks: the inverse modulo every prime pi
+/// is found by the algorithm of `RingParams::inversion` (extended Euclid or divsteps), lifted to pi^ki by Newton
+/// (Hensel) iterations and the results are combined by the CRT.
+pub fn find_inv_polynomial <M: Module, I: RingInteger> (f: &PolyXNm1<M, I>) -> Result<PolyXNm1<M, I>, InverseError> {
+    match f.params().inversion() {
+        InversionMethod::Euclid => find_inv_polynomial_with(f, find_inv_polynomial_mod_prime),
+        InversionMethod::Divsteps => find_inv_polynomial_with(f, find_inv_polynomial_mod_prime_divsteps)
+    }
+}
+//=======================================================================================================================
+fn find_inv_polynomial_with <M: Module, I: RingInteger> (f: &PolyXNm1<M, I>, inverse_mod_prime: fn(&PolyXNm1<M, I>, UInteger) -> Option<Vec<I>>) -> Result<PolyXNm1<M, I>, InverseError> {
+    let params = f.params();
+    let n = params.n() as usize;
+    let f_integers = f.to_integers();
+
+    // the product of the prime powers so far divides the module, so it fits `UInteger`
+    let mut result = vec![I::zero(); n];
+    let mut result_module: UInteger = 1;
+    for (p, k) in factorize(M::get(params)) {
+        let inv_p = inverse_mod_prime(f, p).ok_or(InverseError::NotInvertible { module: p })?;
+        let module = p.pow(k);
+        let inv = hensel_lift::<M, I>(&f_integers, inv_p, n, params.reduction(), p, module);
+
+        result = crt(&result, result_module, &inv, module);
+        result_module *= module;
+    }
+
+    Ok(PolyXNm1::from_integers(params, &result))
+}
+//=======================================================================================================================
+/// Extended Euclidean algorithm for f and the reduction polynomial in Z_p[x], p prime.
+fn find_inv_polynomial_mod_prime <M: Module, I: RingInteger> (f: &PolyXNm1<M, I>, p: UInteger) -> Option<Vec<I>> {
+    let n = f.params().n() as usize;
+    let zp = |x: I| Zp::<M, I>::with_module(x, p);
+
+    // 1. initialization
+    let mut r0 = Polynomial::new(f.params().reduction().coeffs(n).into_iter().map(|x| zp(I::from_integer(x))).collect());
+    let mut r1 = Polynomial::new(f.coeffs().iter().map(|x| zp(x.get())).collect());
+    let mut t0: Polynomial<Zp<M, I>> = Polynomial::zero();
+    let mut t1 = Polynomial::new(vec![zp(I::one())]);
+
+    // 2. search GCD via r1
+    while !r1.is_zero() {
+        let r2_div = &r0 / &r1;
+        let r2_mod = r0 % &r1;
+        let t2 = t0 - r2_div * &t1;
+
+        r0 = r1;
+        r1 = r2_mod;
+        t0 = t1;
+        t1 = t2;
+    }
+
+    if r0.deg() != Some(0) {
+        return None;
+    }
+    let c = r0.coeffs()[0].get();
+    let inv_c = zp(find_inv(&c, &p)?);
+
+    let mut inv: Vec<I> = (t0 * Polynomial::new(vec![inv_c])).coeffs().iter().map(|x| x.get()).collect();
+    inv.resize(n, I::zero());
+    Some(inv)
+}
+//=======================================================================================================================
+/// Bernstein-Yang safegcd for f and the reduction polynomial in Z_p[x], p prime. Both polynomials are stored
+/// reversed, so every divstep cancels the constant term of g; after exactly 2N-1 divsteps delta = 0 iff their gcd is 1.
+/// The iteration count and the memory access pattern do not depend on f: swaps are done with masks.
+fn find_inv_polynomial_mod_prime_divsteps <M: Module, I: RingInteger> (f: &PolyXNm1<M, I>, p: UInteger) -> Option<Vec<I>> {
+    let n = f.params().n() as usize;
+    let module = I::wide_module(p);
+    let reduce = |x: I::Wide| I::narrow(x.rem_euclid(&module));
+
+    // 1. initialization: a = reverse(reduction polynomial), b = reverse(f)
+    let mut a: Vec<I> = f.params().reduction().coeffs(n).into_iter().rev().map(|x| reduce(I::from_integer(x).widen())).collect();
+    let mut b = vec![I::zero(); n + 1];
+    for (i, x) in f.coeffs().iter().enumerate() {
+        b[n - 1 - i] = reduce(x.get().widen());
+    }
+    let mut v = vec![I::zero(); n + 1];
+    let mut r = vec![I::zero(); n + 1];
+    r[0] = I::one();
+    let mut delta = I::one();
+
+    // 2. divsteps
+    for _ in 0..(2 * n - 1) {
+        v.rotate_right(1);
+        v[0] = I::zero();
+
+        let swap = (-delta.clone()).negative_mask() & nonzero_mask(&b[0]);
+        delta = delta.clone() ^ (swap.clone() & (delta.clone() ^ -delta));
+        delta += I::one();
+
+        conditional_swap(&mut a, &mut b, &swap);
+        conditional_swap(&mut v, &mut r, &swap);
+
+        let (a0, b0) = (a[0].widen(), b[0].widen());
+        for (x, y) in b.iter_mut().zip(&a) {
+            *x = reduce(a0.clone() * x.widen() - b0.clone() * y.widen());
+        }
+        for (x, y) in r.iter_mut().zip(&v) {
+            *x = reduce(a0.clone() * x.widen() - b0.clone() * y.widen());
+        }
+
+        b.rotate_left(1);
+        b[n] = I::zero();
+    }
+
+    if !delta.is_zero() {
+        return None;
+    }
+    let scale = find_inv(&a[0], &p)?.widen();
+    Some((0..n).map(|i| reduce(scale.clone() * v[n - 1 - i].widen())).collect())
+}
+//=======================================================================================================================
+#[inline(always)]
+fn nonzero_mask <I: RingInteger> (x: &I) -> I {
+    (x.clone() | -x.clone()).negative_mask()
+}
+
+#[inline(always)]
+fn conditional_swap <I: RingInteger> (a: &mut [I], b: &mut [I], mask: &I) {
+    for (x, y) in a.iter_mut().zip(b.iter_mut()) {
+        let t = mask.clone() & (x.clone() ^ y.clone());
+        *x = x.clone() ^ t.clone();
+        *y = y.clone() ^ t;
+    }
+}
+//=======================================================================================================================
+/// Newton iteration g <- g * (2 - f * g): if f * g = 1 mod p^j, then afterwards f * g = 1 mod p^2j.
+/// Computations are done modulo the target `module` = p^k directly.
+fn hensel_lift <M: Module, I: RingInteger> (f: &[I], mut g: Vec<I>, n: usize, reduction: Reduction, p: UInteger, module: UInteger) -> Vec<I> {
+    let two = I::one() + I::one();
+    let mut precision = p as UWide;
+    while precision < module as UWide {
+        precision = precision.saturating_mul(precision);
+
+        let mut two_minus_fg: Vec<I> = mul_mod::<M, I>(f, &g, n, reduction, module).into_iter().map(|x| M::reduce(-x, module)).collect();
+        two_minus_fg[0] = M::reduce(two_minus_fg[0].clone() + two.clone(), module);
+        g = mul_mod::<M, I>(&g, &two_minus_fg, n, reduction, module);
+    }
+    g
+}
+//=======================================================================================================================
+#[inline(always)]
+fn mul_mod <M: Module, I: RingInteger> (a: &[I], b: &[I], n: usize, reduction: Reduction, module: UInteger) -> Vec<I> {
+    M::convolution(a, b, n, reduction, module).into_iter().map(|x| M::reduce(x, module)).collect()
+}
+//=======================================================================================================================
+/// Coefficient-wise x = a mod m_a, x = b mod m_b for coprime m_a and m_b; the result is taken in [0, m_a * m_b).
+fn crt <I: RingInteger> (a: &[I], m_a: UInteger, b: &[I], m_b: UInteger) -> Vec<I> {
+    let m_a_inv = find_inv(&I::from_module(m_a % m_b), &m_b).unwrap_or_else(I::zero).widen();
+    let (m_a, m_b) = (I::wide_module(m_a), I::wide_module(m_b));
+
+    a.iter().zip(b).map(|(x, y)| {
+        let x = x.widen().rem_euclid(&m_a);
+        let t = ((y.widen() - x.clone()) * m_a_inv.clone()).rem_euclid(&m_b);
+        I::narrow(x + m_a.clone() * t)
+    }).collect()
+}
+//=======================================================================================================================
+/// Prime factorization by trial division: (prime, exponent) pairs in increasing order of primes.
+pub fn factorize (mut m: UInteger) -> Vec<(UInteger, u32)> {
+    let mut factors = Vec::new();
+    let mut d: UInteger = 2;
+    while d <= m / d {
+        if m.is_multiple_of(d) {
+            let mut k = 0;
+            while m.is_multiple_of(d) {
+                m /= d;
+                k += 1;
+            }
+            factors.push((d, k));
+        }
+        d += 1;
+    }
+    if m > 1 {
+        factors.push((m, 1));
+    }
+    factors
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polyxnm1::{Integer, RingParams};
+    use crate::ternary::sample_ternary;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    const REDUCTIONS: [Reduction; 3] = [Reduction::Cyclic, Reduction::Negacyclic, Reduction::NtruPrime];
+
+    /// Coefficients in [0, module): with center-mod p = 2 keeps both 1 and -1.
+    fn residues <M: Module> (a: &PolyXNm1<M>) -> Vec<Integer> {
+        let module = M::get(a.params()) as Integer;
+        a.to_integers().iter().map(|&x| x.rem_euclid(module)).collect()
+    }
+
+    fn is_one <M: Module> (a: &PolyXNm1<M>) -> bool {
+        residues(a).iter().enumerate().all(|(i, &x)| x == (i == 0) as Integer)
+    }
+
+    /// Both algorithms give the same inverse (or the same error) for random ternary f, and the inverse is one.
+    fn assert_methods_agree <M: Module> (params: &RingParams, rng: &mut ChaCha20Rng) {
+        let n = params.n() as usize;
+        for _ in 0..20 {
+            let f = sample_ternary(n, n / 3 + 1, n / 3, rng);
+            let euclid = find_inv_polynomial(&PolyXNm1::<M>::from_integers(params, &f));
+            let divsteps = find_inv_polynomial(&PolyXNm1::<M>::from_integers(&params.with_inversion(InversionMethod::Divsteps), &f));
+            assert_eq!(euclid.as_ref().map(residues), divsteps.as_ref().map(residues), "{:?}, f = {:?}", params, f);
+            if let Ok(inv) = euclid {
+                assert!(is_one(&(PolyXNm1::<M>::from_integers(params, &f) * inv)), "{:?}, f = {:?}", params, f);
+            }
+        }
+    }
+
+    #[test]
+    fn divsteps_agrees_with_euclid () {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for reduction in REDUCTIONS {
+            for n in [11, 16, 17] {
+                // prime, prime-power and composite q for p = 2 and p = 3
+                for (p, q) in [(2, 257), (2, 243), (2, 105), (3, 257), (3, 2048), (3, 1000)] {
+                    let params = RingParams::with_reduction(n, p, q, reduction);
+                    assert_methods_agree::<ModP>(&params, &mut rng);
+                    assert_methods_agree::<ModQ>(&params, &mut rng);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inverts_modulo_prime_prime_power_and_composite_modules () {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        for reduction in REDUCTIONS {
+            for (p, q) in [(3, 257), (3, 2048), (2, 243), (3, 1000), (2, 105)] {
+                let params = RingParams::with_reduction(17, p, q, reduction);
+                let inverses = (0..20).filter_map(|_| {
+                    let f = PolyXNm1::<ModQ>::from_integers(&params, &sample_ternary(17, 6, 5, &mut rng));
+                    find_inv_polynomial(&f).ok().map(|inv| (f, inv))
+                }).inspect(|(f, inv)| assert!(is_one(&(f * inv)), "{:?}, f = {}", params, f)).count();
+                assert!(inverses > 0, "{:?}: no ternary f was invertible", params);
+            }
+        }
+    }
+
+    #[test]
+    fn reports_the_prime_without_an_inverse () {
+        for inversion in [InversionMethod::Euclid, InversionMethod::Divsteps] {
+            // x - 1 divides x^N - 1 modulo every prime
+            let params = RingParams::new(11, 3, 1000).with_inversion(inversion);
+            let f = PolyXNm1::<ModQ>::from_integers(&params, &[-1, 1]);
+            assert_eq!(find_inv_polynomial(&f).err(), Some(InverseError::NotInvertible { module: 2 }));
+            let f = PolyXNm1::<ModP>::from_integers(&params, &[-1, 1]);
+            assert_eq!(find_inv_polynomial(&f).err(), Some(InverseError::NotInvertible { module: 3 }));
+
+            // x + 4 is invertible modulo 3 and 7, but x + 4 = x - 1 modulo 5
+            let params = RingParams::new(11, 2, 105).with_inversion(inversion);
+            let f = PolyXNm1::<ModQ>::from_integers(&params, &[4, 1]);
+            assert_eq!(find_inv_polynomial(&f).err(), Some(InverseError::NotInvertible { module: 5 }));
+        }
+    }
+}
+//=======================================================================================================================
@@ -1,273 +1,470 @@
+pub mod convolution;
+pub mod integer;
+pub mod inverse;
 pub mod service;
 pub mod zp;
 
-use num_traits::{One, Zero};
+use num_traits::Zero;
+use convolution::{convolution_fits, fold};
+use integer::{IntegerType, RingInteger};
 use service::*;
 use zp::*;
-use polynomial_ring::{Polynomial};
-use std::{ops::{Add, Div, Mul, Rem, Sub}, sync::OnceLock};
+use polynomial_ring::Polynomial;
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+
+/// Default integers of the build: i32, or i64 / i128 with the `integer-i64` / `integer-i128` features (i128 if both
+/// are enabled). `Zp` and `PolyXNm1` default to `Integer` coefficients, but are generic over every `RingInteger`, so
+/// an experiment can choose another type for its rings (`cargo run -- integer bigint`). `Wide` holds a product of two
+/// residues (and sums of such products) before the reduction. The parameters, the private keys and the attacks
+/// keep using `Integer`.
+#[cfg(not(any(feature = "integer-i64", feature = "integer-i128")))]
+mod backend {
+    pub type Integer = i32;
+    pub type UInteger = u32;
+    pub type Wide = i64;
+    pub type UWide = u64;
+}
+
+#[cfg(all(feature = "integer-i64", not(feature = "integer-i128")))]
+mod backend {
+    pub type Integer = i64;
+    pub type UInteger = u64;
+    pub type Wide = i128;
+    pub type UWide = u128;
+}
 
-pub type Integer = i32;
-pub type UInteger = u32;
+/// There is no primitive type wider than i128, so products only fit for q < 2^63 (less for long polynomials).
+#[cfg(feature = "integer-i128")]
+mod backend {
+    pub type Integer = i128;
+    pub type UInteger = u128;
+    pub type Wide = i128;
+    pub type UWide = u128;
+}
 
-pub static N: OnceLock<UInteger> = OnceLock::new();
-pub static P: OnceLock<UInteger> = OnceLock::new();
-pub static Q: OnceLock<UInteger> = OnceLock::new();
+pub use backend::*;
 
 //=======================================================================================================================
-pub fn init_polynomial_ring (n: UInteger, p: UInteger, q: UInteger) {
-    assert_eq!(gcd(p, q), 1, "GCD(p = {p}, q = {q}) is not equal to 1");
-    N.set(n).unwrap();
-    P.set(p).unwrap();
-    Q.set(q).unwrap();
+/// Reduction polynomial of the ring: x^N-1 for classic NTRU, x^N+1 for the power-of-two cyclotomics,
+/// x^N-x-1 (N prime) for Streamlined NTRU Prime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reduction {
+    Cyclic,
+    Negacyclic,
+    NtruPrime
 }
 //=======================================================================================================================
-pub struct PolyXNm1<M: GetModule> {
-    coeffs: Polynomial<Zp<M>>
+impl Reduction {
+    /// The N + 1 coefficients of the reduction polynomial, the constant term first.
+    pub fn coeffs (&self, n: usize) -> Vec<Integer> {
+        let mut coeffs = vec![0 as Integer; n + 1];
+        coeffs[n] = 1;
+        match self {
+            Reduction::Cyclic => coeffs[0] = -1,
+            Reduction::Negacyclic => coeffs[0] = 1,
+            Reduction::NtruPrime => {
+                coeffs[0] = -1;
+                coeffs[1] -= 1;
+            }
+        }
+        coeffs
+    }
 }
 //=======================================================================================================================
-impl <M: GetModule> PolyXNm1 <M> {
-    pub fn from_polynomial (polynomial: Polynomial<Integer>) -> PolyXNm1<M> {
-        assert_ne!(N.get(), None, "N parameter is not initialized. First initialize a polynomial ring via init_polynomial_ring() function");
-        assert_ne!(P.get(), None, "P parameter is not initialized. First initialize a polynomial ring via init_polynomial_ring() function");
-        assert_ne!(Q.get(), None, "Q parameter is not initialized. First initialize a polynomial ring via init_polynomial_ring() function");
-        
-        let polynomial_vec_coeffs: Vec<Zp<M>> = polynomial.coeffs().into_iter().map(|x| Zp::new(*x)).collect();
-        
-        PolyXNm1 { coeffs: Polynomial::new(polynomial_vec_coeffs) }
+impl fmt::Display for Reduction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reduction::Cyclic => write!(f, "x^N-1"),
+            Reduction::Negacyclic => write!(f, "x^N+1"),
+            Reduction::NtruPrime => write!(f, "x^N-x-1")
+        }
     }
+}
 //=======================================================================================================================
-    #[inline(always)]
-    pub fn to_polynomial (self) -> Polynomial<Zp<M>> {
-        self.coeffs
-    }
+/// Algorithm of `inverse::find_inv_polynomial` modulo every prime factor of the module.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InversionMethod {
+    /// Extended Euclidean algorithm.
+    #[default]
+    Euclid,
+    /// Bernstein-Yang divsteps: the same steps and memory accesses for every polynomial.
+    Divsteps
+}
 //=======================================================================================================================
-    #[inline(always)]
-    pub fn coeffs_len (&self) -> usize {
-        self.coeffs.coeffs().len()
-    }
+/// Parameters (N, p, q) of the ring Z[x]/(reduction polynomial) and its reductions mod p and mod q, with the
+/// inversion algorithm used in it. Every polynomial carries its own copy, so several parameter sets can be used
+/// side by side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RingParams {
+    n: UInteger,
+    p: UInteger,
+    q: UInteger,
+    reduction: Reduction,
+    inversion: InversionMethod
+}
 //=======================================================================================================================
-    #[inline(always)]
-    pub fn get_little_coeff (&self) -> zp::Zp<M> {
-        self.coeffs.coeffs()[0]
+/// Why `RingParams::try_new` rejects a ring.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RingError {
+    /// N = 0 or q < 2.
+    Degenerate { n: UInteger, q: UInteger },
+    /// gcd(p, q) != 1.
+    NotCoprime { p: UInteger, q: UInteger },
+    /// Products of N coefficients mod `module` overflow the ring integer `integer` (see `convolution_fits`).
+    IntegerOverflow { n: UInteger, module: UInteger, integer: &'static str }
+}
+
+impl fmt::Display for RingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RingError::Degenerate { n, q } => write!(f, "N = {} and q = {} must satisfy N >= 1 and q >= 2", n, q),
+            RingError::NotCoprime { p, q } => write!(f, "gcd(p = {}, q = {}) is not equal to 1", p, q),
+            RingError::IntegerOverflow { n, module, integer } =>
+                write!(f, "products of N = {} coefficients mod {} overflow the {} ring integers \
+                    (choose a wider one, e.g. `cargo run -- integer bigint`)", n, module, integer)
+        }
     }
+}
 //=======================================================================================================================
+impl RingParams {
     #[inline(always)]
-    pub fn get_xnm1 () -> PolyXNm1<M> {
-        let n = *N.get().unwrap() as usize;
-        let mut xnm1 = vec![Zp::new(-1)];
-        xnm1.append(&mut vec![Zp::zero(); n - 1]);
-        xnm1.push(Zp::one());
+    pub fn new (n: UInteger, p: UInteger, q: UInteger) -> RingParams {
+        RingParams::with_reduction(n, p, q, Reduction::Cyclic)
+    }
 
-        PolyXNm1 { coeffs: Polynomial::new(xnm1) }
+    /// `try_new` for parameters known to be valid; panics otherwise.
+    pub fn with_reduction (n: UInteger, p: UInteger, q: UInteger, reduction: Reduction) -> RingParams {
+        RingParams::try_new(n, p, q, reduction).unwrap_or_else(|error| panic!("RingParams: {}", error))
     }
-//=======================================================================================================================
-    pub fn change_module <NewM: Module> (&self) -> PolyXNm1<NewM> {
-        let result: Vec<Zp<NewM>> = self.coeffs.coeffs().into_iter().map(|x| Zp::<NewM>::new(x.get())).collect();
-        PolyXNm1 { coeffs: Polynomial::new(result) }
+
+    /// `try_with_integer` for polynomials with `Integer` coefficients.
+    #[inline(always)]
+    pub fn try_new (n: UInteger, p: UInteger, q: UInteger, reduction: Reduction) -> Result<RingParams, RingError> {
+        RingParams::try_with_integer(n, p, q, reduction, IntegerType::default())
     }
-//=======================================================================================================================
-    #[allow(dead_code)]
-    pub fn to_string (&self) -> String {
-        self.coeffs.to_string()
+
+    /// Ring for polynomials with coefficients of type `integer`. The polynomials mod p (the inverse fp of the private
+    /// key) always use `Integer`, so p has to fit it in any case.
+    pub fn try_with_integer (n: UInteger, p: UInteger, q: UInteger, reduction: Reduction, integer: IntegerType) -> Result<RingParams, RingError> {
+        if n == 0 || q < 2 {
+            return Err(RingError::Degenerate { n, q });
+        }
+        if gcd(p, q) != 1 {
+            return Err(RingError::NotCoprime { p, q });
+        }
+        if !convolution_fits::<Integer>(n as usize, p) {
+            return Err(RingError::IntegerOverflow { n, module: p, integer: Integer::NAME });
+        }
+        let params = RingParams { n, p, q, reduction, inversion: InversionMethod::Euclid };
+        integer.check(&params)?;
+        Ok(params)
     }
-}
-//=======================================================================================================================
-impl <'a, 'b, M: Module> Add<&'b PolyXNm1<M>> for &'a PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
 
-    fn add(self, rhs: &'b PolyXNm1<M>) -> Self::Output {
-        PolyXNm1 {
-            coeffs: &self.coeffs + &rhs.coeffs,
+    /// Whether the products of N residues mod p and mod q fit the ring integer `I`.
+    pub fn check_integer <I: RingInteger> (&self) -> Result<(), RingError> {
+        let module = self.p.max(self.q);
+        if !convolution_fits::<I>(self.n as usize, module) {
+            return Err(RingError::IntegerOverflow { n: self.n, module, integer: I::NAME });
         }
+        Ok(())
     }
-}
 
-impl <M: Module> Add<PolyXNm1<M>> for PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+    /// The same ring with polynomials inverted by `inversion`.
+    #[inline(always)]
+    pub fn with_inversion (self, inversion: InversionMethod) -> RingParams {
+        RingParams { inversion, ..self }
+    }
 
-    fn add(self, rhs: PolyXNm1<M>) -> Self::Output {
-        &self + &rhs
+    #[inline(always)]
+    pub fn n (&self) -> UInteger {
+        self.n
     }
-}
 
-impl <'a, M: Module> Add<&'a PolyXNm1<M>> for PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+    #[inline(always)]
+    pub fn p (&self) -> UInteger {
+        self.p
+    }
 
-    fn add(self, rhs: &'a PolyXNm1<M>) -> Self::Output {
-        &self + rhs
+    #[inline(always)]
+    pub fn q (&self) -> UInteger {
+        self.q
     }
-}
 
-impl <'a, M: Module> Add<PolyXNm1<M>> for &'a PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+    #[inline(always)]
+    pub fn reduction (&self) -> Reduction {
+        self.reduction
+    }
 
-    fn add(self, rhs: PolyXNm1<M>) -> Self::Output {
-        self + &rhs
+    #[inline(always)]
+    pub fn inversion (&self) -> InversionMethod {
+        self.inversion
     }
 }
 //=======================================================================================================================
-impl <'a, 'b, M: Module> Sub<&'b PolyXNm1<M>> for &'a PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
-
-    fn sub(self, rhs: &'b PolyXNm1<M>) -> Self::Output {
-        PolyXNm1 {
-            coeffs: &self.coeffs - &rhs.coeffs,
-        }
-    }
+/// Element of Z_M[x]/(x^N-1), Z_M[x]/(x^N+1) or Z_M[x]/(x^N-x-1), as chosen by `RingParams::reduction`, stored densely:
+/// exactly N coefficients of the ring integer type `I`, the i-th one at index i. The name dates from when x^N-1 was
+/// the only ring.
+pub struct PolyXNm1<M: GetModule, I: RingInteger = Integer> {
+    params: RingParams,
+    coeffs: Vec<Zp<M, I>>
 }
-
-impl <'a, M: Module> Sub <&'a PolyXNm1<M>> for PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
-
-    fn sub(self, rhs: &'a PolyXNm1<M>) -> Self::Output {
-        &self - rhs
+//=======================================================================================================================
+impl <M: GetModule, I: RingInteger> PolyXNm1 <M, I> {
+    pub fn zero (params: &RingParams) -> PolyXNm1<M, I> {
+        PolyXNm1 { params: *params, coeffs: vec![Zp::new(I::zero(), params); params.n() as usize] }
     }
-}
-
-impl <'a, M: Module> Sub <PolyXNm1<M>> for &'a PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+//=======================================================================================================================
+    /// Coefficients beyond N are folded back with the reduction polynomial.
+    pub fn from_integers (params: &RingParams, integers: &[I]) -> PolyXNm1<M, I> {
+        let coeffs = fold(integers, params.n() as usize, params.reduction());
 
-    fn sub(self, rhs: PolyXNm1<M>) -> Self::Output {
-        self - &rhs
+        PolyXNm1 { params: *params, coeffs: coeffs.into_iter().map(|x| Zp::new(x, params)).collect() }
+    }
+//=======================================================================================================================
+    /// `from_integers` for coefficients given as `Integer`, such as ternary polynomials and messages.
+    pub fn from_small (params: &RingParams, integers: &[Integer]) -> PolyXNm1<M, I> {
+        let integers: Vec<I> = integers.iter().map(|&x| I::from_integer(x)).collect();
+        PolyXNm1::from_integers(params, &integers)
+    }
+//=======================================================================================================================
+    #[inline(always)]
+    pub fn from_polynomial (params: &RingParams, polynomial: Polynomial<I>) -> PolyXNm1<M, I> {
+        PolyXNm1::from_integers(params, polynomial.coeffs())
+    }
+//=======================================================================================================================
+    pub fn from_zp_polynomial (params: &RingParams, polynomial: &Polynomial<Zp<M, I>>) -> PolyXNm1<M, I> {
+        let integers: Vec<I> = polynomial.coeffs().iter().map(|x| x.get()).collect();
+        PolyXNm1::from_integers(params, &integers)
+    }
+//=======================================================================================================================
+    #[inline(always)]
+    pub fn params (&self) -> &RingParams {
+        &self.params
+    }
+//=======================================================================================================================
+    #[inline(always)]
+    pub fn coeffs (&self) -> &[Zp<M, I>] {
+        &self.coeffs
+    }
+//=======================================================================================================================
+    #[inline(always)]
+    pub fn coeff (&self, i: usize) -> Zp<M, I> {
+        self.coeffs[i].clone()
+    }
+//=======================================================================================================================
+    pub fn to_integers (&self) -> Vec<I> {
+        self.coeffs.iter().map(|x| x.get()).collect()
+    }
+//=======================================================================================================================
+    /// Sparse representation for the Euclidean-algorithm paths (trailing zeros are trimmed).
+    pub fn to_polynomial (&self) -> Polynomial<Zp<M, I>> {
+        Polynomial::new(self.coeffs.clone())
+    }
+//=======================================================================================================================
+    pub fn is_zero (&self) -> bool {
+        self.coeffs.iter().all(|x| x.is_zero())
+    }
+//=======================================================================================================================
+    /// The reduction polynomial does not fit into N coefficients, so it is only available as a `Polynomial`.
+    pub fn get_reduction_polynomial (params: &RingParams) -> Polynomial<Zp<M, I>> {
+        let coeffs = params.reduction().coeffs(params.n() as usize);
+        Polynomial::new(coeffs.into_iter().map(|x| Zp::new(I::from_integer(x), params)).collect())
+    }
+//=======================================================================================================================
+    pub fn change_module <NewM: Module> (&self) -> PolyXNm1<NewM, I> {
+        let result: Vec<Zp<NewM, I>> = self.coeffs.iter().map(|x| Zp::<NewM, I>::new(x.get(), &self.params)).collect();
+        PolyXNm1 { params: self.params, coeffs: result }
+    }
+//=======================================================================================================================
+    /// The same polynomial with coefficients of the ring integer `J`; the ring has to fit `J`.
+    pub fn change_integer <J: RingInteger> (&self) -> Result<PolyXNm1<M, J>, RingError> {
+        self.params.check_integer::<J>()?;
+        let result: Vec<Zp<M, J>> = self.coeffs.iter().map(|x| Zp::<M, J>::new(x.get().convert(), &self.params)).collect();
+        Ok(PolyXNm1 { params: self.params, coeffs: result })
     }
 }
-
-impl <M: Module> Sub <PolyXNm1<M>> for PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
-
-    fn sub(self, rhs: PolyXNm1<M>) -> Self::Output {
-        &self - &rhs
+//=======================================================================================================================
+impl <M: GetModule, I: RingInteger> fmt::Display for PolyXNm1<M, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Polynomial::new(self.coeffs.clone()))
     }
 }
 //=======================================================================================================================
-impl <'a, 'b, M: Module> Mul<&'b PolyXNm1<M>> for &'a PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
-
-    fn mul(self, rhs: &'b PolyXNm1<M>) -> Self::Output {
-        let mut result  = &self.coeffs * &rhs.coeffs;
-
-        let n = *N.get().unwrap() as usize;
-        let result_len = result.coeffs().len();
-        if result_len > n {
-            let bigger_coeffs = Polynomial::new(result.coeffs()[n..result_len].to_vec());
-            result += &bigger_coeffs;
-            let mut substract_bigger_coeffs = vec![zp::Zp::zero(); n];
-            substract_bigger_coeffs.append(&mut bigger_coeffs.coeffs().to_vec());
-            result -= Polynomial::new(substract_bigger_coeffs);
-        }
+#[inline(always)]
+fn assert_same_ring (a: &RingParams, b: &RingParams) {
+    assert_eq!(a, b, "PolyXNm1: operands belong to different polynomial rings");
+}
+//=======================================================================================================================
+impl <M: Module, I: RingInteger> Add<&PolyXNm1<M, I>> for &PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
 
+    fn add(self, rhs: &PolyXNm1<M, I>) -> Self::Output {
+        assert_same_ring(&self.params, &rhs.params);
         PolyXNm1 {
-            coeffs: result,
+            params: self.params,
+            coeffs: self.coeffs.iter().zip(&rhs.coeffs).map(|(x, y)| x.clone() + y.clone()).collect(),
         }
     }
 }
 
-impl <M: Module> Mul<PolyXNm1<M>> for PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+impl <M: Module, I: RingInteger> Add<&PolyXNm1<M, I>> for PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
 
-    fn mul(self, rhs: Self) -> Self::Output {
-        &self * &rhs
+    fn add(self, rhs: &PolyXNm1<M, I>) -> Self::Output {
+        &self + rhs
     }
 }
 
-impl <'a, M: Module> Mul<PolyXNm1<M>> for &'a PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+impl <M: Module, I: RingInteger> Add<PolyXNm1<M, I>> for &PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
 
-    fn mul(self, rhs: PolyXNm1<M>) -> Self::Output {
-        self * &rhs
+    fn add(self, rhs: PolyXNm1<M, I>) -> Self::Output {
+        self + &rhs
     }
 }
 
-impl <'a, M: Module> Mul<&'a PolyXNm1<M>> for PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+impl <M: Module, I: RingInteger> Add<PolyXNm1<M, I>> for PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
 
-    fn mul(self, rhs: &'a PolyXNm1<M>) -> Self::Output {
-        &self * rhs
+    fn add(self, rhs: PolyXNm1<M, I>) -> Self::Output {
+        &self + &rhs
     }
 }
 //=======================================================================================================================
-impl <'a, 'b, M: Module> Div<&'b PolyXNm1<M>> for &'a PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+impl <M: Module, I: RingInteger> Sub<&PolyXNm1<M, I>> for &PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
 
-    fn div(self, rhs: &'b PolyXNm1<M>) -> Self::Output {
+    fn sub(self, rhs: &PolyXNm1<M, I>) -> Self::Output {
+        assert_same_ring(&self.params, &rhs.params);
         PolyXNm1 {
-            coeffs: &self.coeffs / &rhs.coeffs,
+            params: self.params,
+            coeffs: self.coeffs.iter().zip(&rhs.coeffs).map(|(x, y)| x.clone() - y.clone()).collect(),
         }
     }
 }
 
-impl <'a, M: Module> Div<&'a PolyXNm1<M>> for PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+impl <M: Module, I: RingInteger> Sub<&PolyXNm1<M, I>> for PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
 
-    fn div(self, rhs: &'a PolyXNm1<M>) -> Self::Output {
-        &self / rhs
+    fn sub(self, rhs: &PolyXNm1<M, I>) -> Self::Output {
+        &self - rhs
     }
 }
 
-impl <'a, M: Module> Div<PolyXNm1<M>> for &'a PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+impl <M: Module, I: RingInteger> Sub<PolyXNm1<M, I>> for &PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
 
-    fn div(self, rhs: PolyXNm1<M>) -> Self::Output {
-        self / &rhs
+    fn sub(self, rhs: PolyXNm1<M, I>) -> Self::Output {
+        self - &rhs
     }
 }
 
-impl <M: Module> Div for PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+impl <M: Module, I: RingInteger> Sub<PolyXNm1<M, I>> for PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
 
-    fn div(self, rhs: PolyXNm1<M>) -> Self::Output {
-        &self / &rhs
+    fn sub(self, rhs: PolyXNm1<M, I>) -> Self::Output {
+        &self - &rhs
     }
 }
 //=======================================================================================================================
-impl <'a, 'b, M: Module> Rem<&'b PolyXNm1<M>> for &'a PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+impl <M: Module, I: RingInteger> Mul<&PolyXNm1<M, I>> for &PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
+
+    fn mul(self, rhs: &PolyXNm1<M, I>) -> Self::Output {
+        assert_same_ring(&self.params, &rhs.params);
+        let module = M::get(&self.params);
+        let result = M::convolution(&self.to_integers(), &rhs.to_integers(), self.params.n() as usize, self.params.reduction(), module);
 
-    fn rem(self, rhs: &'b PolyXNm1<M>) -> Self::Output {
         PolyXNm1 {
-            coeffs: &self.coeffs - &self.coeffs / &rhs.coeffs * &rhs.coeffs,
+            params: self.params,
+            coeffs: result.into_iter().map(|x| Zp::with_module(x, module)).collect(),
         }
     }
 }
 
-impl <'a, M: Module> Rem<&'a PolyXNm1<M>> for PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+impl <M: Module, I: RingInteger> Mul<&PolyXNm1<M, I>> for PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
 
-    fn rem(self, rhs: &'a PolyXNm1<M>) -> Self::Output {
-        &self % rhs
+    fn mul(self, rhs: &PolyXNm1<M, I>) -> Self::Output {
+        &self * rhs
     }
 }
 
-impl <'a, M: Module> Rem<PolyXNm1<M>> for &'a PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+impl <M: Module, I: RingInteger> Mul<PolyXNm1<M, I>> for &PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
 
-    fn rem(self, rhs: PolyXNm1<M>) -> Self::Output {
-        self % &rhs
+    fn mul(self, rhs: PolyXNm1<M, I>) -> Self::Output {
+        self * &rhs
     }
 }
 
-impl <M: Module> Rem<PolyXNm1<M>> for PolyXNm1<M> {
-    type Output = PolyXNm1<M>;
+impl <M: Module, I: RingInteger> Mul<PolyXNm1<M, I>> for PolyXNm1<M, I> {
+    type Output = PolyXNm1<M, I>;
 
-    fn rem(self, rhs: PolyXNm1<M>) -> Self::Output {
-        &self % &rhs
+    fn mul(self, rhs: PolyXNm1<M, I>) -> Self::Output {
+        &self * &rhs
     }
 }
 //=======================================================================================================================
-impl <M: GetModule> PartialEq for PolyXNm1<M> {
+impl <M: GetModule, I: RingInteger> PartialEq for PolyXNm1<M, I> {
     fn eq(&self, other: &Self) -> bool {
-        self.coeffs == other.coeffs
-    }
-
-    fn ne(&self, other: &Self) -> bool {
-        self.coeffs != other.coeffs
+        self.params == other.params && self.coeffs == other.coeffs
     }
 }
 //=======================================================================================================================
-impl <M: Module> Clone for PolyXNm1<M> {
+impl <M: Module, I: RingInteger> Clone for PolyXNm1<M, I> {
     fn clone(&self) -> Self {
-        Self { coeffs: self.coeffs.clone() }
+        Self { params: self.params, coeffs: self.coeffs.clone() }
     }
 }
-//=======================================================================================================================
\ No newline at end of file
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::One;
+
+    fn x_power (params: &RingParams, k: usize) -> PolyXNm1<ModQ> {
+        let mut coeffs = vec![0 as Integer; params.n() as usize];
+        coeffs[k] = 1;
+        PolyXNm1::from_integers(params, &coeffs)
+    }
+
+    #[test]
+    fn rings_side_by_side () {
+        let cyclic = RingParams::with_reduction(11, 3, 257, Reduction::Cyclic);
+        let negacyclic = RingParams::with_reduction(16, 3, 2048, Reduction::Negacyclic);
+        for (params, wrapped) in [(cyclic, 1), (negacyclic, -1)] {
+            let n = params.n() as usize;
+            let product = x_power(&params, n - 1) * x_power(&params, 1);
+            assert_eq!(product.params(), &params);
+            assert!(product.coeff(0) == Zp::new(wrapped, &params));
+            assert!(product.coeffs()[1..].iter().all(|x| x.is_zero()));
+            assert_eq!(product.coeff(0).module(), Some(params.q()));
+        }
+
+        let minus_one = Zp::<ModQ>::new(-1, &cyclic);
+        assert!(minus_one + Zp::one() == Zp::zero());
+        assert!(Zp::<ModQ>::one() + Zp::new(2047, &negacyclic) == Zp::zero());
+        assert_eq!((Zp::<ModQ>::one() + Zp::one()).module(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "different polynomial rings")]
+    fn polynomials_of_different_rings_do_not_mix () {
+        let _ = x_power(&RingParams::new(11, 3, 257), 1) + x_power(&RingParams::new(11, 3, 251), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "operands modulo")]
+    fn residues_of_different_modules_do_not_mix () {
+        let _ = Zp::<ModQ>::new(1, &RingParams::new(11, 3, 257)) * Zp::new(1, &RingParams::new(11, 3, 251));
+    }
+
+    #[test]
+    #[should_panic(expected = "not a power of two")]
+    fn mask_reduction_needs_a_power_of_two () {
+        PolyXNm1::<ModQPow2>::from_integers(&RingParams::new(11, 3, 257), &[1]);
+    }
+}
+//=======================================================================================================================
@@ -1,5 +1,5 @@
 use crate::polyxnm1::UInteger;
-use crate::polyxnm1::Integer;
+use crate::polyxnm1::integer::RingInteger;
 
 //=======================================================================================================================
 pub fn gcd(mut p: UInteger, mut q: UInteger) -> UInteger {
@@ -12,16 +12,36 @@ pub fn gcd(mut p: UInteger, mut q: UInteger) -> UInteger {
     q
 }
 //=======================================================================================================================
-pub fn mod_center (a: Integer, p: UInteger) -> Integer {
-    let result = a % p as Integer;
-    if result > (p as Integer - 1) / 2 {
-        result - p as Integer
+pub fn mod_center <I: RingInteger> (a: I, p: UInteger) -> I {
+    let module = I::from_module(p);
+    let half = I::from_module((p - 1) / 2);
+    let result = a % module.clone();
+    if result > half {
+        result - module
     }
-    else if result < -(p as Integer - 1) / 2 {
-        result + p as Integer
+    else if result < -half {
+        result + module
     }
     else {
         result
     }
 }
-//=======================================================================================================================
\ No newline at end of file
+//=======================================================================================================================
+/// `count` (at most 64) bits starting at bit `position`, least significant bit first.
+#[inline(always)]
+pub fn read_bits (bytes: &[u8], position: usize, count: usize) -> u64 {
+    (0..count).fold(0, |acc, j| {
+        let bit = position + j;
+        acc | (((bytes[bit / 8] >> (bit % 8)) & 1) as u64) << j
+    })
+}
+//=======================================================================================================================
+/// Writes the `count` low bits of `value` starting at bit `position` (the bits must be zero).
+#[inline(always)]
+pub fn write_bits (bytes: &mut [u8], position: usize, count: usize, value: u64) {
+    for j in 0..count {
+        let bit = position + j;
+        bytes[bit / 8] |= (((value >> j) & 1) as u8) << (bit % 8);
+    }
+}
+//=======================================================================================================================
@@ -1,10 +1,13 @@
 use num_traits::One;
 use num_traits::Zero;
+use num_traits::Euclid;
 
 use crate::polyxnm1::Integer;
+use crate::polyxnm1::Reduction;
+use crate::polyxnm1::RingParams;
 use crate::polyxnm1::UInteger;
-use crate::polyxnm1::P;
-use crate::polyxnm1::Q;
+use crate::polyxnm1::convolution::ring_convolution;
+use crate::polyxnm1::integer::RingInteger;
 #[cfg(feature = "center-mod")] use crate::polyxnm1::service::mod_center;
 
 use std::marker::PhantomData;
@@ -19,189 +22,230 @@ use std::ops::Sub;
 use std::ops::SubAssign;
 
 //=======================================================================================================================
-pub struct Zp<M: GetModule> {
-    value: Integer,
+/// Residue modulo `module`, stored as a ring integer `I`. The constants `Zp::zero()` and `Zp::one()` are not bound
+/// to a ring (`module` is `None`): they take the module of the other operand at the first operation.
+pub struct Zp<M: GetModule, I: RingInteger = Integer> {
+    value: I,
+    module: Option<UInteger>,
     _marker: PhantomData<M>
 }
 //=======================================================================================================================
-impl <M: GetModule> Zp<M> {
-    #[cfg(feature = "center-mod")]
-    pub fn new (value: Integer) -> Zp<M>{
-        let p = M::get();
+impl <M: GetModule, I: RingInteger> Zp<M, I> {
+    #[inline(always)]
+    pub fn new (value: I, params: &RingParams) -> Zp<M, I> {
+        Zp::with_module(value, M::get(params))
+    }
+
+    #[inline(always)]
+    pub fn with_module (value: I, module: UInteger) -> Zp<M, I> {
         Zp {
-            value: mod_center(value, p),
+            value: M::reduce(value, module),
+            module: Some(module),
             _marker: PhantomData
         }
     }
-    
-    #[cfg(not(feature = "center-mod"))]
-    pub fn new (value: Integer) -> Zp<M>{
-        let p = M::get() as Integer;
-        if value >= p || value < 0 {
-            Zp {
-                value: (value % p + p) % p,
-                _marker: PhantomData
+//=======================================================================================================================
+    pub fn get (&self) -> I {
+        self.value.clone()
+    }
+//=======================================================================================================================
+    /// `None` for a constant not yet bound to a ring.
+    pub fn module (&self) -> Option<UInteger> {
+        self.module
+    }
+//=======================================================================================================================
+    /// Module of a result; residues of two different modules never meet.
+    #[inline(always)]
+    fn common_module (&self, rhs: &Self) -> Option<UInteger> {
+        match (self.module, rhs.module) {
+            (Some(a), Some(b)) => {
+                assert_eq!(a, b, "Zp: operands modulo {} and {}", a, b);
+                Some(a)
             }
-        }
-        else {
-            Zp {
-                 value,
-                 _marker: PhantomData
-            } 
+            (a, b) => a.or(b)
         }
     }
-//=======================================================================================================================
-    pub fn get (&self) -> Integer {
-        self.value
+
+    /// `bound` modulo the common module, or `unbound` on two constants, which stay plain integers.
+    #[inline(always)]
+    fn combine (self, rhs: Self, bound: fn(I, I, UInteger) -> I, unbound: fn(&I, &I) -> Option<I>) -> Self {
+        let module = self.common_module(&rhs);
+        let value = match module {
+            Some(module) => bound(self.value, rhs.value, module),
+            None => unbound(&self.value, &rhs.value).expect("Zp: integer overflow of constants not bound to a ring")
+        };
+        Zp { value, module, _marker: PhantomData }
     }
 }
 //=======================================================================================================================
-impl <M: GetModule> fmt::Display for Zp<M> {
+impl <M: GetModule, I: RingInteger> fmt::Display for Zp<M, I> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.value)
     }
 }
 //=======================================================================================================================
-impl <M: GetModule> Clone for Zp<M> {
+impl <M: GetModule, I: RingInteger> Clone for Zp<M, I> {
     fn clone(&self) -> Self {
-        Self {
-            value: self.value,
-            _marker: PhantomData
-        }
+        Zp { value: self.value.clone(), module: self.module, _marker: PhantomData }
     }
 }
 
-impl <M: GetModule> Copy for Zp<M> { }
+impl <M: GetModule, I: RingInteger + Copy> Copy for Zp<M, I> { }
 //=======================================================================================================================
-impl <M: GetModule> Add for Zp<M> {
+impl <M: GetModule, I: RingInteger> Add for Zp<M, I> {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Zp::new(self.value + rhs.value)
+        self.combine(rhs, M::add, I::checked_add)
     }
 }
 
-impl <M: Module> AddAssign for Zp<M> {
+impl <M: GetModule, I: RingInteger> AddAssign for Zp<M, I> {
     fn add_assign(&mut self, rhs: Self) {
-        *self = *self + rhs;
+        *self = self.clone() + rhs;
     }
 }
 
-impl<'a, M: Module> AddAssign<&'a Zp<M>> for Zp<M> {
-    fn add_assign(&mut self, rhs: &'a Zp<M>) {
-        *self = *self + *rhs;
+impl <M: GetModule, I: RingInteger> AddAssign<&Zp<M, I>> for Zp<M, I> {
+    fn add_assign(&mut self, rhs: &Zp<M, I>) {
+        *self = self.clone() + rhs.clone();
     }
 }
 //=======================================================================================================================
-impl <M: GetModule> Sub for Zp<M> {
+impl <M: GetModule, I: RingInteger> Sub for Zp<M, I> {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Zp::new(self.value - rhs.value)
+        self.combine(rhs, M::sub, I::checked_sub)
     }
 }
 
-impl <M: GetModule> SubAssign for Zp<M> {
+impl <M: GetModule, I: RingInteger> SubAssign for Zp<M, I> {
     fn sub_assign(&mut self, rhs: Self) {
-        *self = *self - rhs;
+        *self = self.clone() - rhs;
     }
 }
 
-impl<'a, M: Module> SubAssign<&'a Zp<M>> for Zp<M> {
-    fn sub_assign(&mut self, rhs: &'a Zp<M>) {
-        *self = *self - *rhs;
+impl <M: GetModule, I: RingInteger> SubAssign<&Zp<M, I>> for Zp<M, I> {
+    fn sub_assign(&mut self, rhs: &Zp<M, I>) {
+        *self = self.clone() - rhs.clone();
     }
 }
 //=======================================================================================================================
-impl <M: GetModule> Mul for Zp<M> {
+impl <M: GetModule, I: RingInteger> Mul for Zp<M, I> {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        Zp::new(self.value * rhs.value)
+        self.combine(rhs, M::mul, I::checked_mul)
     }
 }
 
-impl <M: GetModule> MulAssign for Zp<M> {
+impl <M: GetModule, I: RingInteger> MulAssign for Zp<M, I> {
     fn mul_assign(&mut self, rhs: Self) {
-        *self = *self * rhs;
+        *self = self.clone() * rhs;
     }
 }
 
-impl<'a, M: Module> MulAssign<&'a Zp<M>> for Zp<M> {
-    fn mul_assign(&mut self, rhs: &'a Zp<M>) {
-        *self = *self * *rhs;
+impl <M: GetModule, I: RingInteger> MulAssign<&Zp<M, I>> for Zp<M, I> {
+    fn mul_assign(&mut self, rhs: &Zp<M, I>) {
+        *self = self.clone() * rhs.clone();
     }
 }
 //=======================================================================================================================
-impl <M: GetModule> Div for Zp<M> {
+impl <M: GetModule, I: RingInteger> Div for Zp<M, I> {
     type Output = Self;
 
     fn div(self, rhs: Self) -> Self::Output {
-        assert_ne!(rhs.value, 0, "Zp div: Division by zero");
-        let inv = find_inv(&rhs.value, &M::get()).unwrap();
-        Zp::new(self.value * inv)
+        assert!(!rhs.value.is_zero(), "Zp div: Division by zero");
+        let module = self.common_module(&rhs).expect("Zp div: constants not bound to a ring");
+        let inv = find_inv(&rhs.value, &module).unwrap();
+        Zp { value: M::mul(self.value, inv, module), module: Some(module), _marker: PhantomData }
     }
 }
 
-impl <M: GetModule> DivAssign for Zp<M> {
+impl <M: GetModule, I: RingInteger> DivAssign for Zp<M, I> {
     fn div_assign(&mut self, rhs: Self) {
-        *self = *self / rhs;
+        *self = self.clone() / rhs;
     }
 }
 
-impl<'a, M: Module> DivAssign<&'a Zp<M>> for Zp<M> {
-    fn div_assign(&mut self, rhs: &'a Zp<M>) {
-        *self = *self / *rhs;
+impl <M: GetModule, I: RingInteger> DivAssign<&Zp<M, I>> for Zp<M, I> {
+    fn div_assign(&mut self, rhs: &Zp<M, I>) {
+        *self = self.clone() / rhs.clone();
     }
 }
 //=======================================================================================================================
-impl<M: GetModule> PartialEq for Zp<M> {
+impl <M: GetModule, I: RingInteger> PartialEq for Zp<M, I> {
     fn eq(&self, other: &Self) -> bool {
         self.value == other.value
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        self.value != other.value
-    }
 }
 //=======================================================================================================================
-impl <M: GetModule> Zero for Zp<M> {
+impl <M: GetModule, I: RingInteger> Zero for Zp<M, I> {
     fn zero() -> Self {
-        Zp { value: 0, _marker: PhantomData }
+        Zp { value: I::zero(), module: None, _marker: PhantomData }
     }
 
     fn is_zero(&self) -> bool {
-        self.value == 0
+        self.value.is_zero()
     }
 }
 
-impl <M: GetModule> One for Zp<M> {
+impl <M: GetModule, I: RingInteger> One for Zp<M, I> {
     fn one() -> Self {
-        Zp { value: 1, _marker: PhantomData }
+        Zp { value: I::one(), module: None, _marker: PhantomData }
     }
 }
 //=======================================================================================================================
-pub fn find_inv (a: &Integer, p: &UInteger) -> Option<Integer> {
-    let mut r0 = *p as Integer;
-    let mut r1 = *a;
-    let mut t0 = 0 as Integer;
-    let mut t1 = 1 as Integer;
-    while r1 != 0 {
-        let r2_div = r0 / r1;
-        let r2_rem = r0 % r1;
-        let t2 = t0 - r2_div * t1;
+pub fn find_inv <I: RingInteger> (a: &I, p: &UInteger) -> Option<I> {
+    let mut r0 = I::from_module(*p);
+    let mut r1 = a.clone();
+    let mut t0 = I::zero();
+    let mut t1 = I::one();
+    while !r1.is_zero() {
+        let r2_div = r0.clone() / r1.clone();
+        let r2_rem = r0 % r1.clone();
+        let t2 = t0 - r2_div * t1.clone();
 
         r0 = r1;
         r1 = r2_rem;
         t0 = t1;
         t1 = t2;
     }
-    if r0 == 1 || r0 == -1 { Some(r0 * t0) }
+    if r0.abs().is_one() { Some(r0 * t0) }
     else { None }
 }
 //=======================================================================================================================
+/// Selects the module of a `Zp` from the ring parameters and defines how residues modulo it are computed, for
+/// every ring integer type.
 pub trait GetModule {
-    fn get () -> UInteger;
+    fn get (params: &RingParams) -> UInteger;
+
+    #[inline(always)]
+    fn reduce <I: RingInteger> (value: I, module: UInteger) -> I {
+        reduce_mod(value, module)
+    }
+
+    #[inline(always)]
+    fn add <I: RingInteger> (a: I, b: I, module: UInteger) -> I {
+        Self::reduce(wide_rem::<I>(a.widen() + b.widen(), module), module)
+    }
+
+    #[inline(always)]
+    fn sub <I: RingInteger> (a: I, b: I, module: UInteger) -> I {
+        Self::reduce(wide_rem::<I>(a.widen() - b.widen(), module), module)
+    }
+
+    #[inline(always)]
+    fn mul <I: RingInteger> (a: I, b: I, module: UInteger) -> I {
+        Self::reduce(wide_rem::<I>(a.widen() * b.widen(), module), module)
+    }
+
+    /// Product in Z_module[x]/(reduction polynomial); the result is not yet passed through `reduce`.
+    #[inline(always)]
+    fn convolution <I: RingInteger> (a: &[I], b: &[I], n: usize, reduction: Reduction, module: UInteger) -> Vec<I> {
+        ring_convolution(a, b, n, reduction, module)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -210,27 +254,94 @@ pub struct ModP;
 #[derive(Clone, Copy)]
 pub struct ModQ;
 
+/// Same module as `ModQ`, specialised for q = 2^k: residues are reduced with the mask q - 1 instead of `%`, and
+/// products are computed with wrapping arithmetic (see `RingInteger::wrapping_convolution`).
 #[derive(Clone, Copy)]
-pub struct Mod2;
+pub struct ModQPow2;
 
 impl GetModule for ModP {
-    fn get () -> UInteger {
-        *P.get().unwrap()
+    fn get (params: &RingParams) -> UInteger {
+        params.p()
     }
 }
 
 impl GetModule for ModQ {
-    fn get () -> UInteger {
-        *Q.get().unwrap()
+    fn get (params: &RingParams) -> UInteger {
+        params.q()
     }
 }
 
-impl GetModule for Mod2 {
-    fn get () -> UInteger {
-        2
+impl GetModule for ModQPow2 {
+    fn get (params: &RingParams) -> UInteger {
+        assert!(params.q().is_power_of_two(), "ModQPow2: q = {} is not a power of two", params.q());
+        params.q()
+    }
+
+    #[inline(always)]
+    fn reduce <I: RingInteger> (value: I, module: UInteger) -> I {
+        mask_pow2(value, module)
+    }
+
+    #[inline(always)]
+    fn add <I: RingInteger> (a: I, b: I, module: UInteger) -> I {
+        mask_pow2_wide::<I>(a.widen() + b.widen(), module)
+    }
+
+    #[inline(always)]
+    fn sub <I: RingInteger> (a: I, b: I, module: UInteger) -> I {
+        mask_pow2_wide::<I>(a.widen() - b.widen(), module)
+    }
+
+    #[inline(always)]
+    fn mul <I: RingInteger> (a: I, b: I, module: UInteger) -> I {
+        mask_pow2_wide::<I>(a.widen() * b.widen(), module)
+    }
+
+    #[inline(always)]
+    fn convolution <I: RingInteger> (a: &[I], b: &[I], n: usize, reduction: Reduction, _module: UInteger) -> Vec<I> {
+        I::wrapping_convolution(a, b, n, reduction)
+    }
+}
+//=======================================================================================================================
+#[cfg(feature = "center-mod")]
+fn reduce_mod <I: RingInteger> (value: I, module: UInteger) -> I {
+    mod_center(value, module)
+}
+
+#[cfg(not(feature = "center-mod"))]
+fn reduce_mod <I: RingInteger> (value: I, module: UInteger) -> I {
+    let p = I::from_module(module);
+    if value >= p || value.is_negative() {
+        value.rem_euclid(&p)
+    }
+    else {
+        value
+    }
+}
+//=======================================================================================================================
+/// Remainder of a `Wide` intermediate result, so `a * b` does not overflow `I` before the reduction.
+#[inline(always)]
+fn wide_rem <I: RingInteger> (value: I::Wide, module: UInteger) -> I {
+    I::narrow(value.rem_euclid(&I::wide_module(module)))
+}
+//=======================================================================================================================
+/// The mask keeps the low bits of the two's complement, so negative values need no special case.
+#[inline(always)]
+fn mask_pow2 <I: RingInteger> (value: I, module: UInteger) -> I {
+    let value = value & I::from_module(module - 1);
+    if cfg!(feature = "center-mod") && value > I::from_module((module - 1) / 2) {
+        value - I::from_module(module)
+    }
+    else {
+        value
     }
 }
+
+#[inline(always)]
+fn mask_pow2_wide <I: RingInteger> (value: I::Wide, module: UInteger) -> I {
+    mask_pow2(I::narrow(value & I::wide_module(module - 1)), module)
+}
 //=======================================================================================================================
 pub trait Module: GetModule + Copy {}
 impl <T: GetModule + Copy> Module for T {}
-//=======================================================================================================================
\ No newline at end of file
+//=======================================================================================================================
@@ -0,0 +1,55 @@
+use crate::polyxnm1::UInteger;
+//=======================================================================================================================
+/// Parameters of one classic NTRUEncrypt run: ring (N, p, q) and the weights of f (df ones, df - 1 minus ones),
+/// g and r (dg and dr ones and minus ones).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NtruParams {
+    pub name: &'static str,
+    pub n: UInteger,
+    pub p: UInteger,
+    pub q: UInteger,
+    pub df: u16,
+    pub dg: u16,
+    pub dr: u16
+}
+//=======================================================================================================================
+const fn preset (name: &'static str, n: UInteger, q: UInteger, df: u16, dg: u16, dr: u16) -> NtruParams {
+    NtruParams { name, n, p: 3, q, df, dg, dr }
+}
+
+/// Toy sets of the classes: small enough to print and to reduce with full-block BKZ.
+pub const TOY_11: NtruParams = preset("toy-11", 11, 32, 4, 3, 3);
+pub const TOY_53: NtruParams = preset("toy-53", 53, 128, 4, 3, 4);
+
+/// IEEE 1363.1 (EESS #1) product-form-free sets; f is used directly instead of 1 + p*F.
+pub const EES401EP1: NtruParams = preset("ees401ep1", 401, 2048, 113, 133, 113);
+pub const EES449EP1: NtruParams = preset("ees449ep1", 449, 2048, 134, 149, 134);
+pub const EES677EP1: NtruParams = preset("ees677ep1", 677, 2048, 157, 225, 157);
+pub const EES1087EP2: NtruParams = preset("ees1087ep2", 1087, 2048, 120, 362, 120);
+
+/// Rings of the NIST round 3 NTRU KEM with fixed weights: g of HPS has q/8 - 2 nonzero coefficients, the uniform
+/// ternary polynomials (f and r, and g of HRSS) about 2N/3.
+pub const HPS2048509_SHAPE: NtruParams = preset("hps2048509-shape", 509, 2048, 170, 127, 170);
+pub const HPS2048677_SHAPE: NtruParams = preset("hps2048677-shape", 677, 2048, 226, 127, 226);
+pub const HPS4096821_SHAPE: NtruParams = preset("hps4096821-shape", 821, 4096, 274, 255, 274);
+pub const HRSS701_SHAPE: NtruParams = preset("hrss701-shape", 701, 8192, 234, 233, 233);
+
+/// Sets of the original NTRU paper (Hoffstein, Pipher, Silverman, 1998).
+pub const NTRU_107: NtruParams = preset("ntru-107", 107, 64, 15, 12, 5);
+pub const NTRU_167: NtruParams = preset("ntru-167", 167, 128, 61, 20, 18);
+pub const NTRU_263: NtruParams = preset("ntru-263", 263, 128, 50, 24, 16);
+pub const NTRU_503: NtruParams = preset("ntru-503", 503, 256, 216, 72, 55);
+
+pub const NTRU_PRESETS: [NtruParams; 14] = [
+    TOY_11, TOY_53,
+    EES401EP1, EES449EP1, EES677EP1, EES1087EP2,
+    HPS2048509_SHAPE, HPS2048677_SHAPE, HPS4096821_SHAPE, HRSS701_SHAPE,
+    NTRU_107, NTRU_167, NTRU_263, NTRU_503
+];
+//=======================================================================================================================
+impl NtruParams {
+    pub fn from_name (name: &str) -> Option<NtruParams> {
+        NTRU_PRESETS.iter().find(|params| params.name == name).copied()
+    }
+}
+//=======================================================================================================================
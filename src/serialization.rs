@@ -0,0 +1,361 @@
+use crate::ntru::PrivateKey;
+use crate::polyxnm1::{Integer, PolyXNm1, Reduction, RingParams, UInteger, zp::*};
+use crate::polyxnm1::service::{mod_center, read_bits, write_bits};
+use num_traits::{FromPrimitive, ToPrimitive};
+use std::{fmt, fs, io};
+
+const MAGIC: &[u8; 4] = b"NTRU";
+const VERSION: u8 = 1;
+/// magic, version, kind, N, p, q (64-bit big endian) and the reduction polynomial.
+const HEADER_BYTES: usize = 4 + 1 + 1 + 3 * 8 + 1;
+//=======================================================================================================================
+#[derive(Debug)]
+pub enum FormatError {
+    Io(io::Error),
+    /// Truncated or malformed file, or a file of another kind than expected.
+    Malformed(String)
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Io(error) => write!(f, "{}", error),
+            FormatError::Malformed(message) => write!(f, "malformed NTRU file: {}", message)
+        }
+    }
+}
+
+impl From<io::Error> for FormatError {
+    fn from(error: io::Error) -> Self {
+        FormatError::Io(error)
+    }
+}
+//=======================================================================================================================
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+    /// Header followed by the coefficients bit-packed to ceil(log2 q) (ceil(log2 p) for fp) bits each.
+    Binary,
+    /// `key = value` lines with the coefficients in decimal.
+    Text
+}
+//=======================================================================================================================
+/// Contents of a parameter, key or ciphertext file. Every file carries the parameter set it belongs to.
+pub enum NtruFile<Q: Module> {
+    Params(RingParams),
+    PublicKey(PolyXNm1<Q>),
+    /// f is stored mod q and read back centered, fp is stored mod p.
+    PrivateKey(PrivateKey),
+    Ciphertext(PolyXNm1<Q>)
+}
+//=======================================================================================================================
+impl <Q: Module> NtruFile<Q> {
+    pub fn params (&self) -> RingParams {
+        match self {
+            NtruFile::Params(params) => *params,
+            NtruFile::PublicKey(h) => *h.params(),
+            NtruFile::PrivateKey(key) => *key.fp.params(),
+            NtruFile::Ciphertext(e) => *e.params()
+        }
+    }
+
+    fn kind (&self) -> &'static str {
+        match self {
+            NtruFile::Params(_) => "params",
+            NtruFile::PublicKey(_) => "public-key",
+            NtruFile::PrivateKey(..) => "private-key",
+            NtruFile::Ciphertext(_) => "ciphertext"
+        }
+    }
+//=======================================================================================================================
+    pub fn save (&self, path: &str, format: FileFormat) -> Result<(), FormatError> {
+        match format {
+            FileFormat::Binary => fs::write(path, self.to_bytes())?,
+            FileFormat::Text => fs::write(path, self.to_text())?
+        }
+        Ok(())
+    }
+
+    /// The format is recognized by the magic bytes.
+    pub fn load (path: &str) -> Result<Self, FormatError> {
+        let bytes = fs::read(path)?;
+        if bytes.starts_with(MAGIC) {
+            NtruFile::from_bytes(&bytes)
+        }
+        else {
+            let text = String::from_utf8(bytes).map_err(|_| malformed("neither binary nor text"))?;
+            NtruFile::from_text(&text)
+        }
+    }
+//=======================================================================================================================
+    pub fn to_bytes (&self) -> Vec<u8> {
+        let params = self.params();
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(KINDS.iter().position(|&kind| kind == self.kind()).unwrap() as u8);
+        for value in [params.n(), params.p(), params.q()] {
+            bytes.extend(value.to_u64().expect("parameters beyond 64 bits").to_be_bytes());
+        }
+        bytes.push(REDUCTIONS.iter().position(|&(_, reduction)| reduction == params.reduction()).unwrap() as u8);
+
+        let (q, p) = (params.q(), params.p());
+        match self {
+            NtruFile::Params(_) => {},
+            NtruFile::PublicKey(a) | NtruFile::Ciphertext(a) => bytes.extend(pack(&a.to_integers(), q)),
+            NtruFile::PrivateKey(key) => {
+                bytes.extend(pack(&key.f, q));
+                bytes.extend(pack(&key.fp.to_integers(), p));
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes (bytes: &[u8]) -> Result<Self, FormatError> {
+        if bytes.len() < HEADER_BYTES || !bytes.starts_with(MAGIC) {
+            return Err(malformed("truncated header"));
+        }
+        if bytes[4] != VERSION {
+            return Err(malformed(&format!("unsupported version {}", bytes[4])));
+        }
+        let kind = *KINDS.get(bytes[5] as usize).ok_or_else(|| malformed("unknown kind"))?;
+
+        let value = |i: usize| u64::from_be_bytes(bytes[6 + 8 * i..14 + 8 * i].try_into().unwrap());
+        let reduction = REDUCTIONS.get(bytes[30] as usize).ok_or_else(|| malformed("unknown reduction polynomial"))?.1;
+        let params = checked_params(value(0), value(1), value(2), reduction)?;
+
+        let n = params.n() as usize;
+        let (q_bytes, p_bytes) = (packed_bytes(n, params.q()), packed_bytes(n, params.p()));
+        let payload = &bytes[HEADER_BYTES..];
+        let expected = match kind {
+            "params" => 0,
+            "public-key" | "ciphertext" => q_bytes,
+            _ => q_bytes + p_bytes
+        };
+        if payload.len() != expected {
+            return Err(malformed(&format!("{} payload of {} bytes, expected {}", kind, payload.len(), expected)));
+        }
+
+        Ok(match kind {
+            "params" => NtruFile::Params(params),
+            "public-key" => NtruFile::PublicKey(PolyXNm1::from_integers(&params, &unpack(payload, n, params.q()))),
+            "ciphertext" => NtruFile::Ciphertext(PolyXNm1::from_integers(&params, &unpack(payload, n, params.q()))),
+            _ => {
+                let f = unpack(&payload[..q_bytes], n, params.q()).into_iter().map(|x| mod_center(x, params.q())).collect();
+                let fp = PolyXNm1::from_integers(&params, &unpack(&payload[q_bytes..], n, params.p()));
+                NtruFile::PrivateKey(PrivateKey { f, fp })
+            }
+        })
+    }
+//=======================================================================================================================
+    pub fn to_text (&self) -> String {
+        let params = self.params();
+        let reduction = REDUCTIONS.iter().find(|&&(_, reduction)| reduction == params.reduction()).unwrap().0;
+        let mut text = format!("kind = {}\nn = {}\np = {}\nq = {}\nreduction = {}\n", self.kind(), params.n(), params.p(), params.q(), reduction);
+
+        let line = |name: &str, coeffs: &[Integer]| {
+            format!("{} = {}\n", name, coeffs.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" "))
+        };
+        match self {
+            NtruFile::Params(_) => {},
+            NtruFile::PublicKey(h) => text += &line("h", &h.to_integers()),
+            NtruFile::PrivateKey(key) => {
+                text += &line("f", &key.f);
+                text += &line("fp", &key.fp.to_integers());
+            }
+            NtruFile::Ciphertext(e) => text += &line("e", &e.to_integers())
+        }
+        text
+    }
+
+    pub fn from_text (text: &str) -> Result<Self, FormatError> {
+        let mut fields = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (key, value) = line.split_once('=').ok_or_else(|| malformed(&format!("expected `key = value`, got `{}`", line)))?;
+            fields.push((key.trim(), value.trim()));
+        }
+        let field = |name: &str| fields.iter().find(|&&(key, _)| key == name).map(|&(_, value)| value)
+            .ok_or_else(|| malformed(&format!("missing `{}`", name)));
+        let number = |name: &str| field(name)?.parse::<u64>().map_err(|_| malformed(&format!("invalid `{}`", name)));
+
+        let reduction_name = field("reduction")?;
+        let reduction = REDUCTIONS.iter().find(|&&(name, _)| name == reduction_name)
+            .ok_or_else(|| malformed(&format!("unknown reduction `{}`", reduction_name)))?.1;
+        let params = checked_params(number("n")?, number("p")?, number("q")?, reduction)?;
+
+        let poly = |name: &str| -> Result<Vec<Integer>, FormatError> {
+            let coeffs = field(name)?.split_whitespace().map(|x| x.parse::<Integer>()).collect::<Result<Vec<_>, _>>()
+                .map_err(|_| malformed(&format!("invalid coefficient of `{}`", name)))?;
+            if coeffs.len() != params.n() as usize {
+                return Err(malformed(&format!("`{}` has {} coefficients, expected {}", name, coeffs.len(), params.n())));
+            }
+            Ok(coeffs)
+        };
+
+        match field("kind")? {
+            "params" => Ok(NtruFile::Params(params)),
+            "public-key" => Ok(NtruFile::PublicKey(PolyXNm1::from_integers(&params, &poly("h")?))),
+            "private-key" => Ok(NtruFile::PrivateKey(PrivateKey { f: poly("f")?, fp: PolyXNm1::from_integers(&params, &poly("fp")?) })),
+            "ciphertext" => Ok(NtruFile::Ciphertext(PolyXNm1::from_integers(&params, &poly("e")?))),
+            kind => Err(malformed(&format!("unknown kind `{}`", kind)))
+        }
+    }
+}
+//=======================================================================================================================
+const KINDS: [&str; 4] = ["params", "public-key", "private-key", "ciphertext"];
+const REDUCTIONS: [(&str, Reduction); 3] = [("cyclic", Reduction::Cyclic), ("negacyclic", Reduction::Negacyclic), ("ntru-prime", Reduction::NtruPrime)];
+//=======================================================================================================================
+#[inline(always)]
+fn malformed (message: &str) -> FormatError {
+    FormatError::Malformed(message.to_string())
+}
+//=======================================================================================================================
+/// `RingParams::with_reduction` panics on invalid values; a file must give an error instead.
+fn checked_params (n: u64, p: u64, q: u64, reduction: Reduction) -> Result<RingParams, FormatError> {
+    let convert = |x: u64| UInteger::from_u64(x).ok_or_else(|| malformed(&format!("{} does not fit the integer backend", x)));
+    let (n, p, q) = (convert(n)?, convert(p)?, convert(q)?);
+    if p < 2 {
+        return Err(malformed(&format!("invalid parameter p = {}", p)));
+    }
+    RingParams::try_new(n, p, q, reduction).map_err(|error| malformed(&error.to_string()))
+}
+//=======================================================================================================================
+/// Bits of one coefficient mod m: ceil(log2 m).
+#[inline(always)]
+fn coeff_bits (module: UInteger) -> usize {
+    (module - 1).ilog2() as usize + 1
+}
+
+#[inline(always)]
+fn packed_bytes (n: usize, module: UInteger) -> usize {
+    (n * coeff_bits(module)).div_ceil(8)
+}
+//=======================================================================================================================
+fn pack (coeffs: &[Integer], module: UInteger) -> Vec<u8> {
+    let bits = coeff_bits(module);
+    let mut bytes = vec![0u8; packed_bytes(coeffs.len(), module)];
+    for (i, &x) in coeffs.iter().enumerate() {
+        write_bits(&mut bytes, bits * i, bits, x.rem_euclid(module as Integer) as u64);
+    }
+    bytes
+}
+
+fn unpack (bytes: &[u8], n: usize, module: UInteger) -> Vec<Integer> {
+    let bits = coeff_bits(module);
+    (0..n).map(|i| read_bits(bytes, bits * i, bits) as Integer).collect()
+}
+//=======================================================================================================================
+pub fn load_params (path: &str) -> Result<RingParams, FormatError> {
+    Ok(NtruFile::<ModQ>::load(path)?.params())
+}
+
+pub fn load_public_key <Q: Module> (path: &str) -> Result<PolyXNm1<Q>, FormatError> {
+    match NtruFile::load(path)? {
+        NtruFile::PublicKey(h) => Ok(h),
+        file => Err(malformed(&format!("{} is a {}, not a public key", path, file.kind())))
+    }
+}
+
+#[cfg_attr(feature = "time-measurement", allow(dead_code))]
+pub fn load_private_key <Q: Module> (path: &str) -> Result<PrivateKey, FormatError> {
+    match NtruFile::<Q>::load(path)? {
+        NtruFile::PrivateKey(key) => Ok(key),
+        file => Err(malformed(&format!("{} is a {}, not a private key", path, file.kind())))
+    }
+}
+
+pub fn load_ciphertext <Q: Module> (path: &str) -> Result<PolyXNm1<Q>, FormatError> {
+    match NtruFile::load(path)? {
+        NtruFile::Ciphertext(e) => Ok(e),
+        file => Err(malformed(&format!("{} is a {}, not a ciphertext", path, file.kind())))
+    }
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn private_key (params: &RingParams) -> PrivateKey {
+        PrivateKey {
+            f: vec![1, -1, 0, 1, 1, 0, -1, 0, 1, 0, 0],
+            fp: PolyXNm1::from_integers(params, &[2, 0, 1, 1, 2, 0, 0, 1, 2, 1, 0])
+        }
+    }
+
+    fn assert_round_trip (file: &NtruFile<ModQ>) {
+        for decoded in [NtruFile::<ModQ>::from_bytes(&file.to_bytes()).unwrap(), NtruFile::from_text(&file.to_text()).unwrap()] {
+            assert!(decoded.params() == file.params());
+            match (file, &decoded) {
+                (NtruFile::Params(_), NtruFile::Params(_)) => (),
+                (NtruFile::PublicKey(a), NtruFile::PublicKey(b)) | (NtruFile::Ciphertext(a), NtruFile::Ciphertext(b)) => assert!(a == b),
+                (NtruFile::PrivateKey(a), NtruFile::PrivateKey(b)) => assert!(a.f == b.f && a.fp == b.fp),
+                _ => panic!("decoded a {} instead of a {}", decoded.kind(), file.kind())
+            }
+        }
+    }
+
+    #[test]
+    fn binary_and_text_round_trips () {
+        for reduction in [Reduction::Cyclic, Reduction::Negacyclic] {
+            let params = RingParams::with_reduction(11, 3, 37, reduction);
+            let a = PolyXNm1::from_integers(&params, &[0, 36, 5, 18, 19, 1, 2, 30, 0, 7, 36]);
+            assert_round_trip(&NtruFile::Params(params));
+            assert_round_trip(&NtruFile::PublicKey(a.clone()));
+            assert_round_trip(&NtruFile::Ciphertext(a));
+            assert_round_trip(&NtruFile::PrivateKey(private_key(&params)));
+        }
+    }
+
+    #[test]
+    fn packed_sizes () {
+        let params = RingParams::new(11, 3, 32);
+        let h = PolyXNm1::<ModQ>::from_integers(&params, &[31; 11]);
+        assert_eq!(NtruFile::PublicKey(h).to_bytes().len(), HEADER_BYTES + (11 * 5usize).div_ceil(8));
+        assert_eq!(NtruFile::<ModQ>::PrivateKey(private_key(&params)).to_bytes().len(), HEADER_BYTES + 7 + 3);
+    }
+
+    #[test]
+    fn rejects_truncated_binary () {
+        let params = RingParams::new(11, 3, 32);
+        let bytes = NtruFile::<ModQ>::PrivateKey(private_key(&params)).to_bytes();
+        for len in [0, 3, HEADER_BYTES - 1, HEADER_BYTES, bytes.len() - 1] {
+            assert!(matches!(NtruFile::<ModQ>::from_bytes(&bytes[..len]), Err(FormatError::Malformed(_))), "length {}", len);
+        }
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(NtruFile::<ModQ>::from_bytes(&extended).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_binary () {
+        let params = RingParams::new(11, 3, 32);
+        let bytes = NtruFile::<ModQ>::Params(params).to_bytes();
+        let corrupt = |index: usize, value: u8| {
+            let mut bytes = bytes.clone();
+            bytes[index] = value;
+            NtruFile::<ModQ>::from_bytes(&bytes).is_err()
+        };
+        assert!(corrupt(0, b'X'));
+        assert!(corrupt(4, VERSION + 1));
+        assert!(corrupt(5, KINDS.len() as u8));
+        assert!(corrupt(30, REDUCTIONS.len() as u8));
+        // p = 2, q = 32 share a factor
+        assert!(corrupt(6 + 8 + 7, 2));
+    }
+
+    #[test]
+    fn rejects_malformed_text () {
+        let params = RingParams::new(11, 3, 32);
+        let text = NtruFile::<ModQ>::PublicKey(PolyXNm1::from_integers(&params, &[1; 11])).to_text();
+        let parse = |text: &str| NtruFile::<ModQ>::from_text(text).is_err();
+
+        assert!(!parse(&text));
+        assert!(parse(&text.replace("kind = public-key", "kind = secret")));
+        assert!(parse(&text.replace("reduction = cyclic", "reduction = x^N")));
+        assert!(parse(&text.replace("q = 32", "q = thirty-two")));
+        assert!(parse(&text.replace("q = 32", "q = 33")));
+        assert!(parse(&text.replace("h = 1 1", "h = 1")));
+        assert!(parse(&text.replace("h = 1", "h = one")));
+        assert!(parse(&text.replace("n = 11\n", "")));
+        assert!(parse(&text.replace("n = 11", "n 11")));
+    }
+}
+//=======================================================================================================================
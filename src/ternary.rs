@@ -0,0 +1,151 @@
+use crate::polyxnm1::Integer;
+#[cfg(feature = "sort-sampler")] use crate::polyxnm1::service::mod_center;
+use rand::Rng;
+use rand::seq::index;
+#[cfg(not(feature = "sort-sampler"))] use rand::seq::SliceRandom;
+//=======================================================================================================================
+/// Uniformly random element of T(d1, d2): exactly d1 coefficients 1, d2 coefficients -1 and the others 0.
+#[cfg(not(feature = "sort-sampler"))]
+#[inline(always)]
+pub fn sample_ternary (n: usize, d1: usize, d2: usize, rng: &mut impl Rng) -> Vec<Integer> {
+    sample_ternary_shuffle(n, d1, d2, rng)
+}
+
+#[cfg(feature = "sort-sampler")]
+#[inline(always)]
+pub fn sample_ternary (n: usize, d1: usize, d2: usize, rng: &mut impl Rng) -> Vec<Integer> {
+    sample_ternary_sorted(n, d1, d2, rng)
+}
+//=======================================================================================================================
+/// T(d1, d2) by a Fisher-Yates shuffle of d1 ones, d2 minus ones and n - d1 - d2 zeros.
+#[cfg(not(feature = "sort-sampler"))]
+pub fn sample_ternary_shuffle (n: usize, d1: usize, d2: usize, rng: &mut impl Rng) -> Vec<Integer> {
+    assert_weights(n, d1, d2);
+
+    let mut coeffs = vec![0 as Integer; n];
+    coeffs[..d1].fill(1);
+    coeffs[d1..d1 + d2].fill(-1);
+    coeffs.shuffle(rng);
+    coeffs
+}
+//=======================================================================================================================
+/// T(d1, d2) as in NTRU-HPS: random 30-bit keys carry the coefficient in their two low bits and are sorted by a
+/// network whose comparisons do not depend on the keys.
+#[cfg(feature = "sort-sampler")]
+pub fn sample_ternary_sorted (n: usize, d1: usize, d2: usize, rng: &mut impl Rng) -> Vec<Integer> {
+    assert_weights(n, d1, d2);
+
+    let mut keys: Vec<u32> = (0..n).map(|i| {
+        let label = if i < d1 { 1 } else if i < d1 + d2 { 2 } else { 0 };
+        (rng.next_u32() << 2) | label
+    }).collect();
+    constant_time_sort(&mut keys);
+
+    keys.into_iter().map(|key| mod_center((key & 3) as Integer, 3)).collect()
+}
+//=======================================================================================================================
+/// Exactly w nonzero coefficients, each 1 or -1 with probability 1/2.
+pub fn sample_fixed_weight (n: usize, w: usize, rng: &mut impl Rng) -> Vec<Integer> {
+    assert!(w <= n, "ternary sampler: weight {w} exceeds n = {n}");
+
+    let mut coeffs = vec![0 as Integer; n];
+    for i in index::sample(rng, n, w) {
+        coeffs[i] = if rng.random_bool(0.5) { 1 } else { -1 };
+    }
+    coeffs
+}
+//=======================================================================================================================
+/// Every coefficient uniform in {-1, 0, 1}.
+pub fn sample_iid_ternary (n: usize, rng: &mut impl Rng) -> Vec<Integer> {
+    (0..n).map(|_| rng.random_range(-1..=1)).collect()
+}
+//=======================================================================================================================
+#[inline(always)]
+fn assert_weights (n: usize, d1: usize, d2: usize) {
+    assert!(d1 + d2 <= n, "ternary sampler: {d1} ones and {d2} minus ones do not fit into n = {n} coefficients");
+}
+//=======================================================================================================================
+/// Sorting network of djbsort: the sequence of compare-exchanges depends on the length only.
+#[cfg(feature = "sort-sampler")]
+fn constant_time_sort (x: &mut [u32]) {
+    let n = x.len();
+    if n < 2 {
+        return;
+    }
+
+    let mut top = 1;
+    while top < n - top {
+        top += top;
+    }
+
+    let mut p = top;
+    while p > 0 {
+        for i in 0..n - p {
+            if i & p == 0 {
+                let (a, b) = minmax(x[i], x[i + p]);
+                (x[i], x[i + p]) = (a, b);
+            }
+        }
+
+        let mut i = 0;
+        let mut q = top;
+        while q > p {
+            while i < n - q {
+                if i & p == 0 {
+                    let mut a = x[i + p];
+                    let mut r = q;
+                    while r > p {
+                        (a, x[i + r]) = minmax(a, x[i + r]);
+                        r >>= 1;
+                    }
+                    x[i + p] = a;
+                }
+                i += 1;
+            }
+            q >>= 1;
+        }
+        p >>= 1;
+    }
+}
+//=======================================================================================================================
+/// (min(a, b), max(a, b)) without branches.
+#[cfg(feature = "sort-sampler")]
+#[inline(always)]
+fn minmax (a: u32, b: u32) -> (u32, u32) {
+    let mask = ((b as i64 - a as i64) >> 63) as u32;
+    let swap = (a ^ b) & mask;
+    (a ^ swap, b ^ swap)
+}
+//=======================================================================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn count (coeffs: &[Integer], value: Integer) -> usize {
+        coeffs.iter().filter(|&&x| x == value).count()
+    }
+
+    #[test]
+    fn samplers_have_exact_weights () {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for (n, d1, d2) in [(1, 0, 0), (1, 1, 0), (11, 4, 3), (107, 15, 14), (509, 127, 127), (701, 0, 701)] {
+            for _ in 0..10 {
+                let coeffs = sample_ternary(n, d1, d2, &mut rng);
+                assert_eq!((coeffs.len(), count(&coeffs, 1), count(&coeffs, -1)), (n, d1, d2), "T({}, {}), N = {}", d1, d2, n);
+
+                let coeffs = sample_fixed_weight(n, d1 + d2, &mut rng);
+                assert_eq!((coeffs.len(), n - count(&coeffs, 0)), (n, d1 + d2), "weight {}, N = {}", d1 + d2, n);
+                assert!(coeffs.iter().all(|x| x.abs() <= 1));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "do not fit")]
+    fn rejects_weights_above_n () {
+        sample_ternary(11, 6, 6, &mut ChaCha20Rng::seed_from_u64(1));
+    }
+}
+//=======================================================================================================================